@@ -1,5 +1,7 @@
 
-use dysql::{Content, fetch_all, Value};
+use std::sync::Arc;
+
+use dysql::{Content, SimpleTemplate, SqlBuffer, SqlDialect, Template, fetch_all, Value};
 use criterion::{Criterion, BenchmarkId, criterion_group, criterion_main};
 use sqlx::FromRow;
 
@@ -107,10 +109,57 @@ fn fetch_all_raw_sqlx(c: &mut Criterion) {
     );
 }
 
+static RENDER_SQL: &str = "select * from test_user where 1 = 1 \
+    {{#name}}and name = :name {{/name}}{{#age}}and age > :age {{/age}}{{?id_rng}}and id in (:id_rng) {{/id_rng}}order by id";
+
+/// 先渲染出命名 sql，再提取参数名并逐个从 dto 中获取参数值
+fn render_two_pass(template: &Arc<Template>, dto: &Option<UserDto>) -> usize {
+    let named_sql = dysql::gen_named_sql(template.clone(), dto).unwrap();
+    let mut buf = Vec::with_capacity(named_sql.len());
+    let param_names = dysql::extract_params_buf(&named_sql, &mut buf, SqlDialect::sqlite).unwrap();
+
+    let mut param_values = Vec::with_capacity(param_names.len());
+    for param_name in param_names {
+        param_values.push(SimpleTemplate::new(param_name).apply(dto.as_ref().unwrap()).unwrap());
+    }
+    buf.len() + param_values.len()
+}
+
+/// 渲染 sql 的同时写入占位符并收集参数值，不使用缓存
+fn render_one_pass(sql_buffer: &mut SqlBuffer, template: &Arc<Template>, dto: &Option<UserDto>) -> usize {
+    let (sql, param_values) = sql_buffer.render_sql_and_values(template, dto.as_ref().unwrap(), SqlDialect::sqlite).unwrap();
+    sql.len() + param_values.len()
+}
+
+/// 按 dto 所走的分支使用缓存的 sql，只获取参数值
+fn render_cached(sql_buffer: &mut SqlBuffer, template: &Arc<Template>, dto: &Option<UserDto>) -> usize {
+    let (sql, param_values) = sql_buffer.get_sql_and_values(0.into(), template.clone(), dto, SqlDialect::sqlite).unwrap();
+    sql.len() + param_values.len()
+}
+
+fn render_sql_and_params(c: &mut Criterion) {
+    let template = Arc::new(Template::new(RENDER_SQL).unwrap());
+    let dto = Some(UserDto::new(None, Some("a1".to_owned()), Some(10), Some(vec![1, 2, 3, 4, 5])));
+    // 展开了 IN 列表的结果不缓存，这里不使用 id_rng；两次遍历的方式也不展开 IN 列表
+    let cacheable_dto = Some(UserDto::new(None, Some("a1".to_owned()), Some(10), None));
+    assert_eq!(render_two_pass(&template, &cacheable_dto), render_one_pass(&mut SqlBuffer::default(), &template, &cacheable_dto));
+    assert_eq!(render_one_pass(&mut SqlBuffer::default(), &template, &cacheable_dto), render_cached(&mut SqlBuffer::default(), &template, &cacheable_dto));
+
+    let mut group = c.benchmark_group("render sql and params");
+    group.bench_function("two pass", |b| b.iter(|| render_two_pass(&template, &dto)));
+    group.bench_function("one pass", |b| b.iter(|| render_one_pass(&mut SqlBuffer::default(), &template, &dto)));
+    let mut sql_buffer = SqlBuffer::default();
+    group.bench_function("one pass reused buffer", |b| b.iter(|| render_one_pass(&mut sql_buffer, &template, &dto)));
+    group.bench_function("one pass without cache", |b| b.iter(|| render_one_pass(&mut sql_buffer, &template, &cacheable_dto)));
+    group.bench_function("cached", |b| b.iter(|| render_cached(&mut sql_buffer, &template, &cacheable_dto)));
+    group.finish();
+}
+
 criterion_group!(
     benches,
     fetch_all_dysql_sqlx,
-    fetch_all_raw_sqlx
+    fetch_all_raw_sqlx,
+    render_sql_and_params
 );
 criterion_main!(benches);
 
@@ -2,36 +2,17 @@
 #[macro_export]
 macro_rules! impl_rbatis_adapter_fetch_all {
     () => {
-        pub async fn dy_fetch_all<E, D, U>(self, executor: &E, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
+        pub async fn dy_fetch_all<E, D, U>(self, executor: &E, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
             -> Result<Vec<U>, crate::DySqlError>
         where 
             E: rbatis::executor::Executor,
             D: dysql_tpl::Content + Send + Sync,
             U: serde::de::DeserializeOwned,
         {
-            let named_sql = crate::gen_named_sql(named_template, &dto)?;
-                    
-            let mut buf = Vec::<u8>::with_capacity(named_sql.len());
-            let sql_and_params = crate::extract_params_buf(&named_sql, &mut buf, self.dialect);
-            let sql = unsafe{std::str::from_utf8_unchecked(&buf)};
-            let param_names = match sql_and_params {
-                Ok(val) => val,
-                Err(e) => Err(
-                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
-                )?,
-            };
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, self.dialect)?;
 
-            let mut param_values : Vec<rbs::Value> = Vec::with_capacity(param_names.len());
-            if let Some(dto) = &dto {
-                for param_name in &param_names {
-                    let stpl = dysql_tpl::SimpleTemplate::new(param_name);
-                    
-                    let param_value = stpl
-                        .apply(dto)
-                        .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, Some(e), None)))?;
-                    param_values.push(crate::simple_2_value(param_value));
-                }
-            }
+            let param_values: Vec<rbs::Value> = param_values.drain(..).map(crate::simple_2_value).collect();
 
             let rst = executor
                 .query(&sql, param_values)
@@ -48,39 +29,58 @@ macro_rules! impl_rbatis_adapter_fetch_all {
     };
 }
 
+#[macro_export]
+macro_rules! impl_rbatis_adapter_fetch_stream {
+    () => {
+        /// rbatis 的 Executor 没有提供游标查询，结果集会被一次性读取，然后在 poll 时逐行反序列化
+        pub fn dy_fetch_stream<'e, E, D, U>(self, executor: &'e E, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
+            -> futures_core::stream::BoxStream<'e, Result<U, crate::DySqlError>>
+        where 
+            E: rbatis::executor::Executor,
+            D: dysql_tpl::Content + Send + Sync + 'e,
+            U: serde::de::DeserializeOwned + Send + 'e,
+        {
+            Box::pin(async_stream::try_stream! {
+                let mut sql_buffer = crate::SqlBuffer::default();
+                let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, self.dialect)?;
+
+                let param_values: Vec<rbs::Value> = param_values.drain(..).map(crate::simple_2_value).collect();
+
+                let rst = executor
+                    .query(&sql, param_values)
+                    .await
+                    .map_err(|e| 
+                        crate::DySqlError(crate::ErrorInner::new(crate::Kind::QueryError, Some(e.into()), None))
+                    )?;
+
+                let rows = match rst {
+                    rbs::Value::Array(rows) => rows,
+                    rst => vec![rst],
+                };
+                for row in rows {
+                    let row = rbs::from_value::<U>(row)
+                        .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::ObjectMappingError, Some(e.into()), None)))?;
+                    yield row;
+                }
+            })
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! impl_rbatis_adapter_fetch_one {
     () => {
-        pub async fn dy_fetch_one<E, D, U>(self, executor: &E, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
+        pub async fn dy_fetch_one<E, D, U>(self, executor: &E, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
             -> Result<U, crate::DySqlError>
         where 
             E: rbatis::executor::Executor,
             D: dysql_tpl::Content + Send + Sync,
             U: serde::de::DeserializeOwned,
         {
-            let named_sql = crate::gen_named_sql(named_template, &dto)?;
-                
-            let mut buf = Vec::<u8>::with_capacity(named_sql.len());
-            let sql_and_params = crate::extract_params_buf(&named_sql, &mut buf, self.dialect);
-            let sql = unsafe{std::str::from_utf8_unchecked(&buf)};
-            let param_names = match sql_and_params {
-                Ok(val) => val,
-                Err(e) => Err(
-                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
-                )?,
-            };
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, self.dialect)?;
 
-            let mut param_values : Vec<rbs::Value> = Vec::with_capacity(param_names.len());
-            if let Some(dto) = &dto {
-                for param_name in &param_names {
-                    let stpl = dysql_tpl::SimpleTemplate::new(param_name);
-                    
-                    let param_value = stpl
-                        .apply(dto)
-                        .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, Some(e), None)))?;
-                    param_values.push(crate::simple_2_value(param_value));
-                }
-            }
+            let param_values: Vec<rbs::Value> = param_values.drain(..).map(crate::simple_2_value).collect();
 
             let rst = executor
                 .query(&sql, param_values)
@@ -97,39 +97,51 @@ macro_rules! impl_rbatis_adapter_fetch_one {
     };
 }
 
+#[macro_export]
+macro_rules! impl_rbatis_adapter_fetch_optional {
+    () => {
+        pub async fn dy_fetch_optional<E, D, U>(self, executor: &E, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
+            -> Result<Option<U>, crate::DySqlError>
+        where 
+            E: rbatis::executor::Executor,
+            D: dysql_tpl::Content + Send + Sync,
+            U: serde::de::DeserializeOwned,
+        {
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, self.dialect)?;
+
+            let param_values: Vec<rbs::Value> = param_values.drain(..).map(crate::simple_2_value).collect();
+
+            let rst = executor
+                .query(&sql, param_values)
+                .await
+                .map_err(|e| 
+                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::QueryError, Some(e.into()), None))
+                )?;
+
+            // 解码为 Vec 后取第一条记录，没有记录时返回 None
+            let rst: Vec<U> = rbatis::decode(rst)
+                .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::ObjectMappingError, Some(e.into()), None)))?;
+
+            Ok(rst.into_iter().next())
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! impl_rbatis_adapter_fetch_scalar {
     () => {
-        pub async fn dy_fetch_scalar<E, D, U>(self, executor: &E, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
+        pub async fn dy_fetch_scalar<E, D, U>(self, executor: &E, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
             -> Result<U, crate::DySqlError>
         where 
             E: rbatis::executor::Executor,
             D: dysql_tpl::Content + Send + Sync,
             U: serde::de::DeserializeOwned,
         {
-            let named_sql = crate::gen_named_sql(named_template, &dto)?;
-                
-            let mut buf = Vec::<u8>::with_capacity(named_sql.len());
-            let sql_and_params = crate::extract_params_buf(&named_sql, &mut buf, self.dialect);
-            let sql = unsafe{std::str::from_utf8_unchecked(&buf)};
-            let param_names = match sql_and_params {
-                Ok(val) => val,
-                Err(e) => Err(
-                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
-                )?,
-            };
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, self.dialect)?;
 
-            let mut param_values : Vec<rbs::Value> = Vec::with_capacity(param_names.len());
-            if let Some(dto) = &dto {
-                for param_name in &param_names {
-                    let stpl = dysql_tpl::SimpleTemplate::new(param_name);
-                    
-                    let param_value = stpl
-                        .apply(dto)
-                        .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, Some(e), None)))?;
-                    param_values.push(crate::simple_2_value(param_value));
-                }
-            }
+            let param_values: Vec<rbs::Value> = param_values.drain(..).map(crate::simple_2_value).collect();
 
             let rst = executor
                 .query(&sql, param_values)
@@ -149,35 +161,16 @@ macro_rules! impl_rbatis_adapter_fetch_scalar {
 #[macro_export]
 macro_rules! impl_rbatis_adapter_execute {
     () => {
-        pub async fn dy_execute<E, D>(self, executor: &E, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
+        pub async fn dy_execute<E, D>(self, executor: &E, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
             -> Result<u64, crate::DySqlError>
         where 
             E: rbatis::executor::Executor,
             D: dysql_tpl::Content + Send + Sync,
         {
-            let named_sql = crate::gen_named_sql(named_template, &dto)?;
-                
-            let mut buf = Vec::<u8>::with_capacity(named_sql.len());
-            let sql_and_params = crate::extract_params_buf(&named_sql, &mut buf, self.dialect);
-            let sql = unsafe{std::str::from_utf8_unchecked(&buf)};
-            let param_names = match sql_and_params {
-                Ok(val) => val,
-                Err(e) => Err(
-                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
-                )?,
-            };
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, self.dialect)?;
 
-            let mut param_values : Vec<rbs::Value> = Vec::with_capacity(param_names.len());
-            if let Some(dto) = &dto {
-                for param_name in &param_names {
-                    let stpl = dysql_tpl::SimpleTemplate::new(param_name);
-                    
-                    let param_value = stpl
-                        .apply(dto)
-                        .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, Some(e), None)))?;
-                    param_values.push(crate::simple_2_value(param_value));
-                }
-            }
+            let param_values: Vec<rbs::Value> = param_values.drain(..).map(crate::simple_2_value).collect();
 
             let rst = executor
                 .exec(&sql, param_values)
@@ -191,10 +184,44 @@ macro_rules! impl_rbatis_adapter_execute {
     }
 }
 
+/// 用同一个 sql 模板逐个执行多个 dto，渲染结果相同的 sql 只解析一次参数
+#[macro_export]
+macro_rules! impl_rbatis_adapter_execute_batch {
+    () => {
+        pub async fn dy_execute_batch<E, D, I>(self, executor: &E, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dtos: I)
+            -> Result<Vec<u64>, crate::DySqlError>
+        where 
+            E: rbatis::executor::Executor,
+            D: dysql_tpl::Content + Send + Sync,
+            I: IntoIterator<Item = D> + Send,
+            I::IntoIter: Send,
+        {
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let mut af_rows = Vec::new();
+            for dto in dtos {
+                let dto = Some(dto);
+                let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template.clone(), &dto, self.dialect)?;
+                let param_values: Vec<rbs::Value> = param_values.drain(..).map(crate::simple_2_value).collect();
+
+                let rst = executor
+                    .exec(sql, param_values)
+                    .await
+                    .map_err(|e| 
+                        crate::DySqlError(crate::ErrorInner::new(crate::Kind::QueryError, Some(e.into()), None))
+                    )?;
+
+                af_rows.push(rst.rows_affected);
+            }
+
+            Ok(af_rows)
+        }
+    }
+}
+
 #[macro_export]
 macro_rules! impl_rbatis_adapter_page_count {
     () => {
-        pub async fn dy_page_count<E, D, U>(self, executor: &E, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
+        pub async fn dy_page_count<E, D, U>(self, executor: &E, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
             -> Result<U, crate::DySqlError>
         where 
             E: rbatis::executor::Executor,
@@ -203,19 +230,8 @@ macro_rules! impl_rbatis_adapter_page_count {
         {
             use std::io::Write;
 
-            let named_sql = crate::gen_named_sql(named_template, &dto)?;
-
-            println!("sql: {}", named_sql);
-                
-            let mut buf = Vec::<u8>::with_capacity(named_sql.len());
-            let sql_and_params = crate::extract_params_buf(&named_sql, &mut buf, self.dialect);
-            let sql = unsafe{std::str::from_utf8_unchecked(&buf)};
-            let param_names = match sql_and_params {
-                Ok(val) => val,
-                Err(e) => Err(
-                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
-                )?,
-            };
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, self.dialect)?;
 
             // count sql
             let buffer_size = sql.len() + 200;
@@ -225,17 +241,7 @@ macro_rules! impl_rbatis_adapter_page_count {
                 std::str::from_utf8(&sql_buf).unwrap()
             };
 
-            let mut param_values : Vec<rbs::Value> = Vec::with_capacity(param_names.len());
-            if let Some(dto) = &dto {
-                for param_name in &param_names {
-                    let stpl = dysql_tpl::SimpleTemplate::new(param_name);
-                    
-                    let param_value = stpl
-                        .apply(dto)
-                        .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, Some(e), None)))?;
-                    param_values.push(crate::simple_2_value(param_value));
-                }
-            }
+            let param_values: Vec<rbs::Value> = param_values.drain(..).map(crate::simple_2_value).collect();
 
             let rst = executor
                 .query(&count_sql, param_values)
@@ -255,7 +261,7 @@ macro_rules! impl_rbatis_adapter_page_count {
 #[macro_export]
 macro_rules! impl_rbatis_adapter_page_all {
     () => {
-        pub async fn dy_page_all<E, D, U>(self, executor: &E, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, page_dto: &crate::PageDto<D>) 
+        pub async fn dy_page_all<E, D, U>(self, executor: &E, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, page_dto: &crate::PageDto<D>) 
             -> Result<crate::Pagination<U>, crate::DySqlError>
         where 
             E: rbatis::executor::Executor,
@@ -264,17 +270,8 @@ macro_rules! impl_rbatis_adapter_page_all {
         {
             use std::io::Write;
 
-            let named_sql = crate::gen_named_sql(named_template, &Some(page_dto))?;
-            
-            let mut buf = Vec::<u8>::with_capacity(named_sql.len());
-            let sql_and_params = crate::extract_params_buf(&named_sql, &mut buf, self.dialect);
-            let sql = unsafe{std::str::from_utf8_unchecked(&buf)};
-            let param_names = match sql_and_params {
-                Ok(val) => val,
-                Err(e) => Err(
-                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
-                )?,
-            };
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &Some(page_dto), self.dialect)?;
 
             let buffer_size = sql.len() + 200;
             let mut sql_buf = Vec::<u8>::with_capacity(buffer_size);
@@ -293,15 +290,7 @@ macro_rules! impl_rbatis_adapter_page_all {
                     )?
             };
 
-            let mut param_values : Vec<rbs::Value> = Vec::with_capacity(param_names.len());
-            for param_name in &param_names {
-                let stpl = dysql_tpl::SimpleTemplate::new(param_name);
-                
-                let param_value = stpl
-                    .apply(page_dto)
-                    .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, Some(e), None)))?;
-                param_values.push(crate::simple_2_value(param_value));
-            }
+            let param_values: Vec<rbs::Value> = param_values.drain(..).map(crate::simple_2_value).collect();
 
             let rst = executor
                 .query(&sql, param_values)
@@ -318,4 +307,33 @@ macro_rules! impl_rbatis_adapter_page_all {
             Ok(pg_data)
         }
     };
-}
\ No newline at end of file
+}
+#[macro_export]
+macro_rules! impl_rbatis_adapter_cursor_page_all {
+    () => {
+        pub async fn dy_cursor_page_all<E, D, U>(self, executor: &E, named_template: std::sync::Arc<dysql_tpl::Template>, cursor_dto: &crate::CursorDto<D>) 
+            -> Result<crate::CursorPagination<U>, crate::DySqlError>
+        where 
+            E: rbatis::executor::Executor,
+            D: dysql_tpl::Content + Send + Sync,
+            U: serde::de::DeserializeOwned + dysql_tpl::Content,
+        {
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_cursor_sql_and_values(named_template, cursor_dto, self.dialect)?;
+
+            let param_values: Vec<rbs::Value> = param_values.drain(..).map(crate::simple_2_value).collect();
+
+            let rst = executor
+                .query(sql, param_values)
+                .await
+                .map_err(|e| 
+                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::QueryError, Some(e.into()), None))
+                )?;
+
+            let rst = rbatis::decode(rst)
+                .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::ObjectMappingError, Some(e.into()), None)))?;
+
+            crate::CursorPagination::from_dto(cursor_dto, rst)
+        }
+    };
+}
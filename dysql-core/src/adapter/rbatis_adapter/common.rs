@@ -33,6 +33,7 @@ pub fn simple_2_value(simple_value: SimpleValue) -> Value {
         SimpleValue::t_str(v) => Value::String(v.as_str().unwrap().to_owned()),
         SimpleValue::t_String(v) => Value::String(v.as_string().unwrap().clone()),
         SimpleValue::None(_) => Value::Null,
+        SimpleValue::t_list(v) => Value::Array(v.into_iter().map(simple_2_value).collect()),
         _ => panic!("{:?} type not support", simple_value),
     }
 }
@@ -42,34 +43,57 @@ pub trait RbatisExecutorAdatper
     fn get_dialect(&self) -> SqlDialect;
 
     /// 查询并返回多个指定类型的对象
-    async fn dy_fetch_all<D, U>(self, template_id: u64, named_template: Arc<Template>, dto: Option<D>)
+    async fn dy_fetch_all<D, U>(self, template_id: crate::TemplateId, named_template: Arc<Template>, dto: Option<D>)
         -> Result<Vec<U>, DySqlError>
     where 
         D: Content + Send + Sync,
         U: DeserializeOwned;
 
+    /// 查询并以 Stream 的方式逐行返回指定类型的对象
+    fn dy_fetch_stream<'e, D, U>(self, template_id: crate::TemplateId, named_template: Arc<Template>, dto: Option<D>)
+        -> futures_core::stream::BoxStream<'e, Result<U, DySqlError>>
+    where 
+        Self: 'e,
+        D: Content + Send + Sync + 'e,
+        U: DeserializeOwned + Send + 'e;
+
     /// 查询并返回一个指定类型的对象
-    async fn dy_fetch_one<D, U>(self, template_id: u64, named_template: Arc<Template>, dto: Option<D>)
+    async fn dy_fetch_one<D, U>(self, template_id: crate::TemplateId, named_template: Arc<Template>, dto: Option<D>)
         -> Result<U, DySqlError>
     where 
         D: Content + Send + Sync,
         U: DeserializeOwned;
 
+    /// 查询并返回一个指定类型的对象，没有记录时返回 None
+    async fn dy_fetch_optional<D, U>(self, template_id: crate::TemplateId, named_template: Arc<Template>, dto: Option<D>)
+        -> Result<Option<U>, DySqlError>
+    where 
+        D: Content + Send + Sync,
+        U: DeserializeOwned;
+
     /// 查询并返回一个指定类型的单值
-    async fn dy_fetch_scalar<D, U>(self, template_id: u64, named_template: Arc<Template>, dto: Option<D>)
+    async fn dy_fetch_scalar<D, U>(self, template_id: crate::TemplateId, named_template: Arc<Template>, dto: Option<D>)
         -> Result<U, DySqlError>
     where 
         D: Content + Send + Sync,
         U: DeserializeOwned;
 
     /// 执行一条sql命令并返回受其影响的记录数
-    async fn dy_execute<D>(self, template_id: u64, named_template: Arc<Template>, dto: Option<D>)
+    async fn dy_execute<D>(self, template_id: crate::TemplateId, named_template: Arc<Template>, dto: Option<D>)
         -> Result<u64, DySqlError>
     where 
         D: Content + Send + Sync;
 
+    /// 在调用者的连接 (或事务) 上用同一个 sql 模板逐个执行多个 dto，返回每个 dto 影响的记录数
+    async fn dy_execute_batch<D, I>(self, template_id: crate::TemplateId, named_template: Arc<Template>, dtos: I)
+        -> Result<Vec<u64>, DySqlError>
+    where 
+        D: Content + Send + Sync,
+        I: IntoIterator<Item = D> + Send,
+        I::IntoIter: Send;
+
     /// 新增一条记录
-    async fn dy_insert<D, U>(self, template_id: u64, named_template: Arc<Template>, dto: Option<D>)
+    async fn dy_insert<D, U>(self, template_id: crate::TemplateId, named_template: Arc<Template>, dto: Option<D>)
         -> Result<Option<U>, DySqlError>
     where 
         D: Content + Send + Sync,
@@ -82,23 +106,30 @@ pub trait RbatisExecutorAdatper
         U: serde::de::DeserializeOwned;
 
     /// 用于在分页查询中获取符合条件的总记录数
-    async fn dy_page_count<D, U>(self, template_id: u64, named_template: Arc<Template>, dto: Option<D>)
+    async fn dy_page_count<D, U>(self, template_id: crate::TemplateId, named_template: Arc<Template>, dto: Option<D>)
         -> Result<U, DySqlError>
     where 
         D: Content + Send + Sync,
         U: DeserializeOwned;
 
     /// 用返回分页查询中获取符合条件的结果
-    async fn dy_page_all<D, U>(self, template_id: u64, named_template: Arc<Template>, page_dto: &crate::PageDto<D>)
+    async fn dy_page_all<D, U>(self, template_id: crate::TemplateId, named_template: Arc<Template>, page_dto: &crate::PageDto<D>)
         -> Result<Pagination<U>, DySqlError>
     where 
         D: Content + Send + Sync,
         U: DeserializeOwned;
+
+    /// 游标分页查询，返回一页结果及下一页的游标
+    async fn dy_cursor_page_all<D, U>(self, named_template: Arc<Template>, cursor_dto: &crate::CursorDto<D>)
+        -> Result<crate::CursorPagination<U>, DySqlError>
+    where 
+        D: Content + Send + Sync,
+        U: DeserializeOwned + Content;
 }
 
 macro_rules! impl_rbatis_adapter_fetch_all_0 {
     () => {
-        async fn dy_fetch_all<D, U>(self, template_id: u64, named_template: Arc<Template>, dto: Option<D>)
+        async fn dy_fetch_all<D, U>(self, template_id: crate::TemplateId, named_template: Arc<Template>, dto: Option<D>)
             -> Result<Vec<U>, DySqlError>
         where 
             D: Content + Send + Sync,
@@ -118,9 +149,32 @@ macro_rules! impl_rbatis_adapter_fetch_all_0 {
     };
 }
 
+macro_rules! impl_rbatis_adapter_fetch_stream_0 {
+    () => {
+        fn dy_fetch_stream<'e, D, U>(self, template_id: crate::TemplateId, named_template: Arc<Template>, dto: Option<D>)
+            -> futures_core::stream::BoxStream<'e, Result<U, DySqlError>>
+        where 
+            Self: 'e,
+            D: Content + Send + Sync + 'e,
+            U: DeserializeOwned + Send + 'e,
+        {
+            use SqlDialect::*;
+            match self.get_dialect() {
+                #[cfg(feature = "rbatis-pg")]
+                postgres => RbatisPostgresAdapter::new(postgres).dy_fetch_stream(self, template_id, named_template, dto),
+                #[cfg(feature = "rbatis-mysql")]
+                mysql => RbatisMysqlAdapter::new(mysql).dy_fetch_stream(self, template_id, named_template, dto),
+                #[cfg(feature = "rbatis-sqlite")]
+                sqlite => RbatisSqliteAdapter::new(sqlite).dy_fetch_stream(self, template_id, named_template, dto),
+                _ => panic!("{:?} dialect not support", self.get_dialect()),
+            }
+        }
+    };
+}
+
 macro_rules! impl_rbatis_adapter_fetch_one_0 {
     () => {
-        async fn dy_fetch_one<D, U>(self, template_id: u64, named_template: Arc<Template>, dto: Option<D>)
+        async fn dy_fetch_one<D, U>(self, template_id: crate::TemplateId, named_template: Arc<Template>, dto: Option<D>)
         -> Result<U, DySqlError>
         where 
             D: Content + Send + Sync,
@@ -140,9 +194,31 @@ macro_rules! impl_rbatis_adapter_fetch_one_0 {
     };
 }
 
+macro_rules! impl_rbatis_adapter_fetch_optional_0 {
+    () => {
+        async fn dy_fetch_optional<D, U>(self, template_id: crate::TemplateId, named_template: Arc<Template>, dto: Option<D>)
+        -> Result<Option<U>, DySqlError>
+        where 
+            D: Content + Send + Sync,
+            U: DeserializeOwned,
+        {
+            use SqlDialect::*;
+            match self.get_dialect() {
+                #[cfg(feature = "rbatis-pg")]
+                postgres => RbatisPostgresAdapter::new(postgres).dy_fetch_optional(self, template_id, named_template, dto).await,
+                #[cfg(feature = "rbatis-mysql")]
+                mysql => RbatisMysqlAdapter::new(mysql).dy_fetch_optional(self, template_id, named_template, dto).await,
+                #[cfg(feature = "rbatis-sqlite")]
+                sqlite => RbatisSqliteAdapter::new(sqlite).dy_fetch_optional(self, template_id, named_template, dto).await,
+                _ => panic!("{:?} dialect not support", self.get_dialect()),
+            }
+        }
+    };
+}
+
 macro_rules! impl_rbatis_adapter_fetch_scalar_0 {
     () => {
-        async fn dy_fetch_scalar<D, U>(self, template_id: u64, named_template: Arc<Template>, dto: Option<D>)
+        async fn dy_fetch_scalar<D, U>(self, template_id: crate::TemplateId, named_template: Arc<Template>, dto: Option<D>)
         -> Result<U, DySqlError>
         where 
             D: Content + Send + Sync,
@@ -164,7 +240,7 @@ macro_rules! impl_rbatis_adapter_fetch_scalar_0 {
 
 macro_rules! impl_rbatis_adapter_fetch_execute_0 {
     () => {
-        async fn dy_execute<D>(self, template_id: u64, named_template: Arc<Template>, dto: Option<D>)
+        async fn dy_execute<D>(self, template_id: crate::TemplateId, named_template: Arc<Template>, dto: Option<D>)
             -> Result<u64, DySqlError>
         where 
             D: Content + Send + Sync,
@@ -183,9 +259,32 @@ macro_rules! impl_rbatis_adapter_fetch_execute_0 {
     };
 }
 
+macro_rules! impl_rbatis_adapter_execute_batch_0 {
+    () => {
+        async fn dy_execute_batch<D, I>(self, template_id: crate::TemplateId, named_template: Arc<Template>, dtos: I)
+            -> Result<Vec<u64>, DySqlError>
+        where 
+            D: Content + Send + Sync,
+            I: IntoIterator<Item = D> + Send,
+            I::IntoIter: Send,
+        {
+            use SqlDialect::*;
+            match self.get_dialect() {
+                #[cfg(feature = "rbatis-pg")]
+                postgres => RbatisPostgresAdapter::new(postgres).dy_execute_batch(self, template_id, named_template, dtos).await,
+                #[cfg(feature = "rbatis-mysql")]
+                mysql => RbatisMysqlAdapter::new(mysql).dy_execute_batch(self, template_id, named_template, dtos).await,
+                #[cfg(feature = "rbatis-sqlite")]
+                sqlite => RbatisSqliteAdapter::new(sqlite).dy_execute_batch(self, template_id, named_template, dtos).await,
+                _ => panic!("{:?} dialect not support", self.get_dialect()),
+            }
+        }
+    };
+}
+
 macro_rules! impl_rbatis_adapter_insert_0 {
     () => {
-        async fn dy_insert<D, U>(self, template_id: u64, named_template: Arc<Template>, dto: Option<D>)
+        async fn dy_insert<D, U>(self, template_id: crate::TemplateId, named_template: Arc<Template>, dto: Option<D>)
             -> Result<Option<U>, DySqlError>
         where 
             D: Content + Send + Sync,
@@ -228,7 +327,7 @@ macro_rules! impl_rbatis_adapter_fetch_insert_id_0 {
 
 macro_rules! impl_rbatis_adapter_fetch_page_count_0 {
     () => {
-        async fn dy_page_count<D, U>(self, template_id: u64, named_template: Arc<Template>, dto: Option<D>)
+        async fn dy_page_count<D, U>(self, template_id: crate::TemplateId, named_template: Arc<Template>, dto: Option<D>)
             -> Result<U, DySqlError>
         where 
             D: Content + Send + Sync,
@@ -250,7 +349,7 @@ macro_rules! impl_rbatis_adapter_fetch_page_count_0 {
 
 macro_rules! impl_rbatis_adapter_fetch_page_all_0 {
     () => {
-        async fn dy_page_all<D, U>(self, template_id: u64, named_template: Arc<Template>, page_dto: &crate::PageDto<D>)
+        async fn dy_page_all<D, U>(self, template_id: crate::TemplateId, named_template: Arc<Template>, page_dto: &crate::PageDto<D>)
             -> Result<Pagination<U>, DySqlError>
         where 
             D: Content + Send + Sync,
@@ -270,6 +369,28 @@ macro_rules! impl_rbatis_adapter_fetch_page_all_0 {
     };
 }
 
+macro_rules! impl_rbatis_adapter_fetch_cursor_page_all_0 {
+    () => {
+        async fn dy_cursor_page_all<D, U>(self, named_template: Arc<Template>, cursor_dto: &crate::CursorDto<D>)
+            -> Result<crate::CursorPagination<U>, DySqlError>
+        where 
+            D: Content + Send + Sync,
+            U: DeserializeOwned + Content,
+        {
+            use SqlDialect::*;
+            match self.get_dialect() {
+                #[cfg(feature = "rbatis-pg")]
+                postgres => RbatisPostgresAdapter::new(postgres).dy_cursor_page_all(self, named_template, cursor_dto).await,
+                #[cfg(feature = "rbatis-mysql")]
+                mysql => RbatisMysqlAdapter::new(mysql).dy_cursor_page_all(self, named_template, cursor_dto).await,
+                #[cfg(feature = "rbatis-sqlite")]
+                sqlite => RbatisSqliteAdapter::new(sqlite).dy_cursor_page_all(self, named_template, cursor_dto).await,
+                _ => panic!("{:?} dialect not support", self.get_dialect()),
+            }
+        }
+    };
+}
+
 impl RbatisExecutorAdatper for &rbatis::RBatis {
     fn get_dialect(&self) -> crate::SqlDialect {
         let driver_type = self.driver_type().unwrap();
@@ -277,13 +398,17 @@ impl RbatisExecutorAdatper for &rbatis::RBatis {
     }
 
     impl_rbatis_adapter_fetch_all_0!();
+    impl_rbatis_adapter_fetch_stream_0!();
     impl_rbatis_adapter_fetch_one_0!();
+    impl_rbatis_adapter_fetch_optional_0!();
     impl_rbatis_adapter_fetch_scalar_0!();
     impl_rbatis_adapter_fetch_execute_0!();
+    impl_rbatis_adapter_execute_batch_0!();
     impl_rbatis_adapter_insert_0!();
     impl_rbatis_adapter_fetch_insert_id_0!();
     impl_rbatis_adapter_fetch_page_count_0!();
     impl_rbatis_adapter_fetch_page_all_0!();
+    impl_rbatis_adapter_fetch_cursor_page_all_0!();
 }
 
 impl RbatisExecutorAdatper for &rbatis::executor::RBatisTxExecutor {
@@ -293,13 +418,17 @@ impl RbatisExecutorAdatper for &rbatis::executor::RBatisTxExecutor {
     }
 
     impl_rbatis_adapter_fetch_all_0!();
+    impl_rbatis_adapter_fetch_stream_0!();
     impl_rbatis_adapter_fetch_one_0!();
+    impl_rbatis_adapter_fetch_optional_0!();
     impl_rbatis_adapter_fetch_scalar_0!();
     impl_rbatis_adapter_fetch_execute_0!();
+    impl_rbatis_adapter_execute_batch_0!();
     impl_rbatis_adapter_insert_0!();
     impl_rbatis_adapter_fetch_insert_id_0!();
     impl_rbatis_adapter_fetch_page_count_0!();
     impl_rbatis_adapter_fetch_page_all_0!();
+    impl_rbatis_adapter_fetch_cursor_page_all_0!();
 }
 
 impl RbatisExecutorAdatper for &rbatis::executor::RBatisConnExecutor {
@@ -309,11 +438,15 @@ impl RbatisExecutorAdatper for &rbatis::executor::RBatisConnExecutor {
     }
 
     impl_rbatis_adapter_fetch_all_0!();
+    impl_rbatis_adapter_fetch_stream_0!();
     impl_rbatis_adapter_fetch_one_0!();
+    impl_rbatis_adapter_fetch_optional_0!();
     impl_rbatis_adapter_fetch_scalar_0!();
     impl_rbatis_adapter_fetch_execute_0!();
+    impl_rbatis_adapter_execute_batch_0!();
     impl_rbatis_adapter_insert_0!();
     impl_rbatis_adapter_fetch_insert_id_0!();
     impl_rbatis_adapter_fetch_page_count_0!();
     impl_rbatis_adapter_fetch_page_all_0!();
+    impl_rbatis_adapter_fetch_cursor_page_all_0!();
 }
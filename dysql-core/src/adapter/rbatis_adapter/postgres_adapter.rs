@@ -11,42 +11,27 @@ impl RbatisPostgresAdapter {
 
 impl RbatisPostgresAdapter {
     crate::impl_rbatis_adapter_fetch_one!();
+    crate::impl_rbatis_adapter_fetch_optional!();
     crate::impl_rbatis_adapter_fetch_all!();
+    crate::impl_rbatis_adapter_fetch_stream!();
     crate::impl_rbatis_adapter_fetch_scalar!();
     crate::impl_rbatis_adapter_execute!();
+    crate::impl_rbatis_adapter_execute_batch!();
     crate::impl_rbatis_adapter_page_count!();
     crate::impl_rbatis_adapter_page_all!();
+    crate::impl_rbatis_adapter_cursor_page_all!();
 
-    pub async fn dy_insert<E, D, U>(self, executor: &E, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
+    pub async fn dy_insert<E, D, U>(self, executor: &E, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
         -> Result<Option<U>, crate::DySqlError>
     where 
         E: rbatis::executor::Executor,
         D: dysql_tpl::Content + Send + Sync,
         U: serde::de::DeserializeOwned,
     {
-        let named_sql = crate::gen_named_sql(named_template, &dto)?;
-            
-        let mut buf = Vec::<u8>::with_capacity(named_sql.len());
-        let sql_and_params = crate::extract_params_buf(&named_sql, &mut buf, self.dialect);
-        let sql = unsafe{std::str::from_utf8_unchecked(&buf)};
-        let param_names = match sql_and_params {
-            Ok(val) => val,
-            Err(e) => Err(
-                crate::DySqlError(crate::ErrorInner::new(crate::Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
-            )?,
-        };
+        let mut sql_buffer = crate::SqlBuffer::default();
+        let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, self.dialect)?;
 
-        let mut param_values : Vec<rbs::Value> = Vec::with_capacity(param_names.len());
-        if let Some(dto) = &dto {
-            for param_name in &param_names {
-                let stpl = dysql_tpl::SimpleTemplate::new(param_name);
-                
-                let param_value = stpl
-                    .apply(dto)
-                    .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, Some(e), None)))?;
-                param_values.push(crate::simple_2_value(param_value));
-            }
-        }
+        let param_values: Vec<rbs::Value> = param_values.drain(..).map(crate::simple_2_value).collect();
 
         let rst = executor
             .query(&sql, param_values)
@@ -3,7 +3,7 @@
 macro_rules! impl_sqlx_adapter_fetch_all {
     ($row:path, [$($vtype:ty),+]) => 
     {
-        async fn dy_fetch_all<D, U>(self, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
+        async fn dy_fetch_all<D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
             -> Result<Vec<U>, crate::DySqlError>
         where
             D: dysql_tpl::Content + Send + Sync,
@@ -11,34 +11,12 @@ macro_rules! impl_sqlx_adapter_fetch_all {
         {
             let dialect = self.get_dialect();
 
-            // let sql_data = crate::get_sql_and_params(template_id, named_template.clone(), &dto, dialect)?;
-            // let sql = &sql_data.sql;
-            // let param_names = &sql_data.param_names;
-
-            let named_sql = crate::gen_named_sql(named_template, &dto)?;
-            let mut buf = Vec::<u8>::with_capacity(named_sql.len());
-            let sql_and_params = crate::extract_params_buf(&named_sql, &mut buf, dialect);
-            let sql = unsafe{std::str::from_utf8_unchecked(&buf)};
-            let param_names = match sql_and_params {
-                Ok(val) => val,
-                Err(e) => Err(
-                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
-                )?,
-            };
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, dialect)?;
 
             let mut query = sqlx::query_as::<_, U>(sql);
-            if let Some(dto) = &dto {
-                for param_name in param_names {
-                    let stpl = dysql_tpl::SimpleTemplate::new(param_name);
-                    
-                    let param_value = stpl.apply(dto);
-                    match param_value {
-                        Ok(param_value) => {
-                            query = impl_bind_sqlx_param_value!(query, param_value, [$($vtype),+]);
-                        },
-                        Err(e) => Err(crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, Some(e), None)))?,
-                    }
-                }
+            for param_value in param_values.drain(..) {
+                query = impl_bind_sqlx_param_value!(query, param_value, [$($vtype),+]);
             }
 
             let rst = query.fetch_all(self).await;
@@ -48,41 +26,60 @@ macro_rules! impl_sqlx_adapter_fetch_all {
     };
 }
 
+#[macro_export]
+macro_rules! impl_sqlx_adapter_fetch_stream {
+    ($row:path, [$($vtype:ty),+]) =>
+    {
+        fn dy_fetch_stream<'e, D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
+            -> futures_core::stream::BoxStream<'e, Result<U, crate::DySqlError>>
+        where
+            Self: 'e,
+            D: dysql_tpl::Content + Send + Sync + 'e,
+            for<'r> U: sqlx::FromRow<'r, $row> + Send + Unpin + 'e,
+        {
+            // sql 及绑定参数都保存在 stream 内部，结果集按行从数据库中拉取
+            Box::pin(async_stream::try_stream! {
+                let dialect = self.get_dialect();
+
+                let mut sql_buffer = crate::SqlBuffer::default();
+                let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, dialect)?;
+
+                // try_stream! 无法改写宏内部的 `?`，所以参数绑定放在闭包中完成
+                let query = (|| -> Result<_, crate::DySqlError> {
+                    let mut query = sqlx::query_as::<_, U>(sql);
+                    for param_value in param_values.drain(..) {
+                        query = impl_bind_sqlx_param_value!(query, param_value, [$($vtype),+]);
+                    }
+                    Ok(query)
+                })()?;
+
+                let mut rows = query.fetch(self);
+                while let Some(row) = futures_util::TryStreamExt::try_next(&mut rows)
+                    .await
+                    .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::QueryError, Some(Box::new(e)), None)))?
+                {
+                    yield row;
+                }
+            })
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! impl_sqlx_adapter_fetch_one {
     ($row:path, [$($vtype:ty),+]) => {
-        async fn dy_fetch_one<D, U>(self, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
+        async fn dy_fetch_one<D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
             -> Result<U, crate::DySqlError>
         where
             D: dysql_tpl::Content + Send + Sync,
             for<'r> U: sqlx::FromRow<'r, $row> + Send + Unpin,
         {
-            let named_sql = crate::gen_named_sql(named_template, &dto)?;
-            
-            let mut buf = Vec::<u8>::with_capacity(named_sql.len());
-            let sql_and_params = crate::extract_params_buf(&named_sql, &mut buf, self.get_dialect());
-            let sql = unsafe{std::str::from_utf8_unchecked(&buf)};
-            let param_names = match sql_and_params {
-                Ok(val) => val,
-                Err(e) => Err(
-                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
-                )?,
-            };
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, self.get_dialect())?;
     
             let mut query = sqlx::query_as::<_, U>(&sql);
-            if let Some(dto) = &dto {
-                for param_name in &param_names {
-                    let stpl = dysql_tpl::SimpleTemplate::new(param_name);
-                    
-                    let param_value = stpl.apply(dto);
-                    match param_value {
-                        Ok(param_value) => {
-                            
-                            query = impl_bind_sqlx_param_value!(query, param_value, [$($vtype),+]);
-                        },
-                        Err(e) => Err(crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, Some(e), None)))?,
-                    }
-                }
+            for param_value in param_values.drain(..) {
+                query = impl_bind_sqlx_param_value!(query, param_value, [$($vtype),+]);
             }
     
             let rst = query.fetch_one(self).await;
@@ -92,40 +89,45 @@ macro_rules! impl_sqlx_adapter_fetch_one {
     };
 }
 
+#[macro_export]
+macro_rules! impl_sqlx_adapter_fetch_optional {
+    ($row:path, [$($vtype:ty),+]) => {
+        async fn dy_fetch_optional<D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
+            -> Result<Option<U>, crate::DySqlError>
+        where
+            D: dysql_tpl::Content + Send + Sync,
+            for<'r> U: sqlx::FromRow<'r, $row> + Send + Unpin,
+        {
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, self.get_dialect())?;
+    
+            let mut query = sqlx::query_as::<_, U>(&sql);
+            for param_value in param_values.drain(..) {
+                query = impl_bind_sqlx_param_value!(query, param_value, [$($vtype),+]);
+            }
+    
+            let rst = query.fetch_optional(self).await;
+    
+            rst.map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::QueryError, Some(Box::new(e)), None)))
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! impl_sqlx_adapter_fetch_scalar {
     ([$($vtype:ty),+]) => {
-        async fn dy_fetch_scalar<D, U>(self, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
+        async fn dy_fetch_scalar<D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
             -> Result<U, crate::DySqlError>
         where
             D: dysql_tpl::Content + Send + Sync,
             for<'r> U: sqlx::Decode<'r, Self::DB> + sqlx::Type<Self::DB> + Send + Unpin
         {
-            let named_sql = crate::gen_named_sql(named_template, &dto)?;
-
-            let mut buf = Vec::<u8>::with_capacity(named_sql.len());
-            let sql_and_params = crate::extract_params_buf(&named_sql, &mut buf, self.get_dialect());
-            let sql = unsafe{std::str::from_utf8_unchecked(&buf)};
-            let param_names = match sql_and_params {
-                Ok(val) => val,
-                Err(e) => Err(
-                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
-                )?,
-            };
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, self.get_dialect())?;
 
             let mut query = sqlx::query_scalar::<_, U>(&sql);
-            if let Some(dto) = &dto {
-                for param_name in &param_names {
-                    let stpl = dysql_tpl::SimpleTemplate::new(param_name);
-                    
-                    let param_value = stpl.apply(dto);
-                    match param_value {
-                        Ok(param_value) => {
-                            query = impl_bind_sqlx_param_value!(query, param_value, [$($vtype),+]);
-                        },
-                        Err(e) => Err(crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, Some(e), None)))?,
-                    }
-                }
+            for param_value in param_values.drain(..) {
+                query = impl_bind_sqlx_param_value!(query, param_value, [$($vtype),+]);
             }
 
             let rst = query.fetch_one(self).await;
@@ -139,36 +141,17 @@ macro_rules! impl_sqlx_adapter_fetch_scalar {
 #[macro_export]
 macro_rules! impl_sqlx_adapter_execute {
     ([$($vtype:ty),+]) => {
-        async fn dy_execute<D>(self, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
+        async fn dy_execute<D>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
             -> Result<u64, crate::DySqlError>
         where
             D: dysql_tpl::Content + Send + Sync
         {
-            let named_sql = crate::gen_named_sql(named_template, &dto)?;
-
-            let mut buf = Vec::<u8>::with_capacity(named_sql.len());
-            let sql_and_params = crate::extract_params_buf(&named_sql, &mut buf, self.get_dialect());
-            let sql = unsafe{std::str::from_utf8_unchecked(&buf)};
-            let param_names = match sql_and_params {
-                Ok(val) => val,
-                Err(e) => Err(
-                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
-                )?,
-            };
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, self.get_dialect())?;
     
             let mut query = sqlx::query::<_>(&sql);
-            if let Some(dto) = &dto {
-                for param_name in &param_names {
-                    let stpl = dysql_tpl::SimpleTemplate::new(param_name);
-                    
-                    let param_value = stpl.apply(dto);
-                    match param_value {
-                        Ok(param_value) => {
-                            query = impl_bind_sqlx_param_value!(query, param_value, [$($vtype),+]);
-                        },
-                        Err(e) => Err(crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, Some(e), None)))?,
-                    }
-                }
+            for param_value in param_values.drain(..) {
+                query = impl_bind_sqlx_param_value!(query, param_value, [$($vtype),+]);
             }
     
             let rst = query.execute(self).await;
@@ -183,27 +166,60 @@ macro_rules! impl_sqlx_adapter_execute {
     };
 }
 
+/// 在同一个连接上用同一个 sql 模板逐个执行多个 dto，
+/// 渲染结果相同的 sql 复用连接上缓存的预编译语句
+#[macro_export]
+macro_rules! impl_sqlx_adapter_execute_batch {
+    ([$($vtype:ty),+]) => {
+        async fn dy_execute_batch<D, I>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dtos: I)
+            -> Result<Vec<u64>, crate::DySqlError>
+        where
+            D: dysql_tpl::Content + Send + Sync,
+            I: IntoIterator<Item = D> + Send,
+            I::IntoIter: Send,
+        {
+            let dialect = self.get_dialect();
+            let mut conn = sqlx::Acquire::acquire(self)
+                .await
+                .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::QueryError, Some(Box::new(e)), None)))?;
+
+            // 所有 dto 复用同一个 SqlBuffer，渲染结果相同的 sql 由连接缓存预编译语句
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let mut af_rows = Vec::new();
+            for dto in dtos {
+                let dto = Some(dto);
+                let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template.clone(), &dto, dialect)?;
+
+                let mut query = sqlx::query::<_>(sql);
+                for param_value in param_values.drain(..) {
+                    query = impl_bind_sqlx_param_value!(query, param_value, [$($vtype),+]);
+                }
+
+                let rst = query.execute(&mut *conn).await;
+                let rst = rst.map_err(|e|
+                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::QueryError, Some(Box::new(e)), None))
+                )?;
+
+                af_rows.push(rst.rows_affected());
+            }
+
+            Ok(af_rows)
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! impl_sqlx_adapter_page_count {
     ([$($vtype:ty),+])  => {
-        async fn dy_page_count<D, U>(self, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
+        async fn dy_page_count<D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
             -> Result<U, crate::DySqlError>
         where
             D: dysql_tpl::Content + Send + Sync,
             for<'r> U: sqlx::Decode<'r, Self::DB> + sqlx::Type<Self::DB> + Send + Unpin
         {
             use std::io::Write;
-            let named_sql = crate::gen_named_sql(named_template, &dto)?;
-            
-            let mut buf = Vec::<u8>::with_capacity(named_sql.len());
-            let sql_and_params = crate::extract_params_buf(&named_sql, &mut buf, self.get_dialect());
-            let sql = unsafe{std::str::from_utf8_unchecked(&buf)};
-            let param_names = match sql_and_params {
-                Ok(val) => val,
-                Err(e) => Err(
-                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
-                )?,
-            };
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, self.get_dialect())?;
     
             // count sql
             let buffer_size = sql.len() + 200;
@@ -214,18 +230,8 @@ macro_rules! impl_sqlx_adapter_page_count {
             };
     
             let mut query = sqlx::query_scalar::<_, U>(&count_sql);
-            if let Some(dto) = &dto {
-                for param_name in &param_names {
-                    let stpl = dysql_tpl::SimpleTemplate::new(param_name);
-                    
-                    let param_value = stpl.apply(dto);
-                    match param_value {
-                        Ok(param_value) => {
-                            query = impl_bind_sqlx_param_value!(query, param_value, [$($vtype),+]);
-                        },
-                        Err(e) => Err(crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, Some(e), None)))?,
-                    }
-                }
+            for param_value in param_values.drain(..) {
+                query = impl_bind_sqlx_param_value!(query, param_value, [$($vtype),+]);
             }
     
             let rst = query.fetch_one(self).await;
@@ -238,7 +244,7 @@ macro_rules! impl_sqlx_adapter_page_count {
 #[macro_export]
 macro_rules! impl_sqlx_adapter_page_all {
     ($row:path, [$($vtype:ty),+]) => {
-        async fn dy_page_all<D, U>(self, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, page_dto: &crate::PageDto<D>) 
+        async fn dy_page_all<D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, page_dto: &crate::PageDto<D>) 
             -> Result<crate::Pagination<U>, crate::DySqlError>
         where
             D: dysql_tpl::Content + Send + Sync,
@@ -246,17 +252,8 @@ macro_rules! impl_sqlx_adapter_page_all {
         {
             use std::io::Write;
 
-            let named_sql= named_template.render_sql(page_dto);
-        
-            let mut buf = Vec::<u8>::with_capacity(named_sql.len());
-            let sql_and_params = crate::extract_params_buf(&named_sql, &mut buf, self.get_dialect());
-            let sql = unsafe{std::str::from_utf8_unchecked(&buf)};
-            let param_names = match sql_and_params {
-                Ok(val) => val,
-                Err(e) => Err(
-                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
-                )?,
-            };
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &Some(page_dto), self.get_dialect())?;
     
             let buffer_size = sql.len() + 200;
             let mut sql_buf = Vec::<u8>::with_capacity(buffer_size);
@@ -278,16 +275,8 @@ macro_rules! impl_sqlx_adapter_page_all {
             };
     
             let mut query = sqlx::query_as::<_, U>(&page_sql);
-            for param_name in &param_names {
-                let stpl = dysql_tpl::SimpleTemplate::new(param_name);
-                
-                let param_value = stpl.apply(&page_dto);
-                match param_value {
-                    Ok(param_value) => {
-                        query = impl_bind_sqlx_param_value!(query, param_value, [$($vtype),+]);
-                    },
-                    Err(e) => Err(crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, Some(e), None)))?,
-                }
+            for param_value in param_values.drain(..) {
+                query = impl_bind_sqlx_param_value!(query, param_value, [$($vtype),+]);
             }
     
             let rst = query.fetch_all(self).await;
@@ -302,3 +291,31 @@ macro_rules! impl_sqlx_adapter_page_all {
         }
     };
 }
+
+#[macro_export]
+macro_rules! impl_sqlx_adapter_cursor_page_all {
+    ($row:path, [$($vtype:ty),+]) => {
+        async fn dy_cursor_page_all<D, U>(self, named_template: std::sync::Arc<dysql_tpl::Template>, cursor_dto: &crate::CursorDto<D>) 
+            -> Result<crate::CursorPagination<U>, crate::DySqlError>
+        where
+            D: dysql_tpl::Content + Send + Sync,
+            for<'r> U: sqlx::FromRow<'r, Self::Row> + dysql_tpl::Content + Send + Unpin
+        {
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_cursor_sql_and_values(named_template, cursor_dto, self.get_dialect())?;
+    
+            let mut query = sqlx::query_as::<_, U>(sql);
+            for param_value in param_values.drain(..) {
+                query = impl_bind_sqlx_param_value!(query, param_value, [$($vtype),+]);
+            }
+    
+            let rst = query.fetch_all(self).await;
+            let rst = match rst {
+                Ok(v) => v,
+                Err(e) => Err(crate::DySqlError(crate::ErrorInner::new(crate::Kind::QueryError, Some(Box::new(e)), None)))?,
+            };
+    
+            crate::CursorPagination::from_dto(cursor_dto, rst)
+        }
+    };
+}
@@ -13,6 +13,7 @@ macro_rules! impl_bind_sqlx_param_value {
                 dysql_tpl::SimpleValue::t_str(val) => $query.bind(unsafe {&*val.0}),
                 dysql_tpl::SimpleValue::t_String(val) => $query.bind(unsafe {&*val.0}),
                 dysql_tpl::SimpleValue::None(val) => $query.bind(val),
+                dysql_tpl::SimpleValue::t_list(val) => $query.bind(<Self::DB as crate::SqlxListParam>::list_param(val)?),
                 _ => Err(crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, None, Some(format!("the type of {:?} is not support", $p_val)))))?,
             }
         }
@@ -51,34 +52,57 @@ pub trait SqlxExecutorAdatper
     }
 
     /// 查询并返回多个指定类型的对象
-    async fn dy_fetch_all<D, U>(self, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
+    async fn dy_fetch_all<D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
         -> Result<Vec<U>, crate::DySqlError>
     where
         D: dysql_tpl::Content + Send + Sync,
         for<'r> U: sqlx::FromRow<'r, Self::Row> + Send + Unpin;
 
+    /// 查询并以 Stream 的方式逐行返回指定类型的对象
+    fn dy_fetch_stream<'e, D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
+        -> futures_core::stream::BoxStream<'e, Result<U, crate::DySqlError>>
+    where
+        Self: 'e,
+        D: dysql_tpl::Content + Send + Sync + 'e,
+        for<'r> U: sqlx::FromRow<'r, Self::Row> + Send + Unpin + 'e;
+
     /// 查询并返回一个指定类型的对象
-    async fn dy_fetch_one<D, U>(self, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
+    async fn dy_fetch_one<D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
         -> Result<U, crate::DySqlError>
     where
         D: dysql_tpl::Content + Send + Sync,
         for<'r> U: sqlx::FromRow<'r, Self::Row> + Send + Unpin;
 
+    /// 查询并返回一个指定类型的对象，没有记录时返回 None
+    async fn dy_fetch_optional<D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
+        -> Result<Option<U>, crate::DySqlError>
+    where
+        D: dysql_tpl::Content + Send + Sync,
+        for<'r> U: sqlx::FromRow<'r, Self::Row> + Send + Unpin;
+
     /// 查询并返回一个指定类型的单值
-    async fn dy_fetch_scalar<D, U>(self, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
+    async fn dy_fetch_scalar<D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
         -> Result<U, crate::DySqlError>
     where
         D: dysql_tpl::Content + Send + Sync,
         for<'r> U: sqlx::Decode<'r, Self::DB> + sqlx::Type<Self::DB> + Send + Unpin;
 
     /// 执行一条sql命令并返回受其影响的记录数
-    async fn dy_execute<D>(self, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
+    async fn dy_execute<D>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
         -> Result<u64, crate::DySqlError>
     where
         D: dysql_tpl::Content + Send + Sync;
 
+    /// 在调用者的连接 (或事务) 上用同一个 sql 模板逐个执行多个 dto，返回每个 dto 影响的记录数
+    async fn dy_execute_batch<D, I>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dtos: I)
+        -> Result<Vec<u64>, crate::DySqlError>
+    where
+        D: dysql_tpl::Content + Send + Sync,
+        I: IntoIterator<Item = D> + Send,
+        I::IntoIter: Send;
+
     /// 新增一条记录
-    async fn dy_insert<D, U>(self, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
+    async fn dy_insert<D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
         -> Result<Option<U>, crate::DySqlError>
     where
         D: dysql_tpl::Content + Send + Sync,
@@ -91,16 +115,23 @@ pub trait SqlxExecutorAdatper
         for<'r> U: sqlx::Decode<'r, Self::DB> + sqlx::Type<Self::DB> + Send + Unpin;
 
     /// 用于在分页查询中获取符合条件的总记录数
-    async fn dy_page_count<D, U>(self, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
+    async fn dy_page_count<D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>) 
         -> Result<U, crate::DySqlError>
     where
         D: dysql_tpl::Content + Send + Sync,
         for<'r> U: sqlx::Decode<'r, Self::DB> + sqlx::Type<Self::DB> + Send + Unpin;
 
     /// 用返回分页查询中获取符合条件的结果
-    async fn dy_page_all<D, U>(self, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, page_dto: &crate::PageDto<D>) 
+    async fn dy_page_all<D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, page_dto: &crate::PageDto<D>) 
         -> Result<crate::Pagination<U>, crate::DySqlError>
     where
         D: dysql_tpl::Content + Send + Sync,
         for<'r> U: sqlx::FromRow<'r, Self::Row> + Send + Unpin;
+
+    /// 游标分页查询，返回一页结果及下一页的游标
+    async fn dy_cursor_page_all<D, U>(self, named_template: std::sync::Arc<dysql_tpl::Template>, cursor_dto: &crate::CursorDto<D>) 
+        -> Result<crate::CursorPagination<U>, crate::DySqlError>
+    where
+        D: dysql_tpl::Content + Send + Sync,
+        for<'r> U: sqlx::FromRow<'r, Self::Row> + dysql_tpl::Content + Send + Unpin;
 }
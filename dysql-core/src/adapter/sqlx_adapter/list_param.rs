@@ -0,0 +1,181 @@
+use dysql_tpl::SimpleValue;
+
+use crate::{DySqlError, ErrorInner, Kind};
+
+/// 列表参数 (如 `= ANY(:ids)` 中的 Vec 字段) 在各数据库下的绑定方式
+pub trait SqlxListParam: sqlx::Database {
+    type List: for<'q> sqlx::Encode<'q, Self> + sqlx::Type<Self> + Send;
+
+    /// 将列表参数转换为可绑定的值
+    fn list_param(values: Vec<SimpleValue>) -> Result<Self::List, DySqlError>;
+}
+
+fn unsupported_list(dialect: &str) -> DySqlError {
+    DySqlError(ErrorInner::new(
+        Kind::BindParamterError,
+        None,
+        Some(format!("list parameter is not supported by {}, use 'IN (:name)' instead", dialect)),
+    ))
+}
+
+#[cfg(feature = "sqlx-mysql")]
+impl SqlxListParam for sqlx::MySql {
+    type List = Option<i64>;
+
+    fn list_param(_values: Vec<SimpleValue>) -> Result<Self::List, DySqlError> {
+        Err(unsupported_list("mysql"))
+    }
+}
+
+#[cfg(feature = "sqlx-sqlite")]
+impl SqlxListParam for sqlx::Sqlite {
+    type List = Option<i64>;
+
+    fn list_param(_values: Vec<SimpleValue>) -> Result<Self::List, DySqlError> {
+        Err(unsupported_list("sqlite"))
+    }
+}
+
+#[cfg(feature = "sqlx-postgres")]
+pub use pg_array::*;
+
+#[cfg(feature = "sqlx-postgres")]
+mod pg_array {
+    use chrono::{DateTime, NaiveDateTime, Utc};
+    use dysql_tpl::SimpleValue;
+    use sqlx::{Encode, Postgres, Type};
+    use sqlx::encode::IsNull;
+    use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo};
+    use sqlx::postgres::types::Oid;
+    use uuid::Uuid;
+
+    use crate::{DySqlError, EmptyInListPolicy, ErrorInner, Kind};
+    use super::SqlxListParam;
+
+    /// 绑定为 postgres 数组的列表参数，数组的元素类型由列表中第一个非 None 的元素决定
+    #[allow(non_camel_case_types)]
+    #[derive(Debug)]
+    pub enum SqlxPgArray {
+        /// 空列表无法得知元素类型，绑定为 NULL 并由数据库推断参数类型
+        Null,
+        i16(Vec<Option<i16>>),
+        i32(Vec<Option<i32>>),
+        i64(Vec<Option<i64>>),
+        f32(Vec<Option<f32>>),
+        f64(Vec<Option<f64>>),
+        bool(Vec<Option<bool>>),
+        text(Vec<Option<String>>),
+        Uuid(Vec<Option<Uuid>>),
+        NaiveDateTime(Vec<Option<NaiveDateTime>>),
+        Utc(Vec<Option<DateTime<Utc>>>),
+    }
+
+    /// 按指定的元素类型收集列表中的值，None 绑定为数组中的 NULL
+    macro_rules! collect_pg_array {
+        ($values:ident, $variant:ident, { $($pat:pat => $conv:expr),+ $(,)? }) => {
+            $values
+                .iter()
+                .map(|value| match value {
+                    SimpleValue::None(_) => Ok(None),
+                    $($pat => Ok(Some($conv)),)+
+                    _ => Err(DySqlError(ErrorInner::new(
+                        Kind::BindParamterError,
+                        None,
+                        Some(format!("the type of list element {:?} is not consistent", value)),
+                    ))),
+                })
+                .collect::<Result<Vec<_>, DySqlError>>()
+                .map(SqlxPgArray::$variant)
+        };
+    }
+
+    impl SqlxPgArray {
+        pub fn new(values: Vec<SimpleValue>) -> Result<Self, DySqlError> {
+            let first = values.iter().find(|v| !matches!(v, SimpleValue::None(_)));
+            match first {
+                None if values.is_empty() && EmptyInListPolicy::current() == EmptyInListPolicy::Error => Err(DySqlError(
+                    ErrorInner::new(Kind::BindParamterError, None, Some("empty list is bound to an array parameter".to_owned()))
+                )),
+                // 与 `= ANY(NULL)` 的结果一致
+                None => Ok(SqlxPgArray::Null),
+                Some(SimpleValue::t_i16(_)) => collect_pg_array!(values, i16, { SimpleValue::t_i16(v) => *v }),
+                Some(SimpleValue::t_i32(_)) => collect_pg_array!(values, i32, { SimpleValue::t_i32(v) => *v }),
+                Some(SimpleValue::t_i64(_)) => collect_pg_array!(values, i64, { SimpleValue::t_i64(v) => *v }),
+                Some(SimpleValue::t_f32(_)) => collect_pg_array!(values, f32, { SimpleValue::t_f32(v) => *v }),
+                Some(SimpleValue::t_f64(_)) => collect_pg_array!(values, f64, { SimpleValue::t_f64(v) => *v }),
+                Some(SimpleValue::t_bool(_)) => collect_pg_array!(values, bool, { SimpleValue::t_bool(v) => *v }),
+                Some(SimpleValue::t_Uuid(_)) => collect_pg_array!(values, Uuid, { SimpleValue::t_Uuid(v) => *v }),
+                Some(SimpleValue::t_NaiveDateTime(_)) => collect_pg_array!(values, NaiveDateTime, { SimpleValue::t_NaiveDateTime(v) => *v }),
+                Some(SimpleValue::t_str(_) | SimpleValue::t_String(_)) => collect_pg_array!(values, text, {
+                    SimpleValue::t_str(v) => v.as_str().expect("Unexpected error").to_owned(),
+                    SimpleValue::t_String(v) => v.as_string().expect("Unexpected error").clone(),
+                }),
+                Some(SimpleValue::t_Utc(_) | SimpleValue::t_DateTime_Local(_) | SimpleValue::t_DateTime_FixedOffset(_)) => collect_pg_array!(values, Utc, {
+                    SimpleValue::t_Utc(v) => *v,
+                    SimpleValue::t_DateTime_Local(v) => v.with_timezone(&Utc),
+                    SimpleValue::t_DateTime_FixedOffset(v) => v.with_timezone(&Utc),
+                }),
+                Some(value) => Err(DySqlError(ErrorInner::new(
+                    Kind::BindParamterError,
+                    None,
+                    Some(format!("the type of list element {:?} is not support", value)),
+                ))),
+            }
+        }
+    }
+
+    impl Type<Postgres> for SqlxPgArray {
+        fn type_info() -> PgTypeInfo {
+            // oid 为 0 时由数据库推断参数类型
+            PgTypeInfo::with_oid(Oid(0))
+        }
+
+        fn compatible(_ty: &PgTypeInfo) -> bool {
+            true
+        }
+    }
+
+    impl Encode<'_, Postgres> for SqlxPgArray {
+        fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+            match self {
+                SqlxPgArray::Null => IsNull::Yes,
+                SqlxPgArray::i16(v) => v.encode_by_ref(buf),
+                SqlxPgArray::i32(v) => v.encode_by_ref(buf),
+                SqlxPgArray::i64(v) => v.encode_by_ref(buf),
+                SqlxPgArray::f32(v) => v.encode_by_ref(buf),
+                SqlxPgArray::f64(v) => v.encode_by_ref(buf),
+                SqlxPgArray::bool(v) => v.encode_by_ref(buf),
+                SqlxPgArray::text(v) => v.encode_by_ref(buf),
+                SqlxPgArray::Uuid(v) => v.encode_by_ref(buf),
+                SqlxPgArray::NaiveDateTime(v) => v.encode_by_ref(buf),
+                SqlxPgArray::Utc(v) => v.encode_by_ref(buf),
+            }
+        }
+
+        fn produces(&self) -> Option<PgTypeInfo> {
+            let ty = match self {
+                SqlxPgArray::Null => Self::type_info(),
+                SqlxPgArray::i16(_) => <Vec<i16> as Type<Postgres>>::type_info(),
+                SqlxPgArray::i32(_) => <Vec<i32> as Type<Postgres>>::type_info(),
+                SqlxPgArray::i64(_) => <Vec<i64> as Type<Postgres>>::type_info(),
+                SqlxPgArray::f32(_) => <Vec<f32> as Type<Postgres>>::type_info(),
+                SqlxPgArray::f64(_) => <Vec<f64> as Type<Postgres>>::type_info(),
+                SqlxPgArray::bool(_) => <Vec<bool> as Type<Postgres>>::type_info(),
+                SqlxPgArray::text(_) => <Vec<String> as Type<Postgres>>::type_info(),
+                SqlxPgArray::Uuid(_) => <Vec<Uuid> as Type<Postgres>>::type_info(),
+                SqlxPgArray::NaiveDateTime(_) => <Vec<NaiveDateTime> as Type<Postgres>>::type_info(),
+                SqlxPgArray::Utc(_) => <Vec<DateTime<Utc>> as Type<Postgres>>::type_info(),
+            };
+
+            Some(ty)
+        }
+    }
+
+    impl SqlxListParam for Postgres {
+        type List = SqlxPgArray;
+
+        fn list_param(values: Vec<SimpleValue>) -> Result<Self::List, DySqlError> {
+            SqlxPgArray::new(values)
+        }
+    }
+}
@@ -4,6 +4,12 @@
 #[cfg(feature = "sqlx")]
 pub use common::*;
 
+#[cfg(feature = "sqlx")]
+mod list_param;
+
+#[cfg(feature = "sqlx")]
+pub use list_param::*;
+
 #[cfg(feature = "sqlx-postgres")]
 mod postgres_adapter;
 
@@ -2,7 +2,7 @@
 macro_rules! impl_tokio_pg_adapter_fetch_all {
     ([$($vtype:ty),+]) => 
     {
-        async fn dy_fetch_all<D, U>(self, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
+        async fn dy_fetch_all<D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
             -> Result<Vec<U>, crate::DySqlError>
         where 
             D: dysql_tpl::Content + Send + Sync,
@@ -10,35 +10,16 @@ macro_rules! impl_tokio_pg_adapter_fetch_all {
         {
             let dialect = self.get_dialect();
 
-            let named_sql = crate::gen_named_sql(named_template, &dto)?;
-            let mut buf = Vec::<u8>::with_capacity(named_sql.len());
-            let sql_and_params = crate::extract_params_buf(&named_sql, &mut buf, dialect);
-            let sql = unsafe{std::str::from_utf8_unchecked(&buf)};
-            let param_names = match sql_and_params {
-                Ok(val) => val,
-                Err(e) => Err(
-                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
-                )?,
-            };
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, dialect)?;
 
             let stmt = self
                 .prepare(&sql)
                 .await
                 .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::PrepareStamentError, Some(Box::new(e)), None)))?;
 
-            let mut param_values : Vec<dysql_tpl::SimpleValue> = Vec::with_capacity(param_names.len());
-            if let Some(dto) = &dto {
-                for param_name in &param_names {
-                    let stpl = dysql_tpl::SimpleTemplate::new(param_name);
-                    
-                    let param_value = stpl
-                        .apply(dto)
-                        .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, Some(e), None)))?;
-                    param_values.push(param_value);
-                }
-            }
-            let mut tosql_values : Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::with_capacity(param_names.len());
-            for param_value in &param_values {
+            let mut tosql_values : Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::with_capacity(param_values.len());
+            for param_value in param_values.iter() {
                 impl_bind_tokio_pg_param_value!(tosql_values, param_value, [$($vtype),+]);
             }
 
@@ -60,45 +41,73 @@ macro_rules! impl_tokio_pg_adapter_fetch_all {
     };
 }
 
+#[macro_export]
+macro_rules! impl_tokio_pg_adapter_fetch_stream {
+    ([$($vtype:ty),+]) =>
+    {
+        fn dy_fetch_stream<'e, D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
+            -> futures_core::stream::BoxStream<'e, Result<U, crate::DySqlError>>
+        where
+            Self: 'e,
+            D: dysql_tpl::Content + Send + Sync + 'e,
+            U: tokio_pg_mapper::FromTokioPostgresRow + Send + 'e,
+        {
+            // sql 及绑定参数都保存在 stream 内部，结果集通过 query_raw 按行从数据库中拉取
+            Box::pin(async_stream::try_stream! {
+                let mut sql_buffer = crate::SqlBuffer::default();
+                let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, self.get_dialect())?;
+                let stmt = self
+                    .prepare(&sql)
+                    .await
+                    .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::PrepareStamentError, Some(Box::new(e)), None)))?;
+
+                // try_stream! 无法改写宏内部的 `?`，所以参数绑定放在闭包中完成
+                let tosql_values = (|| -> Result<_, crate::DySqlError> {
+                    let mut tosql_values : Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::with_capacity(param_values.len());
+                    for param_value in param_values.iter() {
+                        impl_bind_tokio_pg_param_value!(tosql_values, param_value, [$($vtype),+]);
+                    }
+                    Ok(tosql_values)
+                })()?;
+
+                let rows = self
+                    .query_raw(&stmt, tosql_values)
+                    .await
+                    .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::QueryError, Some(Box::new(e)), None)))?;
+                futures_util::pin_mut!(rows);
+
+                while let Some(row) = futures_util::TryStreamExt::try_next(&mut rows)
+                    .await
+                    .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::QueryError, Some(Box::new(e)), None)))?
+                {
+                    let rst = <U>::from_row(row)
+                        .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::ObjectMappingError, Some(Box::new(e)), None)))?;
+                    yield rst;
+                }
+            })
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! impl_tokio_pg_adapter_fetch_one {
     ([$($vtype:ty),+]) => 
     {
-        async fn dy_fetch_one<D, U>(self, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
+        async fn dy_fetch_one<D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
             -> Result<U, crate::DySqlError>
         where 
             D: dysql_tpl::Content + Send + Sync,
             U: tokio_pg_mapper::FromTokioPostgresRow,
         {
-            let named_sql = crate::gen_named_sql(named_template, &dto)?;
-
-            let mut buf = Vec::<u8>::with_capacity(named_sql.len());
-            let sql_and_params = crate::extract_params_buf(&named_sql, &mut buf, self.get_dialect());
-            let sql = unsafe{std::str::from_utf8_unchecked(&buf)};
-            let param_names = match sql_and_params {
-                Ok(val) => val,
-                Err(e) => Err(
-                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
-                )?,
-            };
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, self.get_dialect())?;
             let stmt = self
                 .prepare(&sql)
                 .await
                 .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::PrepareStamentError, Some(Box::new(e)), None)))?;
 
-            let mut param_values : Vec<dysql_tpl::SimpleValue> = Vec::with_capacity(param_names.len());
-            if let Some(dto) = &dto {
-                for param_name in &param_names {
-                    let stpl = dysql_tpl::SimpleTemplate::new(param_name);
-                    
-                    let param_value = stpl
-                        .apply(dto)
-                        .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, Some(e), None)))?;
-                    param_values.push(param_value);
-                }
-            }
-            let mut tosql_values : Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::with_capacity(param_names.len());
-            for param_value in &param_values {
+            let mut tosql_values : Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::with_capacity(param_values.len());
+            for param_value in param_values.iter() {
                 impl_bind_tokio_pg_param_value!(tosql_values, param_value, [$($vtype),+]);
             }
 
@@ -122,45 +131,67 @@ macro_rules! impl_tokio_pg_adapter_fetch_one {
     };
 }
 
+#[macro_export]
+macro_rules! impl_tokio_pg_adapter_fetch_optional {
+    ([$($vtype:ty),+]) => 
+    {
+        async fn dy_fetch_optional<D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
+            -> Result<Option<U>, crate::DySqlError>
+        where 
+            D: dysql_tpl::Content + Send + Sync,
+            U: tokio_pg_mapper::FromTokioPostgresRow,
+        {
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, self.get_dialect())?;
+            let stmt = self
+                .prepare(&sql)
+                .await
+                .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::PrepareStamentError, Some(Box::new(e)), None)))?;
+
+            let mut tosql_values : Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::with_capacity(param_values.len());
+            for param_value in param_values.iter() {
+                impl_bind_tokio_pg_param_value!(tosql_values, param_value, [$($vtype),+]);
+            }
+
+            // 多于一行时取第一行，与 sqlx 及 rbatis 的行为一致 (query_opt 在多行时会报错)
+            let rows = self
+                .query_raw(&stmt, tosql_values)
+                .await
+                .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::QueryError, Some(Box::new(e)), None)))?;
+            futures_util::pin_mut!(rows);
+            let row = futures_util::TryStreamExt::try_next(&mut rows)
+                .await
+                .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::QueryError, Some(Box::new(e)), None)))?;
+            let rst = match row {
+                Some(row) => Some(<U>::from_row(row)
+                    .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::ObjectMappingError, Some(Box::new(e)), None)))?),
+                None => None,
+            };
+
+            Ok(rst)
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! impl_tokio_pg_adapter_fetch_scalar {
     ([$($vtype:ty),+]) => 
     {
-        async fn dy_fetch_scalar<D, U>(self, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
+        async fn dy_fetch_scalar<D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
             -> Result<U, crate::DySqlError>
         where 
             D: dysql_tpl::Content + Send + Sync,
             for<'a> U: tokio_postgres::types::FromSql<'a>,
         {
-            let named_sql = crate::gen_named_sql(named_template, &dto)?;
-            
-            let mut buf = Vec::<u8>::with_capacity(named_sql.len());
-            let sql_and_params = crate::extract_params_buf(&named_sql, &mut buf, self.get_dialect());
-            let sql = unsafe{std::str::from_utf8_unchecked(&buf)};
-            let param_names = match sql_and_params {
-                Ok(val) => val,
-                Err(e) => Err(
-                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
-                )?,
-            };
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, self.get_dialect())?;
             let stmt = self
                 .prepare(&sql)
                 .await
                 .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::PrepareStamentError, Some(Box::new(e)), None)))?;
 
-            let mut param_values : Vec<dysql_tpl::SimpleValue> = Vec::with_capacity(param_names.len());
-            if let Some(dto) = &dto {
-                for param_name in &param_names {
-                    let stpl = dysql_tpl::SimpleTemplate::new(param_name);
-                    
-                    let param_value = stpl
-                        .apply(dto)
-                        .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, Some(e), None)))?;
-                    param_values.push(param_value);
-                }
-            }
-            let mut tosql_values : Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::with_capacity(param_names.len());
-            for param_value in &param_values {
+            let mut tosql_values : Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::with_capacity(param_values.len());
+            for param_value in param_values.iter() {
                 impl_bind_tokio_pg_param_value!(tosql_values, param_value, [$($vtype),+]);
             }
 
@@ -183,40 +214,20 @@ macro_rules! impl_tokio_pg_adapter_fetch_scalar {
 macro_rules! impl_tokio_pg_adapter_execute {
     ([$($vtype:ty),+]) => 
     {
-        async fn dy_execute<D>(self, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
+        async fn dy_execute<D>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
             -> Result<u64, crate::DySqlError>
         where 
             D: dysql_tpl::Content + Send + Sync,
         {
-            let named_sql = crate::gen_named_sql(named_template, &dto)?;
-
-            let mut buf = Vec::<u8>::with_capacity(named_sql.len());
-            let sql_and_params = crate::extract_params_buf(&named_sql, &mut buf, self.get_dialect());
-            let sql = unsafe{std::str::from_utf8_unchecked(&buf)};
-            let param_names = match sql_and_params {
-                Ok(val) => val,
-                Err(e) => Err(
-                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
-                )?,
-            };
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, self.get_dialect())?;
             let stmt = self
                 .prepare(&sql)
                 .await
                 .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::PrepareStamentError, Some(Box::new(e)), None)))?;
 
-            let mut param_values : Vec<dysql_tpl::SimpleValue> = Vec::with_capacity(param_names.len());
-            if let Some(dto) = &dto {
-                for param_name in &param_names {
-                    let stpl = dysql_tpl::SimpleTemplate::new(param_name);
-                    
-                    let param_value = stpl
-                        .apply(dto)
-                        .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, Some(e), None)))?;
-                    param_values.push(param_value);
-                }
-            }
-            let mut tosql_values : Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::with_capacity(param_names.len());
-            for param_value in &param_values {
+            let mut tosql_values : Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::with_capacity(param_values.len());
+            for param_value in param_values.iter() {
                 impl_bind_tokio_pg_param_value!(tosql_values, param_value, [$($vtype),+]);
             }
 
@@ -233,45 +244,74 @@ macro_rules! impl_tokio_pg_adapter_execute {
     };
 }
 
+/// 用同一个 sql 模板逐个执行多个 dto，渲染结果相同的 sql 只预编译一次
+#[macro_export]
+macro_rules! impl_tokio_pg_adapter_execute_batch {
+    ([$($vtype:ty),+]) => 
+    {
+        async fn dy_execute_batch<D, I>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dtos: I)
+            -> Result<Vec<u64>, crate::DySqlError>
+        where 
+            D: dysql_tpl::Content + Send + Sync,
+            I: IntoIterator<Item = D> + Send,
+            I::IntoIter: Send,
+        {
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let mut stmt_cache: std::collections::HashMap<String, tokio_postgres::Statement> = std::collections::HashMap::new();
+            let mut af_rows = Vec::new();
+            for dto in dtos {
+                let dto = Some(dto);
+                let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template.clone(), &dto, self.get_dialect())?;
+
+                if !stmt_cache.contains_key(sql) {
+                    let stmt = self
+                        .prepare(sql)
+                        .await
+                        .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::PrepareStamentError, Some(Box::new(e)), None)))?;
+                    stmt_cache.insert(sql.to_owned(), stmt);
+                }
+                let stmt = &stmt_cache[sql];
+
+                let mut tosql_values : Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::with_capacity(param_values.len());
+                for param_value in param_values.iter() {
+                    impl_bind_tokio_pg_param_value!(tosql_values, param_value, [$($vtype),+]);
+                }
+
+                let params = tosql_values.into_iter();
+                let params = params.as_slice();
+
+                let affect_count = self
+                    .execute(stmt, &params)
+                    .await
+                    .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::QueryError, Some(Box::new(e)), None)))?;
+
+                af_rows.push(affect_count);
+            }
+
+            Ok(af_rows)
+        }
+    };
+}
+
 #[macro_export]
 macro_rules! impl_tokio_pg_adapter_insert {
     ([$($vtype:ty),+]) => 
     {
-        async fn dy_insert<D, U>(self, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
+        async fn dy_insert<D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
             -> Result<Option<U>, crate::DySqlError>
         where 
             D: dysql_tpl::Content + Send + Sync,
             for<'a> U: tokio_postgres::types::FromSql<'a>,
         {
-            let named_sql = crate::gen_named_sql(named_template, &dto)?;
-
-            let mut buf = Vec::<u8>::with_capacity(named_sql.len());
-            let sql_and_params = crate::extract_params_buf(&named_sql, &mut buf, self.get_dialect());
-            let sql = unsafe{std::str::from_utf8_unchecked(&buf)};
-            let param_names = match sql_and_params {
-                Ok(val) => val,
-                Err(e) => Err(
-                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
-                )?,
-            };
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, self.get_dialect())?;
             let stmt = self
                 .prepare(&sql)
                 .await
                 .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::PrepareStamentError, Some(Box::new(e)), None)))?;
 
-            let mut param_values : Vec<dysql_tpl::SimpleValue> = Vec::with_capacity(param_names.len());
-            if let Some(dto) = &dto {
-                for param_name in &param_names {
-                    let stpl = dysql_tpl::SimpleTemplate::new(param_name);
-                    
-                    let param_value = stpl
-                        .apply(dto)
-                        .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, Some(e), None)))?;
-                    param_values.push(param_value);
-                }
-            }
-            let mut tosql_values : Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::with_capacity(param_names.len());
-            for param_value in &param_values {
+            let mut tosql_values : Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::with_capacity(param_values.len());
+            for param_value in param_values.iter() {
                 impl_bind_tokio_pg_param_value!(tosql_values, param_value, [$($vtype),+]);
             }
             let params = tosql_values.into_iter();
@@ -306,7 +346,7 @@ macro_rules! impl_tokio_pg_adapter_fetch_insert_id {
 macro_rules! impl_tokio_pg_adapter_page_count {
     ([$($vtype:ty),+]) => 
     {
-        async fn dy_page_count<D, U>(self, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
+        async fn dy_page_count<D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, dto: Option<D>)
             -> Result<U, crate::DySqlError>
         where 
             D: dysql_tpl::Content + Send + Sync,
@@ -314,17 +354,8 @@ macro_rules! impl_tokio_pg_adapter_page_count {
         {
             use std::io::Write;
 
-            let named_sql = crate::gen_named_sql(named_template, &dto)?;
-            
-            let mut buf = Vec::<u8>::with_capacity(named_sql.len());
-            let sql_and_params = crate::extract_params_buf(&named_sql, &mut buf, self.get_dialect());
-            let sql = unsafe{std::str::from_utf8_unchecked(&buf)};
-            let param_names = match sql_and_params {
-                Ok(val) => val,
-                Err(e) => Err(
-                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
-                )?,
-            };
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &dto, self.get_dialect())?;
 
             // count sql
             let buffer_size = sql.len() + 200;
@@ -339,19 +370,8 @@ macro_rules! impl_tokio_pg_adapter_page_count {
                 .await
                 .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::PrepareStamentError, Some(Box::new(e)), None)))?;
 
-            let mut param_values : Vec<dysql_tpl::SimpleValue> = Vec::with_capacity(param_names.len());
-            if let Some(dto) = &dto {
-                for param_name in &param_names {
-                    let stpl = dysql_tpl::SimpleTemplate::new(param_name);
-                    
-                    let param_value = stpl
-                        .apply(dto)
-                        .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, Some(e), None)))?;
-                    param_values.push(param_value);
-                }
-            }
-            let mut tosql_values : Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::with_capacity(param_names.len());
-            for param_value in &param_values {
+            let mut tosql_values : Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::with_capacity(param_values.len());
+            for param_value in param_values.iter() {
                 impl_bind_tokio_pg_param_value!(tosql_values, param_value, [$($vtype),+]);
             }
 
@@ -374,7 +394,7 @@ macro_rules! impl_tokio_pg_adapter_page_count {
 macro_rules! impl_tokio_pg_adapter_page_all {
     ([$($vtype:ty),+]) => 
     {
-        async fn dy_page_all<D, U>(self, template_id: u64, named_template: std::sync::Arc<dysql_tpl::Template>, page_dto: &crate::PageDto<D>)
+        async fn dy_page_all<D, U>(self, template_id: crate::TemplateId, named_template: std::sync::Arc<dysql_tpl::Template>, page_dto: &crate::PageDto<D>)
             -> Result<crate::Pagination<U>, crate::DySqlError>
         where 
             D: dysql_tpl::Content + Send + Sync,
@@ -382,17 +402,8 @@ macro_rules! impl_tokio_pg_adapter_page_all {
         {   
             use std::io::Write;
 
-            let named_sql= named_template.render_sql(page_dto);
-            
-            let mut buf = Vec::<u8>::with_capacity(named_sql.len());
-            let sql_and_params = crate::extract_params_buf(&named_sql, &mut buf, self.get_dialect());
-            let sql = unsafe{std::str::from_utf8_unchecked(&buf)};
-            let param_names = match sql_and_params {
-                Ok(val) => val,
-                Err(e) => Err(
-                    crate::DySqlError(crate::ErrorInner::new(crate::Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
-                )?,
-            };
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_sql_and_values(template_id, named_template, &Some(page_dto), self.get_dialect())?;
 
             let buffer_size = sql.len() + 200;
             let mut sql_buf = Vec::<u8>::with_capacity(buffer_size);
@@ -415,18 +426,8 @@ macro_rules! impl_tokio_pg_adapter_page_all {
                 .await
                 .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::PrepareStamentError, Some(Box::new(e)), None)))?;
 
-            let mut param_values : Vec<dysql_tpl::SimpleValue> = Vec::with_capacity(param_names.len());
-            for param_name in &param_names {
-                let stpl = dysql_tpl::SimpleTemplate::new(param_name);
-                
-                let param_value = stpl
-                    .apply(page_dto)
-                    .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::BindParamterError, Some(e), None)))?;
-                param_values.push(param_value);
-            }
-
             let mut tosql_values : Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::new(); 
-            for param_value in &param_values {
+            for param_value in param_values.iter() {
                 impl_bind_tokio_pg_param_value!(tosql_values, param_value, [$($vtype),+]);
             }
 
@@ -449,4 +450,44 @@ macro_rules! impl_tokio_pg_adapter_page_all {
             Ok(pg_data)
         }
     }
-}
\ No newline at end of file
+}
+#[macro_export]
+macro_rules! impl_tokio_pg_adapter_cursor_page_all {
+    ([$($vtype:ty),+]) => 
+    {
+        async fn dy_cursor_page_all<D, U>(self, named_template: std::sync::Arc<dysql_tpl::Template>, cursor_dto: &crate::CursorDto<D>)
+            -> Result<crate::CursorPagination<U>, crate::DySqlError>
+        where 
+            D: dysql_tpl::Content + Send + Sync,
+            U: tokio_pg_mapper::FromTokioPostgresRow + dysql_tpl::Content
+        {   
+            let mut sql_buffer = crate::SqlBuffer::default();
+            let (sql, param_values) = sql_buffer.get_cursor_sql_and_values(named_template, cursor_dto, self.get_dialect())?;
+
+            let stmt = self
+                .prepare(sql)
+                .await
+                .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::PrepareStamentError, Some(Box::new(e)), None)))?;
+
+            let mut tosql_values : Vec<&(dyn tokio_postgres::types::ToSql + Sync)> = Vec::new(); 
+            for param_value in param_values.iter() {
+                impl_bind_tokio_pg_param_value!(tosql_values, param_value, [$($vtype),+]);
+            }
+
+            let params = tosql_values.into_iter();
+            let params = params.as_slice();
+
+            let rows = self
+                .query(&stmt, &params)
+                .await
+                .map_err(|e| crate::DySqlError(crate::ErrorInner::new(crate::Kind::QueryError, Some(Box::new(e)), None)))?;
+
+            let rst = rows
+                .iter()
+                .map(|row| <U>::from_row_ref(row).expect("query unexpected error"))
+                .collect::<Vec<U>>();
+
+            crate::CursorPagination::from_dto(cursor_dto, rst)
+        }
+    }
+}
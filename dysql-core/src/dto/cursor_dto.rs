@@ -0,0 +1,306 @@
+mod content;
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use dysql_tpl::{Content, SimpleError, SimpleValue};
+
+use crate::{DySqlError, ErrorInner, Kind, SortModel};
+
+/// 游标中保存的排序字段取值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum CursorValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+    Uuid(uuid::Uuid),
+    NaiveDateTime(NaiveDateTime),
+    Utc(DateTime<Utc>),
+}
+
+impl CursorValue {
+    fn from_simple(value: SimpleValue) -> Result<Self, DySqlError> {
+        let int = |v: Result<i64, std::num::TryFromIntError>| v.map(CursorValue::Int)
+            .map_err(|e| DySqlError(ErrorInner::new(Kind::CursorError, Some(Box::new(e)), None)));
+
+        let value = match value {
+            SimpleValue::t_bool(v) => CursorValue::Bool(v),
+            SimpleValue::t_i64(v) => CursorValue::Int(v),
+            SimpleValue::t_i32(v) => CursorValue::Int(v.into()),
+            SimpleValue::t_i16(v) => CursorValue::Int(v.into()),
+            SimpleValue::t_i8(v) => CursorValue::Int(v.into()),
+            SimpleValue::t_u32(v) => CursorValue::Int(v.into()),
+            SimpleValue::t_u16(v) => CursorValue::Int(v.into()),
+            SimpleValue::t_u8(v) => CursorValue::Int(v.into()),
+            SimpleValue::t_isize(v) => int(v.try_into())?,
+            SimpleValue::t_usize(v) => int(v.try_into())?,
+            SimpleValue::t_u64(v) => int(v.try_into())?,
+            SimpleValue::t_i128(v) => int(v.try_into())?,
+            SimpleValue::t_u128(v) => int(v.try_into())?,
+            SimpleValue::t_f32(v) => CursorValue::Float(v.into()),
+            SimpleValue::t_f64(v) => CursorValue::Float(v),
+            SimpleValue::t_char(v) => CursorValue::Str(v.to_string()),
+            SimpleValue::t_Uuid(v) => CursorValue::Uuid(v),
+            SimpleValue::t_NaiveDateTime(v) => CursorValue::NaiveDateTime(v),
+            SimpleValue::t_Utc(v) => CursorValue::Utc(v),
+            SimpleValue::t_DateTime_Local(v) => CursorValue::Utc(v.into()),
+            SimpleValue::t_DateTime_FixedOffset(v) => CursorValue::Utc(v.into()),
+            // `col > NULL` 不匹配任何记录，分页会在此中断
+            SimpleValue::None(_) => Err(DySqlError(ErrorInner::new(Kind::CursorError, None, Some("sort field can not be null".to_owned()))))?,
+            SimpleValue::t_str(_) => CursorValue::Str(value.as_str().expect("Unexpected error").to_owned()),
+            SimpleValue::t_String(_) => CursorValue::Str(value.as_string().expect("Unexpected error").clone()),
+            SimpleValue::t_list(_) => Err(DySqlError(ErrorInner::new(Kind::CursorError, None, Some("sort field can not be a list".to_owned()))))?,
+        };
+
+        Ok(value)
+    }
+
+    /// 转换为用于绑定 sql 参数的值
+    fn to_simple(&self) -> Result<SimpleValue, SimpleError> {
+        match self {
+            CursorValue::Bool(v) => Ok(SimpleValue::t_bool(*v)),
+            CursorValue::Int(v) => Ok(SimpleValue::t_i64(*v)),
+            CursorValue::Float(v) => Ok(SimpleValue::t_f64(*v)),
+            CursorValue::Str(v) => v.apply_unescaped(),
+            CursorValue::Uuid(v) => Ok(SimpleValue::t_Uuid(*v)),
+            CursorValue::NaiveDateTime(v) => Ok(SimpleValue::t_NaiveDateTime(*v)),
+            CursorValue::Utc(v) => Ok(SimpleValue::t_Utc(*v)),
+        }
+    }
+}
+
+/// 游标编码前的内容：排序字段及上一页最后一条记录中这些字段的值
+#[derive(Debug, Serialize, Deserialize)]
+struct CursorToken {
+    fields: Vec<String>,
+    values: Vec<CursorValue>,
+}
+
+/// 游标参数在 sql 中的参数名前缀
+const CURSOR_PARAM_PREFIX: &str = "__dy_cursor_";
+
+/// 游标 (keyset) 分页的 dto，根据 sort_model 中的排序字段及上一页返回的游标定位下一页，
+/// 最后一个排序字段需要能唯一确定一条记录 (如 id)
+#[derive(Debug, Deserialize, Clone)]
+// #[derive(Content)]
+pub struct CursorDto <T> {
+    pub data: Option<T>,
+    pub page_size: u64,
+    pub sort_model: Vec<SortModel>,
+    pub cursor: Option<String>,
+    #[serde(skip)]
+    cursor_values: Vec<CursorValue>,
+}
+
+impl<T> CursorDto<T>
+{
+    pub fn new(page_size: u64, data: Option<T>, sort_model: Vec<SortModel>, cursor: Option<String>) -> Self {
+        Self {
+            data,
+            page_size,
+            sort_model,
+            cursor,
+            cursor_values: vec![],
+        }
+    }
+
+    /// 校验排序字段并解码游标
+    pub fn init(&mut self) -> Result<(), DySqlError> {
+        if self.page_size == 0 {
+            Err(cursor_error("page_size of cursor pagination must be greater than 0".to_owned()))?
+        }
+        if self.sort_model.is_empty() {
+            Err(cursor_error("cursor pagination need at least one sort field".to_owned()))?
+        }
+        for sm in &self.sort_model {
+            let is_ident = sm.field.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+                && sm.field.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+            if !is_ident {
+                Err(cursor_error(format!("invalid sort field: '{}'", sm.field)))?
+            }
+            if !sm.sort.eq_ignore_ascii_case("asc") && !sm.sort.eq_ignore_ascii_case("desc") {
+                Err(cursor_error(format!("invalid sort order: '{}'", sm.sort)))?
+            }
+        }
+
+        self.cursor_values = match &self.cursor {
+            Some(cursor) => {
+                let token = decode_cursor(cursor)?;
+                let fields_matched = token.fields.len() == self.sort_model.len()
+                    && token.fields.iter().zip(&self.sort_model).all(|(f, sm)| *f == sm.field);
+                if !fields_matched || token.values.len() != token.fields.len() {
+                    Err(cursor_error("the cursor does not match the sort fields".to_owned()))?
+                }
+                token.values
+            },
+            None => vec![],
+        };
+
+        Ok(())
+    }
+
+    /// 将渲染后的 sql 包装为游标分页查询：
+    /// 排序方向一致时使用 `(a, b) > (:c0, :c1)` 的行比较，否则展开为 `a > :c0 OR (a = :c0 AND b < :c1)`，
+    /// 多取一条记录用于判断是否还有下一页
+    pub fn page_sql(&self, named_sql: &str) -> String {
+        let (prefix, suffix) = self.page_sql_parts();
+        format!("{}{}{}", prefix, named_sql, suffix)
+    }
+
+    /// 游标分页查询中渲染后的 sql 之前及之后的部分，游标的比较条件都在之后的部分中
+    pub(crate) fn page_sql_parts(&self) -> (&'static str, String) {
+        let mut sql = ") AS __dy_cursor_page".to_owned();
+
+        if !self.cursor_values.is_empty() {
+            let ops: Vec<&str> = self.sort_model.iter()
+                .map(|sm| if sm.sort.eq_ignore_ascii_case("desc") { "<" } else { ">" })
+                .collect();
+            let param = |i: usize| format!(":{}{}", CURSOR_PARAM_PREFIX, i);
+
+            let predicate = if ops.iter().all(|op| *op == ops[0]) {
+                let fields: Vec<&str> = self.sort_model.iter().map(|sm| sm.field.as_str()).collect();
+                let params: Vec<String> = (0..fields.len()).map(param).collect();
+                format!("({}) {} ({})", fields.join(", "), ops[0], params.join(", "))
+            } else {
+                let terms: Vec<String> = (0..self.sort_model.len()).map(|i| {
+                    let mut conds: Vec<String> = (0..i)
+                        .map(|j| format!("{} = {} ", self.sort_model[j].field, param(j)))
+                        .collect();
+                    conds.push(format!("{} {} {} ", self.sort_model[i].field, ops[i], param(i)));
+                    format!("({})", conds.join("AND "))
+                }).collect();
+                terms.join(" OR ")
+            };
+            sql.push_str(" WHERE ");
+            sql.push_str(&predicate);
+        }
+
+        let order_by: Vec<String> = self.sort_model.iter()
+            .map(|sm| format!("{} {}", sm.field, sm.sort.to_ascii_uppercase()))
+            .collect();
+        sql.push_str(&format!(" ORDER BY {} LIMIT {}", order_by.join(", "), self.page_size.saturating_add(1)));
+
+        ("SELECT * FROM (", sql)
+    }
+
+    /// 根据一条记录中排序字段的值生成游标
+    pub fn next_cursor<U>(&self, row: &U) -> Result<String, DySqlError>
+    where
+        U: Content,
+    {
+        let mut values = Vec::with_capacity(self.sort_model.len());
+        for sm in &self.sort_model {
+            let value = dysql_tpl::SimpleTemplate::new(&sm.field)
+                .apply(row)
+                .map_err(|e| DySqlError(ErrorInner::new(Kind::CursorError, Some(e), None)))?;
+            values.push(CursorValue::from_simple(value)?);
+        }
+
+        let token = CursorToken {
+            fields: self.sort_model.iter().map(|sm| sm.field.clone()).collect(),
+            values,
+        };
+
+        encode_cursor(&token)
+    }
+
+    /// 获取 sql 中游标参数 `__dy_cursor_N` 对应的值
+    fn cursor_param(&self, name: &str) -> Option<&CursorValue> {
+        let index: usize = name.strip_prefix(CURSOR_PARAM_PREFIX)?.parse().ok()?;
+        self.cursor_values.get(index)
+    }
+}
+
+fn cursor_error(msg: String) -> DySqlError {
+    DySqlError(ErrorInner::new(Kind::CursorError, None, Some(msg)))
+}
+
+/// 游标对外是不透明的字符串：json 序列化后再做十六进制编码
+fn encode_cursor(token: &CursorToken) -> Result<String, DySqlError> {
+    use std::fmt::Write;
+
+    let json = serde_json::to_vec(token)
+        .map_err(|e| DySqlError(ErrorInner::new(Kind::CursorError, Some(Box::new(e)), None)))?;
+    let mut cursor = String::with_capacity(json.len() * 2);
+    for b in json {
+        write!(cursor, "{:02x}", b).unwrap();
+    }
+
+    Ok(cursor)
+}
+
+fn decode_cursor(cursor: &str) -> Result<CursorToken, DySqlError> {
+    let invalid = || cursor_error(format!("invalid cursor: '{}'", cursor));
+
+    let chunks = cursor.as_bytes().chunks_exact(2);
+    if !chunks.remainder().is_empty() {
+        Err(invalid())?
+    }
+    let json = chunks
+        .map(|hex| std::str::from_utf8(hex).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok()))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(invalid)?;
+
+    serde_json::from_slice(&json).map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sort(field: &str, sort: &str) -> SortModel {
+        SortModel { field: field.to_owned(), sort: sort.to_owned() }
+    }
+
+    #[test]
+    fn test_cursor_page_sql() {
+        let mut dto = CursorDto::new(10, Option::<()>::None, vec![sort("age", "asc"), sort("id", "asc")], None);
+        dto.init().unwrap();
+        assert_eq!(
+            "SELECT * FROM (select * from test_user) AS __dy_cursor_page ORDER BY age ASC, id ASC LIMIT 11",
+            dto.page_sql("select * from test_user")
+        );
+
+        let token = CursorToken { fields: vec!["age".to_owned(), "id".to_owned()], values: vec![CursorValue::Int(13), CursorValue::Int(4)] };
+        dto.cursor = Some(encode_cursor(&token).unwrap());
+        dto.init().unwrap();
+        assert_eq!(
+            "SELECT * FROM (select * from test_user) AS __dy_cursor_page WHERE (age, id) > (:__dy_cursor_0, :__dy_cursor_1) ORDER BY age ASC, id ASC LIMIT 11",
+            dto.page_sql("select * from test_user")
+        );
+
+        // 排序方向不一致时展开比较条件
+        dto.sort_model = vec![sort("age", "desc"), sort("id", "asc")];
+        dto.init().unwrap();
+        assert_eq!(
+            "SELECT * FROM (select * from test_user) AS __dy_cursor_page WHERE (age < :__dy_cursor_0 ) OR (age = :__dy_cursor_0 AND id > :__dy_cursor_1 ) ORDER BY age DESC, id ASC LIMIT 11",
+            dto.page_sql("select * from test_user")
+        );
+
+        // 游标与排序字段不一致，或排序字段不合法
+        dto.sort_model = vec![sort("id", "asc")];
+        assert!(dto.init().is_err());
+        dto.sort_model = vec![sort("id; drop table test_user", "asc")];
+        assert!(dto.init().is_err());
+        dto.cursor = Some("xyz".to_owned());
+        dto.sort_model = vec![sort("id", "asc")];
+        assert!(dto.init().is_err());
+
+        // page_size 为 0 时无法生成下一页的游标
+        let mut dto = CursorDto::new(0, Option::<()>::None, vec![sort("id", "asc")], None);
+        assert!(dto.init().is_err());
+
+        dto.page_size = u64::MAX;
+        dto.init().unwrap();
+        assert!(dto.page_sql("select * from test_user").ends_with(&format!("LIMIT {}", u64::MAX)));
+    }
+
+    #[test]
+    fn test_next_cursor_with_null() {
+        let dto = CursorDto::new(10, Option::<()>::None, vec![sort("value", "asc")], None);
+        assert!(dto.next_cursor(&crate::Value::new(Some(3_i64))).is_ok());
+
+        let err = dto.next_cursor(&crate::Value::new(Option::<i64>::None)).unwrap_err();
+        assert_eq!(Kind::CursorError, err.0.kind);
+    }
+}
@@ -0,0 +1,217 @@
+#![allow(unused)]
+
+use super::CursorDto;
+
+impl<T> dysql_tpl::Content for CursorDto<T>
+where
+    T: dysql_tpl::Content,
+{
+    const FIELDS: dysql_tpl::ContentFields = dysql_tpl::ContentFields::Named {
+        fields: &[
+            dysql_tpl::ContentField { name: "data", fields: <Option<T> as dysql_tpl::Content>::FIELDS },
+            dysql_tpl::ContentField { name: "page_size", fields: dysql_tpl::ContentFields::Unknown },
+        ],
+        flatten: &[],
+    };
+
+    #[inline]
+    fn capacity_hint(&self, tpl: &dysql_tpl::Template) -> usize {
+        tpl.capacity_hint() + self.data.capacity_hint(tpl) + self.page_size.capacity_hint(tpl)
+    }
+    #[inline]
+    fn render_section<C, E, IC>(
+        &self,
+        section: dysql_tpl::Section<C>,
+        encoder: &mut E,
+        _content: Option<&IC>,
+    ) -> std::result::Result<(), E::Error>
+    where
+        C: dysql_tpl::traits::ContentSequence,
+        E: dysql_tpl::encoding::Encoder,
+    {
+        section.with(self).render(encoder, Option::<&()>::None)
+    }
+    #[inline]
+    fn apply_section<C>(
+        &self,
+        section: dysql_tpl::SimpleSection<C>,
+    ) -> std::result::Result<dysql_tpl::SimpleValue, dysql_tpl::SimpleError>
+    where
+        C: dysql_tpl::traits::ContentSequence,
+    {
+        section.with(self).apply()
+    }
+    #[inline]
+    fn render_notnone_section<C, E, IC>(
+        &self,
+        section: dysql_tpl::Section<C>,
+        encoder: &mut E,
+        _content: Option<&IC>,
+    ) -> std::result::Result<(), E::Error>
+    where
+        C: dysql_tpl::traits::ContentSequence,
+        E: dysql_tpl::encoding::Encoder,
+    {
+        section.with(self).render(encoder, Option::<&()>::None)
+    }
+    #[inline]
+    fn render_field_escaped<E>(
+        &self,
+        hash: u64,
+        name: &str,
+        encoder: &mut E,
+    ) -> std::result::Result<bool, E::Error>
+    where
+        E: dysql_tpl::encoding::Encoder,
+    {
+        match hash {
+            4430724373119788750u64 => self.data.render_escaped(encoder).map(|_| true),
+            10087286125916898991u64 => {
+                self.page_size.render_escaped(encoder).map(|_| true)
+            }
+            _ => Ok(false),
+        }
+    }
+    #[inline]
+    fn render_field_unescaped<E>(
+        &self,
+        hash: u64,
+        name: &str,
+        encoder: &mut E,
+    ) -> std::result::Result<bool, E::Error>
+    where
+        E: dysql_tpl::encoding::Encoder,
+    {
+        match hash {
+            4430724373119788750u64 => self.data.render_unescaped(encoder).map(|_| true),
+            10087286125916898991u64 => {
+                self.page_size.render_unescaped(encoder).map(|_| true)
+            }
+            _ => Ok(false),
+        }
+    }
+    #[inline]
+    fn apply_field_unescaped(
+        &self,
+        hash: u64,
+        name: &str,
+    ) -> std::result::Result<dysql_tpl::SimpleValue, dysql_tpl::SimpleError> {
+        match hash {
+            4430724373119788750u64 => self.data.apply_unescaped(),
+            10087286125916898991u64 => self.page_size.apply_unescaped(),
+            // 游标参数按名称获取
+            _ => match self.cursor_param(name) {
+                Some(value) => value.to_simple(),
+                None => Err(
+                    dysql_tpl::SimpleInnerError(std::format!("the data type of field: {0} is not supported ", name)).into()
+                )
+            }
+        }
+    }
+    fn render_field_section<P, E>(
+        &self,
+        hash: u64,
+        name: &str,
+        section: dysql_tpl::Section<P>,
+        encoder: &mut E,
+    ) -> std::result::Result<bool, E::Error>
+    where
+        P: dysql_tpl::traits::ContentSequence,
+        E: dysql_tpl::encoding::Encoder,
+    {
+        match hash {
+            4430724373119788750u64 => {
+                self.data
+                    .render_section(section, encoder, Option::<&()>::None)
+                    .map(|_| true)
+            }
+            10087286125916898991u64 => {
+                self.page_size
+                    .render_section(section, encoder, Option::<&()>::None)
+                    .map(|_| true)
+            }
+            _ => Ok(false),
+        }
+    }
+    fn apply_field_section<P>(
+        &self,
+        hash: u64,
+        name: &str,
+        section: dysql_tpl::SimpleSection<P>,
+    ) -> std::result::Result<dysql_tpl::SimpleValue, dysql_tpl::SimpleError>
+    where
+        P: dysql_tpl::traits::ContentSequence,
+    {
+        match hash {
+            4430724373119788750u64 => self.data.apply_section(section),
+            10087286125916898991u64 => self.page_size.apply_section(section),
+            _ => {
+                Err(
+                    dysql_tpl::SimpleInnerError(std::format!("tthe data type of field is not supported")).into()
+                )
+            }
+        }
+    }
+    fn render_field_inverse<P, E>(
+        &self,
+        hash: u64,
+        name: &str,
+        section: dysql_tpl::Section<P>,
+        encoder: &mut E,
+    ) -> std::result::Result<bool, E::Error>
+    where
+        P: dysql_tpl::traits::ContentSequence,
+        E: dysql_tpl::encoding::Encoder,
+    {
+        match hash {
+            4430724373119788750u64 => {
+                self.data
+                    .render_inverse(section, encoder, Option::<&()>::None)
+                    .map(|_| true)
+            }
+            10087286125916898991u64 => {
+                self.page_size
+                    .render_inverse(section, encoder, Option::<&()>::None)
+                    .map(|_| true)
+            }
+            _ => Ok(false),
+        }
+    }
+    fn render_field_notnone_section<P, E>(
+        &self,
+        hash: u64,
+        name: &str,
+        section: dysql_tpl::Section<P>,
+        encoder: &mut E,
+    ) -> std::result::Result<bool, E::Error>
+    where
+        P: dysql_tpl::traits::ContentSequence,
+        E: dysql_tpl::encoding::Encoder,
+    {
+        match hash {
+            4430724373119788750u64 => {
+                self.data.render_notnone_section(section, encoder, Option::<&()>::None)?;
+                Ok(self.data.is_truthy())
+            }
+            10087286125916898991u64 => {
+                self.page_size
+                    .render_notnone_section(section, encoder, Option::<&()>::None)?;
+                Ok(self.page_size.is_truthy())
+            }
+            _ => Ok(false),
+        }
+    }
+    #[inline]
+    fn field_operand(
+        &self,
+        hash: u64,
+        name: &str,
+        path: &[dysql_tpl::FieldPath],
+    ) -> std::option::Option<dysql_tpl::Operand<'_>> {
+        match hash {
+            4430724373119788750u64 => self.data.path_operand(path),
+            10087286125916898991u64 => self.page_size.path_operand(path),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use serde::Serialize;
+
+use crate::{CursorDto, DySqlError};
+
+#[derive(Debug, Serialize, Clone)]
+pub struct CursorPagination <T> {
+    pub data: Vec<T>,
+    pub page_size: u64,
+    pub has_next: bool,
+    pub next_cursor: Option<String>,
+}
+
+impl<T> CursorPagination<T> {
+    /// rows 为多取了一条记录的查询结果，用于判断是否还有下一页，下一页的游标由本页最后一条记录生成
+    pub fn from_dto<Dto>(dto: &CursorDto<Dto>, mut rows: Vec<T>) -> Result<Self, DySqlError>
+    where
+        T: dysql_tpl::Content,
+    {
+        let has_next = rows.len() as u64 > dto.page_size;
+        rows.truncate(dto.page_size as usize);
+
+        let next_cursor = match rows.last() {
+            Some(row) if has_next => Some(dto.next_cursor(row)?),
+            _ => None,
+        };
+
+        Ok(Self {
+            data: rows,
+            page_size: dto.page_size,
+            has_next,
+            next_cursor,
+        })
+    }
+
+    /// 将分页数据映射为另一种类型
+    pub fn map<U, F>(self, f: F) -> CursorPagination<U>
+    where
+        F: FnMut(T) -> U,
+    {
+        CursorPagination {
+            data: self.data.into_iter().map(f).collect(),
+            page_size: self.page_size,
+            has_next: self.has_next,
+            next_cursor: self.next_cursor,
+        }
+    }
+}
@@ -3,9 +3,13 @@ mod sort_model;
 mod page_dto;
 mod value;
 mod empty_object;
+mod cursor_dto;
+mod cursor_pagination;
 
 pub use pagination::*;
 pub use sort_model::*;
 pub use page_dto::*;
 pub use value::*;
 pub use empty_object::*;
+pub use cursor_dto::*;
+pub use cursor_pagination::*;
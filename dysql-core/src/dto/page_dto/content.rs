@@ -6,6 +6,20 @@ impl<T> dysql_tpl::Content for PageDto<T>
 where
     T: dysql_tpl::Content,
 {
+    const FIELDS: dysql_tpl::ContentFields = dysql_tpl::ContentFields::Named {
+        fields: &[
+            dysql_tpl::ContentField { name: "data", fields: <Option<T> as dysql_tpl::Content>::FIELDS },
+            dysql_tpl::ContentField { name: "page_size", fields: dysql_tpl::ContentFields::Unknown },
+            dysql_tpl::ContentField { name: "page_no", fields: dysql_tpl::ContentFields::Unknown },
+            dysql_tpl::ContentField { name: "total_page", fields: dysql_tpl::ContentFields::Unknown },
+            dysql_tpl::ContentField { name: "start", fields: dysql_tpl::ContentFields::Unknown },
+            dysql_tpl::ContentField { name: "total", fields: dysql_tpl::ContentFields::Unknown },
+            dysql_tpl::ContentField { name: "is_sort", fields: dysql_tpl::ContentFields::Unknown },
+            dysql_tpl::ContentField { name: "sort_model", fields: dysql_tpl::ContentFields::Unknown },
+        ],
+        flatten: &[],
+    };
+
     #[inline]
     fn capacity_hint(&self, tpl: &dysql_tpl::Template) -> usize {
         tpl.capacity_hint() + self.total.capacity_hint(tpl)
@@ -321,4 +335,23 @@ where
             _ => Ok(false),
         }
     }
+    #[inline]
+    fn field_operand(
+        &self,
+        hash: u64,
+        name: &str,
+        path: &[dysql_tpl::FieldPath],
+    ) -> std::option::Option<dysql_tpl::Operand<'_>> {
+        match hash {
+            1212331373962215526u64 => self.total.path_operand(path),
+            4430724373119788750u64 => self.data.path_operand(path),
+            8220941355636662115u64 => self.is_sort.path_operand(path),
+            9928246550803098198u64 => self.total_page.path_operand(path),
+            10087286125916898991u64 => self.page_size.path_operand(path),
+            11609058959308731613u64 => self.page_no.path_operand(path),
+            11721374545196086984u64 => self.sort_model.path_operand(path),
+            13127600857983441824u64 => self.start.path_operand(path),
+            _ => None,
+        }
+    }
 }
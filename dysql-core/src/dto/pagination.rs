@@ -27,4 +27,19 @@ impl<T> Pagination<T> {
             total: dto.total.expect("Unexpected error")
         }
     }
+
+    /// 将分页数据映射为另一种类型
+    pub fn map<U, F>(self, f: F) -> Pagination<U>
+    where
+        F: FnMut(T) -> U,
+    {
+        Pagination {
+            data: self.data.into_iter().map(f).collect(),
+            page_size: self.page_size,
+            page_no: self.page_no,
+            total_page: self.total_page,
+            start: self.start,
+            total: self.total,
+        }
+    }
 }
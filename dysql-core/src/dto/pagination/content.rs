@@ -275,4 +275,21 @@ where
             _ => Ok(false),
         }
     }
+    #[inline]
+    fn field_operand(
+        &self,
+        hash: u64,
+        name: &str,
+        path: &[dysql_tpl::FieldPath],
+    ) -> std::option::Option<dysql_tpl::Operand<'_>> {
+        match hash {
+            1212331373962215526u64 => self.total.path_operand(path),
+            4430724373119788750u64 => self.data.path_operand(path),
+            9928246550803098198u64 => self.total_page.path_operand(path),
+            10087286125916898991u64 => self.page_size.path_operand(path),
+            11609058959308731613u64 => self.page_no.path_operand(path),
+            13127600857983441824u64 => self.start.path_operand(path),
+            _ => None,
+        }
+    }
 }
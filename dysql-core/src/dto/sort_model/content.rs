@@ -184,4 +184,17 @@ impl dysql_tpl::Content for SortModel {
             _ => Ok(false),
         }
     }
+    #[inline]
+    fn field_operand(
+        &self,
+        hash: u64,
+        name: &str,
+        path: &[dysql_tpl::FieldPath],
+    ) -> std::option::Option<dysql_tpl::Operand<'_>> {
+        match hash {
+            5264107000299760680u64 => self.field.path_operand(path),
+            9189260103713392746u64 => self.sort.path_operand(path),
+            _ => None,
+        }
+    }
 }
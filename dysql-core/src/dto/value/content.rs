@@ -6,6 +6,13 @@ impl<T> dysql_tpl::Content for Value<T>
 where
     T: dysql_tpl::Content,
 {
+    const FIELDS: dysql_tpl::ContentFields = dysql_tpl::ContentFields::Named {
+        fields: &[
+            dysql_tpl::ContentField { name: "value", fields: T::FIELDS },
+        ],
+        flatten: &[],
+    };
+
     #[inline]
     fn capacity_hint(&self, tpl: &dysql_tpl::Template) -> usize {
         tpl.capacity_hint() + self.value.capacity_hint(tpl)
@@ -169,5 +176,17 @@ where
             _ => Ok(false),
         }
     }
+    #[inline]
+    fn field_operand(
+        &self,
+        hash: u64,
+        name: &str,
+        path: &[dysql_tpl::FieldPath],
+    ) -> std::option::Option<dysql_tpl::Operand<'_>> {
+        match hash {
+            2388869121238140847u64 => self.value.path_operand(path),
+            _ => None,
+        }
+    }
 }
 
@@ -2,11 +2,15 @@ use std::{fmt::{self, Display}, error::Error};
 use serde::Serialize;
 
 #[derive(Debug)]
-pub struct ParseSqlError(pub String);
+pub struct ParseSqlError {
+    pub msg: String,
+    /// 出错位置在 sql 中的字节偏移量
+    pub offset: usize,
+}
 
 impl Display for ParseSqlError {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(fmt, "parse sql error: {}", self.0)
+        write!(fmt, "parse sql error: {} at byte {}", self.msg, self.offset)
     }
 }
 
@@ -29,6 +33,8 @@ pub enum Kind {
     QueryError,
     ObjectMappingError,
     RecordNotFound,
+    SchemaError,
+    CursorError,
 }
 
 #[derive(Debug, Serialize)]
@@ -77,6 +83,8 @@ impl fmt::Display for DySqlError {
                 Kind::ObjectMappingError => fmt.write_str("sql error: error object mapping"),
                 Kind::ExtractSqlParamterError => fmt.write_str("sql error: error extract sql parameter"),
                 Kind::RecordNotFound => fmt.write_str("sql error: error record is not found"),
+                Kind::SchemaError => fmt.write_str("sql error: error schema snapshot"),
+                Kind::CursorError => fmt.write_str("sql error: error pagination cursor"),
             }
         };
         
@@ -2,7 +2,9 @@
 
 use std::io::Cursor;
 
-use crate::{sql_dialect::SqlDialect, error::{ParseSqlResult, ParseSqlError}};
+use dysql_tpl::{SqlSegmentKind, SqlSegments};
+
+use crate::{sql_dialect::SqlDialect, error::ParseSqlResult, SqlLexer, SqlPiece};
 
 ///
 /// extract sql and params from raw sql
@@ -27,69 +29,80 @@ pub fn extract_params_buf<'a>(o_sql: &'a str, sql_buf: &mut Vec<u8>, sql_dial: S
     let mut sql_buf = Cursor::new(sql_buf);
     let mut params = vec![];
 
-    let mut count = 0;
-    let mut start: usize = 0;
-    let mut cur = start;
-    let end = o_sql.len();
-
-    while cur < end {
-        let (found, current_cursor) = char_index(o_sql, cur, &[b':']);
-
-        if found {
-            cur = current_cursor;
-            count += 1;
-            match sql_dial {
-                SqlDialect::postgres => {
-                    write!(&mut sql_buf, "{}{}{}", &o_sql[start..cur], "$", &count).unwrap();
-                },
-                _ => write!(&mut sql_buf, "{}?",&o_sql[start..cur]).unwrap(),
-            }
-            
-            // skip ":" char
-            cur += 1;
-            start = cur;
-
-            // get named parameter end index
-            let err_msg = "not found named parameter after ':'".to_owned();
-            if cur == end {
-                return Err(ParseSqlError(err_msg))
-            } else {
-                let (found, current_cursor) = char_index(o_sql, cur, &[b' ', b'\n', b'\t', b',', b';', b'{', b')', b'|']);
-                if found && current_cursor == cur {
-                    return Err(ParseSqlError(err_msg))
+    for piece in SqlLexer::new(o_sql, sql_dial) {
+        match piece? {
+            SqlPiece::Text(text) => sql_buf.write_all(text.as_bytes()).unwrap(),
+            SqlPiece::Param { name, .. } => {
+                params.push(name);
+                match sql_dial {
+                    SqlDialect::postgres => write!(&mut sql_buf, "${}", params.len()).unwrap(),
+                    _ => sql_buf.write_all(b"?").unwrap(),
                 }
-
-                cur = current_cursor;
-                let p = &o_sql[start..cur];
-                // params.push(p.to_owned());
-                params.push(p);
-                start = cur;
             }
-        } else {
-            write!(&mut sql_buf, "{}", &o_sql[start..end]).unwrap();
-            break;
         }
     }
 
     Ok(params)
 }
 
-///
-/// get the index for specified chars in the string slice from begin pos
-/// 
-fn char_index(s: &str, begin: usize, search_chars: &[u8]) -> (bool, usize) {
-    let end = s.len();
-    for i in begin..end {
-        let c = &s[i..i+1];
-        for j in 0..search_chars.len() {
-            let a = search_chars[j];
-            if c.as_bytes()[0] == a {
-                return (true, i)
+/// 将 sql 中字符串、引号中的标识符及注释之外的各行去掉首尾空白合并为一行，并去掉 `--` 单行注释，避免注释吞掉合并到其后的 sql；
+/// `/* .. */` 注释可能是优化器提示，原样保留。
+/// 编译期无法得知数据库方言，只有在各个方言的词法规则下都是 sql 代码的换行才合并，都是单行注释的内容才去掉，
+/// 其余内容 (如 `'C:\' -- ..` 这样在 mysql 中未结束的字符串) 连同其中的换行原样保留
+pub fn join_sql_lines(sql: &str) -> String {
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    enum Class {
+        Code,
+        Comment,
+        Verbatim,
+    }
+
+    let mut classes: Vec<Option<Class>> = vec![None; sql.len()];
+    for dialect in [SqlDialect::postgres, SqlDialect::mysql, SqlDialect::sqlite] {
+        for segment in SqlSegments::new(sql, dialect.syntax()) {
+            let class = match segment.kind {
+                SqlSegmentKind::Code => Class::Code,
+                SqlSegmentKind::LineComment => Class::Comment,
+                _ => Class::Verbatim,
+            };
+            for c in &mut classes[segment.offset..segment.offset + segment.text.len()] {
+                *c = match *c {
+                    Some(prev) if prev != class => Some(Class::Verbatim),
+                    _ => Some(class),
+                };
             }
         }
     }
-    
-    (false, end)
+
+    // 去掉注释，并记录可以合并的换行的位置
+    let mut stripped = String::with_capacity(sql.len());
+    let mut breaks = vec![];
+    let mut start = 0;
+    while start < sql.len() {
+        let class = classes[start];
+        let end = classes[start..].iter().position(|c| *c != class).map_or(sql.len(), |len| start + len);
+        match class {
+            Some(Class::Comment) => (),
+            Some(Class::Code) => {
+                for (i, line) in sql[start..end].split('\n').enumerate() {
+                    if i > 0 {
+                        breaks.push(stripped.len());
+                    }
+                    stripped.push_str(line);
+                }
+            }
+            _ => stripped.push_str(&sql[start..end]),
+        }
+        start = end;
+    }
+
+    let mut lines = Vec::with_capacity(breaks.len() + 1);
+    let mut line_start = 0;
+    for line_end in breaks.into_iter().chain(std::iter::once(stripped.len())) {
+        lines.push(stripped[line_start..line_end].trim());
+        line_start = line_end;
+    }
+    lines.join(" ")
 }
 
 #[cfg(test)]
@@ -105,6 +118,21 @@ mod tests {
         assert_eq!(vec!["id", "name"], rst.unwrap());
     }
 
+    #[test]
+    fn test_extract_sql_with_casts_and_literals() {
+        let sql = "select created_at::date, '12:30' from abc -- :a\nwhere id=:id and tag = '\\:'";
+        let mut buf = Vec::with_capacity(sql.len());
+        let rst = extract_params_buf(sql, &mut buf, SqlDialect::mysql);
+        assert_eq!("select created_at::date, '12:30' from abc -- :a\nwhere id=? and tag = '\\:'", std::str::from_utf8(&buf).unwrap());
+        assert_eq!(vec!["id"], rst.unwrap());
+
+        let sql = "select arr[1\\:2] from abc where id=:id";
+        let mut buf = Vec::with_capacity(sql.len());
+        let rst = extract_params_buf(sql, &mut buf, SqlDialect::postgres);
+        assert_eq!("select arr[1:2] from abc where id=$1", std::str::from_utf8(&buf).unwrap());
+        assert_eq!(vec!["id"], rst.unwrap());
+    }
+
     #[test]
     fn test_extract_wrong_parameter() {
         let sql = "select * from abc where id=: id and name=:name order by id";
@@ -123,4 +151,25 @@ mod tests {
             Err(_) => (),
         };
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_join_sql_lines() {
+        let sql = "select * from test_user -- 用户表\n\
+            where name = '--a' -- :name\n\
+            /*+ INDEX(test_user) */ and age > :age\n\
+            -- order by id";
+        assert_eq!(
+            "select * from test_user where name = '--a' /*+ INDEX(test_user) */ and age > :age ",
+            join_sql_lines(sql)
+        );
+
+        assert_eq!("select 'a -- b' , `c--d` from t", join_sql_lines("  select 'a -- b' ,\n  `c--d` from t  "));
+
+        // 多行字符串中的换行及缩进原样保留
+        assert_eq!("select 'a\n  b' from t where id = :id", join_sql_lines("select 'a\n  b' from t\n  where id = :id"));
+
+        // `\` 在 postgres 及 sqlite 中不是转义符，mysql 中则是：注释和换行都保留，不会吞掉之后的 sql
+        let sql = "select * from t where path = 'C:\\' -- note\n  and id = :id";
+        assert_eq!(sql, join_sql_lines(sql));
+    }
+}
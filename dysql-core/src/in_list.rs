@@ -0,0 +1,106 @@
+//! Find list parameters in `IN (:name)`, which are expanded to one placeholder per element while rendering.
+
+use once_cell::sync::OnceCell;
+
+use crate::{SqlDialect, SqlLexer, SqlPiece};
+
+/// 空列表绑定到 `IN (:name)` 时的处理方式，可选值为 `error` (默认) 和 `null`
+pub const EMPTY_IN_LIST_ENV: &str = "DYSQL_EMPTY_IN_LIST";
+
+static EMPTY_IN_LIST_POLICY: OnceCell<EmptyInListPolicy> = OnceCell::new();
+
+/// 空列表绑定到 `IN (:name)` 时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmptyInListPolicy {
+    /// 返回 BindParamterError
+    Error,
+    /// 展开为 `IN (NULL)`，不匹配任何记录
+    Null,
+}
+
+impl EmptyInListPolicy {
+    /// 获取环境变量中配置的处理方式
+    pub fn current() -> Self {
+        *EMPTY_IN_LIST_POLICY.get_or_init(|| match std::env::var(EMPTY_IN_LIST_ENV) {
+            Ok(val) if val.eq_ignore_ascii_case("null") => EmptyInListPolicy::Null,
+            _ => EmptyInListPolicy::Error,
+        })
+    }
+}
+
+/// 判断 sql 中是否有 `IN (:name)` 形式的参数，有的话需要在运行时根据 dto 展开
+pub fn has_in_list_params(named_sql: &str) -> bool {
+    in_list_params(named_sql).next().is_some()
+}
+
+/// 查找 `IN (:name)` 中命名参数的位置，返回 `:` 的下标及参数名的结束下标，
+/// 编译期还不确定数据库类型，按 postgres 的词法跳过字符串及注释
+fn in_list_params(sql: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+    let bytes = sql.as_bytes();
+    SqlLexer::new(sql, SqlDialect::postgres).map_while(Result::ok).filter_map(move |piece| {
+        let (start, end) = match piece {
+            SqlPiece::Param { name, offset } => (offset, offset + 1 + name.len()),
+            SqlPiece::Text(_) => return None,
+        };
+
+        let close = skip_blank(bytes, end);
+        if bytes.get(close) != Some(&b')') {
+            return None;
+        }
+
+        ends_with_in_paren(&sql[..start]).then_some((start, end))
+    })
+}
+
+/// 判断已渲染的 sql 是否以 `IN (` 结尾，即之后的参数位于 `IN (:name)` 中
+pub(crate) fn ends_with_in_paren(sql: &str) -> bool {
+    let bytes = sql.as_bytes();
+    let open = skip_blank_back(bytes, bytes.len());
+    if open == 0 || bytes[open - 1] != b'(' {
+        return false;
+    }
+
+    let kw_end = skip_blank_back(bytes, open - 1);
+    kw_end >= 2
+        && bytes[kw_end - 2..kw_end].eq_ignore_ascii_case(b"in")
+        && (kw_end == 2 || !is_word_byte(bytes[kw_end - 3]))
+}
+
+#[inline]
+fn is_word_byte(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_'
+}
+
+#[inline]
+fn skip_blank(bytes: &[u8], mut idx: usize) -> usize {
+    while idx < bytes.len() && bytes[idx].is_ascii_whitespace() {
+        idx += 1;
+    }
+    idx
+}
+
+#[inline]
+fn skip_blank_back(bytes: &[u8], mut idx: usize) -> usize {
+    while idx > 0 && bytes[idx - 1].is_ascii_whitespace() {
+        idx -= 1;
+    }
+    idx
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_in_list_params() {
+        assert!(has_in_list_params("select * from t where id in (:ids)"));
+        assert!(has_in_list_params("select * from t where id IN(:data.ids )"));
+        assert!(!has_in_list_params("select * from t where id = any(:ids)"));
+        assert!(!has_in_list_params("select * from t where login(:ids)"));
+        assert!(!has_in_list_params("select * from t where id in (:a, :b)"));
+        assert!(!has_in_list_params("select * from t where id = 1 -- or id in (:ids)"));
+
+        assert!(ends_with_in_paren("select * from t where id IN ("));
+        assert!(!ends_with_in_paren("select * from t where login("));
+    }
+}
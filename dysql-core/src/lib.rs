@@ -3,18 +3,31 @@
 // #![feature(proc_macro_hygiene)]
 #![allow(async_fn_in_trait)]
 mod extract_sql;
+mod sql_lexer;
+mod in_list;
+mod sql_params;
 mod sql_dialect;
 mod error;
 mod dysql_context;
 mod utils;
+mod schema;
+mod tuple_row;
+mod static_sql;
+mod sql_override;
 mod adapter;
 mod dto;
 
 pub use extract_sql::*;
+pub use sql_lexer::*;
+pub use in_list::*;
 pub use sql_dialect::*;
 pub use error::*;
 pub use dysql_context::*;
 pub use utils::*;
+pub use schema::*;
+pub use tuple_row::*;
+pub use static_sql::*;
+pub use sql_override::*;
 
 #[allow(unused_imports)]
 pub use adapter::*;
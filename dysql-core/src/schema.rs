@@ -0,0 +1,664 @@
+//! Validate sql templates against an offline database schema snapshot.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+use dysql_tpl::{SqlSegmentKind, SqlSegments, Template};
+use dysql_tpl::sql_syntax::{is_ident_char, param_end};
+use serde::{Serialize, Deserialize};
+
+use crate::{SqlDialect, DySqlError, ErrorInner, Kind, DySqlResult};
+
+/// 模板中的 section 数不超过该值时穷举所有组合，否则只校验有限的几种组合
+const MAX_FULL_VARIANT_SECTIONS: usize = 10;
+
+/// 渲染 sql 组合时用于替代模板变量的值
+const VARIANT_PLACEHOLDER: &str = "1";
+
+const KEYWORDS: &[&str] = &[
+    "select", "from", "where", "and", "or", "not", "in", "is", "null", "as", "on", "using",
+    "join", "inner", "left", "right", "full", "outer", "cross", "natural", "lateral",
+    "group", "by", "having", "order", "asc", "desc", "nulls", "first", "last", "limit", "offset", "fetch", "next",
+    "union", "intersect", "except", "all", "distinct", "any", "some", "exists", "between", "like", "ilike",
+    "similar", "escape", "glob", "regexp", "rlike", "case", "when", "then", "else", "end",
+    "insert", "into", "values", "update", "set", "delete", "returning", "default", "replace", "ignore",
+    "conflict", "do", "nothing", "duplicate", "key", "primary", "with", "recursive",
+    "true", "false", "unknown", "cast", "collate", "interval", "at", "time", "zone",
+    "over", "partition", "window", "rows", "range", "preceding", "following", "unbounded", "current", "row",
+    "filter", "within", "for", "share", "nowait", "skip", "locked", "of", "only", "to", "div", "mod", "xor",
+    "int", "integer", "bigint", "smallint", "tinyint", "text", "varchar", "char", "character", "varying",
+    "boolean", "bool", "numeric", "decimal", "real", "double", "precision", "float", "date", "datetime",
+    "timestamp", "timestamptz", "uuid", "json", "jsonb", "bytea", "blob", "signed", "unsigned", "binary",
+    "current_timestamp", "current_date", "current_time", "localtime", "localtimestamp", "current_user",
+    "year", "month", "day", "hour", "minute", "second", "epoch", "leading", "trailing", "both",
+];
+
+/// extract(year from ..) 等函数参数中会出现 from 关键字
+const DATE_FIELDS: &[&str] = &["year", "month", "day", "hour", "minute", "second", "epoch"];
+
+/// 数据库表结构快照，在编译期用于校验 sql 中的表名、列名以及占位符
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SchemaSnapshot {
+    pub dialect: String,
+    /// 表名 -> 列名
+    pub tables: BTreeMap<String, Vec<String>>,
+}
+
+impl SchemaSnapshot {
+    pub fn new(dialect: SqlDialect) -> Self {
+        Self {
+            dialect: dialect.to_string(),
+            tables: BTreeMap::new(),
+        }
+    }
+
+    /// 添加一个表字段，表名和字段名都按小写保存
+    pub fn add_column(&mut self, table: &str, column: &str) {
+        let columns = self.tables.entry(table.to_ascii_lowercase()).or_default();
+        let column = column.to_ascii_lowercase();
+        if !columns.contains(&column) {
+            columns.push(column);
+        }
+    }
+
+    pub fn get_dialect(&self) -> SqlDialect {
+        SqlDialect::from(self.dialect.as_str())
+    }
+
+    /// 从 json 文件中加载快照
+    pub fn load<P: AsRef<Path>>(path: P) -> DySqlResult<Self> {
+        let content = std::fs::read_to_string(path.as_ref())
+            .map_err(|e| DySqlError(ErrorInner::new(Kind::SchemaError, Some(Box::new(e)), None)))?;
+
+        serde_json::from_str(&content)
+            .map_err(|e| DySqlError(ErrorInner::new(Kind::SchemaError, Some(Box::new(e)), None)))
+    }
+
+    /// 将快照保存为 json 文件
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> DySqlResult<()> {
+        if let Some(parent) = path.as_ref().parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| DySqlError(ErrorInner::new(Kind::SchemaError, Some(Box::new(e)), None)))?;
+        }
+
+        let content = serde_json::to_string_pretty(self)
+            .map_err(|e| DySqlError(ErrorInner::new(Kind::SchemaError, Some(Box::new(e)), None)))?;
+
+        std::fs::write(path.as_ref(), content)
+            .map_err(|e| DySqlError(ErrorInner::new(Kind::SchemaError, Some(Box::new(e)), None)))
+    }
+
+    /// 从 sqlx postgres 连接中抓取表结构快照
+    #[cfg(feature = "sqlx-postgres")]
+    pub async fn capture_sqlx_postgres<'e, E>(executor: E) -> DySqlResult<Self>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Postgres>,
+    {
+        let rows = sqlx::query_as::<_, (String, String)>(
+            "SELECT table_name::text, column_name::text FROM information_schema.columns \
+            WHERE table_schema NOT IN ('pg_catalog', 'information_schema') \
+            ORDER BY table_name, ordinal_position"
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| DySqlError(ErrorInner::new(Kind::QueryError, Some(Box::new(e)), None)))?;
+
+        Ok(Self::from_rows(SqlDialect::postgres, rows))
+    }
+
+    /// 从 sqlx mysql 连接中抓取表结构快照
+    #[cfg(feature = "sqlx-mysql")]
+    pub async fn capture_sqlx_mysql<'e, E>(executor: E) -> DySqlResult<Self>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::MySql>,
+    {
+        let rows = sqlx::query_as::<_, (String, String)>(
+            "SELECT CAST(table_name AS CHAR), CAST(column_name AS CHAR) FROM information_schema.columns \
+            WHERE table_schema = DATABASE() \
+            ORDER BY table_name, ordinal_position"
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| DySqlError(ErrorInner::new(Kind::QueryError, Some(Box::new(e)), None)))?;
+
+        Ok(Self::from_rows(SqlDialect::mysql, rows))
+    }
+
+    /// 从 sqlx sqlite 连接中抓取表结构快照
+    #[cfg(feature = "sqlx-sqlite")]
+    pub async fn capture_sqlx_sqlite<'e, E>(executor: E) -> DySqlResult<Self>
+    where
+        E: sqlx::Executor<'e, Database = sqlx::Sqlite>,
+    {
+        let rows = sqlx::query_as::<_, (String, String)>(
+            "SELECT m.name, p.name FROM sqlite_master m JOIN pragma_table_info(m.name) p \
+            WHERE m.type IN ('table', 'view') AND m.name NOT LIKE 'sqlite_%' \
+            ORDER BY m.name, p.cid"
+        )
+        .fetch_all(executor)
+        .await
+        .map_err(|e| DySqlError(ErrorInner::new(Kind::QueryError, Some(Box::new(e)), None)))?;
+
+        Ok(Self::from_rows(SqlDialect::sqlite, rows))
+    }
+
+    /// 从 tokio-postgres 连接中抓取表结构快照
+    #[cfg(feature = "tokio-postgres")]
+    pub async fn capture_tokio_pg(client: &tokio_postgres::Client) -> DySqlResult<Self> {
+        let rows = client
+            .query(
+                "SELECT table_name::text, column_name::text FROM information_schema.columns \
+                WHERE table_schema NOT IN ('pg_catalog', 'information_schema') \
+                ORDER BY table_name, ordinal_position",
+                &[]
+            )
+            .await
+            .map_err(|e| DySqlError(ErrorInner::new(Kind::QueryError, Some(Box::new(e)), None)))?;
+
+        let rows = rows.iter().map(|row| (row.get(0), row.get(1))).collect();
+
+        Ok(Self::from_rows(SqlDialect::postgres, rows))
+    }
+
+    #[allow(dead_code)]
+    fn from_rows(dialect: SqlDialect, rows: Vec<(String, String)>) -> Self {
+        let mut schema = Self::new(dialect);
+        for (table, column) in rows {
+            schema.add_column(&table, &column);
+        }
+
+        schema
+    }
+
+    /// 校验模板所能渲染出的各种 sql 组合，返回的错误信息中包含出错的 sql
+    pub fn check_template(&self, template: &Template) -> Result<(), String> {
+        let names = template.section_names();
+        let sec_len = names.len();
+
+        let mut variants: Vec<Vec<bool>> = Vec::new();
+        if sec_len <= MAX_FULL_VARIANT_SECTIONS {
+            for bits in 0..(1usize << sec_len) {
+                variants.push((0..sec_len).map(|i| bits & (1 << i) != 0).collect());
+            }
+        } else {
+            // section 过多时只校验全开、全关以及逐个关闭的组合
+            variants.push(vec![true; sec_len]);
+            variants.push(vec![false; sec_len]);
+            for i in 0..sec_len {
+                let mut variant = vec![true; sec_len];
+                variant[i] = false;
+                variants.push(variant);
+            }
+        }
+
+        let mut checked = HashSet::new();
+        for variant in variants {
+            let sql = template.render_sql_variant(
+                |name| names.iter().position(|n| *n == name).map(|i| variant[i]).unwrap_or(false),
+                VARIANT_PLACEHOLDER
+            );
+
+            if !checked.insert(sql.clone()) {
+                continue;
+            }
+
+            self.check_sql(&sql).map_err(|e| format!("{}\n    sql: {}", e, sql))?;
+        }
+
+        Ok(())
+    }
+
+    /// 校验一条带命名参数的 sql
+    pub fn check_sql(&self, named_sql: &str) -> Result<(), String> {
+        let dialect = self.get_dialect();
+        let tokens = lex(named_sql, &dialect);
+
+        check_placeholders(&tokens)?;
+        check_insert_values(&tokens)?;
+        self.check_tables_and_columns(&tokens)
+    }
+
+    fn check_tables_and_columns(&self, tokens: &[SqlToken]) -> Result<(), String> {
+        let mut cte_names: HashSet<String> = HashSet::new();
+        let mut aliases: HashSet<String> = HashSet::new();
+        // 别名 -> 表名
+        let mut table_aliases: HashMap<String, String> = HashMap::new();
+        let mut tables: Vec<String> = Vec::new();
+        let mut has_unknown_source = false;
+        let mut consumed: HashSet<usize> = HashSet::new();
+
+        // 收集 cte 名称及 `as` 别名
+        for (i, token) in tokens.iter().enumerate() {
+            if let SqlToken::Ident(name, _) = token {
+                let next_is_as = matches!(tokens.get(i + 1), Some(t) if t.is_keyword("as"));
+                let next2_is_paren = matches!(tokens.get(i + 2), Some(SqlToken::Punct('(')));
+                if next_is_as && next2_is_paren {
+                    cte_names.insert(name.clone());
+                    continue;
+                }
+            }
+            if token.is_keyword("as") {
+                if let Some(SqlToken::Ident(name, _)) = tokens.get(i + 1) {
+                    if !matches!(tokens.get(i + 2), Some(SqlToken::Punct('('))) {
+                        aliases.insert(name.clone());
+                    }
+                }
+            }
+        }
+
+        // 收集 from / join / into / update 后的表
+        let mut parens: Vec<bool> = Vec::new(); // true 表示子查询的括号
+        let mut i = 0;
+        while i < tokens.len() {
+            match &tokens[i] {
+                SqlToken::Punct('(') => {
+                    let is_subquery = matches!(tokens.get(i + 1), Some(t) if t.is_keyword("select") || t.is_keyword("with"));
+                    parens.push(is_subquery);
+                }
+                SqlToken::Punct(')') => {
+                    parens.pop();
+                }
+                token if token.is_keyword("from") || token.is_keyword("join") || token.is_keyword("into") || token.is_keyword("update") => {
+                    let in_expression = matches!(parens.last(), Some(false));
+                    let prev_is_date_field = i > 0 && DATE_FIELDS.iter().any(|f| tokens[i - 1].is_keyword(f));
+                    let prev_is_key = i > 0 && tokens[i - 1].is_keyword("key");
+                    if !(token.is_keyword("from") && (in_expression || prev_is_date_field)) && !prev_is_key {
+                        let is_from = token.is_keyword("from");
+                        let allow_function = is_from || token.is_keyword("join");
+                        let mut cur = i + 1;
+                        loop {
+                            cur = self.parse_table_ref(tokens, cur, allow_function, &cte_names, &mut tables, &mut table_aliases, &mut has_unknown_source, &mut consumed)?;
+                            if is_from && matches!(tokens.get(cur), Some(SqlToken::Punct(','))) {
+                                cur += 1;
+                            } else {
+                                break;
+                            }
+                        }
+                    }
+                }
+                _ => (),
+            }
+            i += 1;
+        }
+
+        // 校验字段
+        for (i, token) in tokens.iter().enumerate() {
+            if consumed.contains(&i) {
+                continue;
+            }
+
+            let (name, quoted) = match token {
+                SqlToken::Ident(name, quoted) => (name, *quoted),
+                _ => continue,
+            };
+            if !quoted && KEYWORDS.contains(&name.as_str()) {
+                continue;
+            }
+
+            let prev = if i > 0 { tokens.get(i - 1) } else { None };
+            let next = tokens.get(i + 1);
+
+            // 函数调用
+            if matches!(next, Some(SqlToken::Punct('('))) {
+                continue;
+            }
+            // 别名定义及类型转换
+            if matches!(prev, Some(t) if t.is_keyword("as")) || matches!(prev, Some(SqlToken::Cast)) {
+                continue;
+            }
+            // 隐式别名: `count(*) cnt`, `name n`
+            if matches!(prev, Some(SqlToken::Punct(')')) | Some(SqlToken::Number) | Some(SqlToken::Str))
+                || matches!(prev, Some(SqlToken::Ident(p, q)) if *q || !KEYWORDS.contains(&p.as_str()))
+            {
+                continue;
+            }
+
+            // 带限定名的字段 `alias.column`
+            if matches!(next, Some(SqlToken::Punct('.'))) {
+                let column_idx = i + 2;
+                let table = table_aliases.get(name).cloned().or_else(|| {
+                    if tables.contains(name) { Some(name.clone()) } else { None }
+                });
+                if let (Some(table), Some(SqlToken::Ident(column, _))) = (table, tokens.get(column_idx)) {
+                    if let Some(columns) = self.tables.get(&table) {
+                        if !columns.contains(column) {
+                            return Err(format!("column `{}` does not exist in table `{}`", column, table));
+                        }
+                    }
+                }
+                consumed.insert(column_idx);
+                continue;
+            }
+            if matches!(prev, Some(SqlToken::Punct('.'))) {
+                continue;
+            }
+
+            if aliases.contains(name) || cte_names.contains(name) || table_aliases.contains_key(name) {
+                continue;
+            }
+            if has_unknown_source || tables.is_empty() {
+                continue;
+            }
+
+            let exists = tables.iter()
+                .filter_map(|t| self.tables.get(t))
+                .any(|columns| columns.contains(name));
+            if !exists {
+                return Err(format!("column `{}` does not exist in table(s): {}", name, tables.join(", ")));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 解析一个表引用，返回解析后的位置
+    #[allow(clippy::too_many_arguments)]
+    fn parse_table_ref(
+        &self,
+        tokens: &[SqlToken],
+        mut cur: usize,
+        allow_function: bool,
+        cte_names: &HashSet<String>,
+        tables: &mut Vec<String>,
+        table_aliases: &mut HashMap<String, String>,
+        has_unknown_source: &mut bool,
+        consumed: &mut HashSet<usize>,
+    ) -> Result<usize, String> {
+        let mut table = match tokens.get(cur) {
+            Some(SqlToken::Ident(name, quoted)) if *quoted || !KEYWORDS.contains(&name.as_str()) => name.clone(),
+            Some(SqlToken::Punct('(')) => {
+                // 子查询，其中的字段无法确定
+                *has_unknown_source = true;
+                return Ok(cur);
+            }
+            _ => return Ok(cur),
+        };
+        consumed.insert(cur);
+        cur += 1;
+
+        // schema.table
+        while let (Some(SqlToken::Punct('.')), Some(SqlToken::Ident(name, _))) = (tokens.get(cur), tokens.get(cur + 1)) {
+            table = name.clone();
+            consumed.insert(cur + 1);
+            cur += 2;
+        }
+
+        // from / join 后的表函数，如: generate_series(1, 10)
+        if allow_function && matches!(tokens.get(cur), Some(SqlToken::Punct('('))) {
+            *has_unknown_source = true;
+            return Ok(cur);
+        }
+
+        if cte_names.contains(&table) {
+            *has_unknown_source = true;
+        } else if !self.tables.contains_key(&table) {
+            return Err(format!("table `{}` does not exist in schema snapshot", table));
+        } else if !tables.contains(&table) {
+            tables.push(table.clone());
+        }
+
+        // 别名
+        let alias_idx = if matches!(tokens.get(cur), Some(t) if t.is_keyword("as")) { cur + 1 } else { cur };
+        if let Some(SqlToken::Ident(alias, quoted)) = tokens.get(alias_idx) {
+            if *quoted || !KEYWORDS.contains(&alias.as_str()) {
+                table_aliases.insert(alias.clone(), table);
+                consumed.insert(alias_idx);
+                cur = alias_idx + 1;
+            }
+        }
+
+        Ok(cur)
+    }
+}
+
+/// 命名参数与原生占位符不能混用，否则绑定的参数个数与占位符个数不一致
+fn check_placeholders(tokens: &[SqlToken]) -> Result<(), String> {
+    let named = tokens.iter().filter(|t| matches!(t, SqlToken::Param)).count();
+    let positional = tokens.iter().filter(|t| matches!(t, SqlToken::Positional)).count();
+
+    if positional > 0 {
+        return Err(format!(
+            "placeholder count mismatch: {} named parameter(s) are bound but the sql has {} placeholder(s), use `:name` instead of positional placeholders",
+            named,
+            named + positional
+        ));
+    }
+
+    Ok(())
+}
+
+/// 校验 `insert into t (..) values (..)` 中字段数与值的个数是否一致
+fn check_insert_values(tokens: &[SqlToken]) -> Result<(), String> {
+    let mut i = 0;
+    while i < tokens.len() {
+        if !tokens[i].is_keyword("insert") {
+            i += 1;
+            continue;
+        }
+
+        // 找到字段列表
+        let mut cur = i + 1;
+        while cur < tokens.len() && !matches!(tokens[cur], SqlToken::Punct('(')) && !tokens[cur].is_keyword("values") && !tokens[cur].is_keyword("select") {
+            cur += 1;
+        }
+        if !matches!(tokens.get(cur), Some(SqlToken::Punct('('))) {
+            i = cur;
+            continue;
+        }
+        let (column_count, end) = count_items(tokens, cur);
+        cur = end;
+
+        if !matches!(tokens.get(cur), Some(t) if t.is_keyword("values")) {
+            i = cur;
+            continue;
+        }
+        cur += 1;
+
+        // 可能有多组 values
+        while matches!(tokens.get(cur), Some(SqlToken::Punct('('))) {
+            let (value_count, end) = count_items(tokens, cur);
+            if value_count != column_count {
+                return Err(format!(
+                    "placeholder count mismatch: insert has {} column(s) but {} value(s)",
+                    column_count,
+                    value_count
+                ));
+            }
+            cur = end;
+            if matches!(tokens.get(cur), Some(SqlToken::Punct(','))) {
+                cur += 1;
+            } else {
+                break;
+            }
+        }
+        i = cur;
+    }
+
+    Ok(())
+}
+
+/// 统计从 `(` 开始的括号内顶层元素个数，返回 (个数, 括号结束后的位置)
+fn count_items(tokens: &[SqlToken], start: usize) -> (usize, usize) {
+    let mut depth = 0;
+    let mut count = 0;
+    let mut has_item = false;
+    let mut cur = start;
+
+    while cur < tokens.len() {
+        match tokens[cur] {
+            SqlToken::Punct('(') => {
+                depth += 1;
+                if depth > 1 { has_item = true }
+            }
+            SqlToken::Punct(')') => {
+                depth -= 1;
+                if depth == 0 {
+                    if has_item { count += 1 }
+                    return (count, cur + 1);
+                }
+            }
+            SqlToken::Punct(',') if depth == 1 => {
+                count += 1;
+                has_item = false;
+            }
+            _ => has_item = true,
+        }
+        cur += 1;
+    }
+
+    (count, cur)
+}
+
+#[derive(Debug, PartialEq)]
+enum SqlToken {
+    /// (小写的名称, 是否为引号中的标识符)
+    Ident(String, bool),
+    Str,
+    Number,
+    /// `:name` 命名参数
+    Param,
+    /// `?` 或 `$1` 原生占位符
+    Positional,
+    /// `::` 类型转换
+    Cast,
+    Punct(char),
+}
+
+impl SqlToken {
+    fn is_keyword(&self, keyword: &str) -> bool {
+        matches!(self, SqlToken::Ident(name, false) if name == keyword)
+    }
+}
+
+fn is_ident_start(c: u8) -> bool {
+    c.is_ascii_alphabetic() || c == b'_' || c >= 0x80
+}
+
+/// 按方言的词法规则切分出字符串、引号中的标识符及注释，与渲染 sql 时识别参数的规则一致，只对代码部分分词
+fn lex(sql: &str, dialect: &SqlDialect) -> Vec<SqlToken> {
+    let mut tokens = Vec::new();
+
+    for segment in SqlSegments::new(sql, dialect.syntax()) {
+        match segment.kind {
+            SqlSegmentKind::Code => lex_code(segment.text, dialect, &mut tokens),
+            SqlSegmentKind::Str => tokens.push(SqlToken::Str),
+            SqlSegmentKind::QuotedIdent => {
+                let quote = segment.text.as_bytes()[0] as char;
+                let name = &segment.text[1..];
+                let name = name.strip_suffix(quote).unwrap_or(name);
+                tokens.push(SqlToken::Ident(name.to_ascii_lowercase(), true));
+            }
+            SqlSegmentKind::LineComment | SqlSegmentKind::BlockComment => (),
+        }
+    }
+
+    tokens
+}
+
+fn lex_code(sql: &str, dialect: &SqlDialect, tokens: &mut Vec<SqlToken>) {
+    let bytes = sql.as_bytes();
+    let len = bytes.len();
+    let mut i = 0;
+
+    while i < len {
+        let c = bytes[i];
+        match c {
+            b' ' | b'\n' | b'\t' | b'\r' => i += 1,
+            b':' if i + 1 < len && bytes[i + 1] == b':' => {
+                tokens.push(SqlToken::Cast);
+                i += 2;
+            }
+            b':' if i + 1 < len && is_ident_start(bytes[i + 1]) => {
+                i = param_end(bytes, i + 1);
+                tokens.push(SqlToken::Param);
+            }
+            b'?' if *dialect != SqlDialect::postgres => {
+                tokens.push(SqlToken::Positional);
+                i += 1;
+            }
+            b'$' if i + 1 < len && bytes[i + 1].is_ascii_digit() => {
+                i += 1;
+                while i < len && bytes[i].is_ascii_digit() { i += 1 }
+                tokens.push(SqlToken::Positional);
+            }
+            c if c.is_ascii_digit() => {
+                while i < len && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'.') { i += 1 }
+                tokens.push(SqlToken::Number);
+            }
+            c if is_ident_start(c) => {
+                let start = i;
+                while i < len && (is_ident_char(bytes[i]) || bytes[i] == b'$') { i += 1 }
+                tokens.push(SqlToken::Ident(sql[start..i].to_ascii_lowercase(), false));
+            }
+            c => {
+                tokens.push(SqlToken::Punct(c as char));
+                i += 1;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot() -> SchemaSnapshot {
+        let mut schema = SchemaSnapshot::new(SqlDialect::postgres);
+        for column in ["id", "name", "age"] {
+            schema.add_column("test_user", column);
+        }
+        for column in ["id", "user_id", "title"] {
+            schema.add_column("post", column);
+        }
+        schema
+    }
+
+    #[test]
+    fn test_check_sql() {
+        let schema = snapshot();
+        schema.check_sql("select * from test_user where name = :name and age > :age order by id").unwrap();
+        schema.check_sql("select u.name, p.title from test_user u join post as p on u.id = p.user_id").unwrap();
+        schema.check_sql("select count(*) as cnt from test_user where id in (select user_id from post) order by cnt").unwrap();
+        schema.check_sql("select name from test_user where id::text = :id and name like '%' || :name || '%'").unwrap();
+        schema.check_sql("insert into test_user (name, age) values (:name, :age) returning id").unwrap();
+        schema.check_sql("update test_user set name = :name where id = :id").unwrap();
+
+        let rst = schema.check_sql("select * from test_users");
+        assert!(rst.unwrap_err().contains("table `test_users`"));
+
+        let rst = schema.check_sql("select nmae from test_user");
+        assert!(rst.unwrap_err().contains("column `nmae`"));
+
+        let rst = schema.check_sql("select u.nmae from test_user u");
+        assert!(rst.unwrap_err().contains("column `nmae` does not exist in table `test_user`"));
+    }
+
+    #[test]
+    fn test_check_placeholders() {
+        let mut schema = snapshot();
+
+        let rst = schema.check_sql("select * from test_user where id = $1 and name = :name");
+        assert!(rst.unwrap_err().contains("placeholder count mismatch"));
+
+        let rst = schema.check_sql("insert into test_user (name, age) values (:name)");
+        assert!(rst.unwrap_err().contains("2 column(s) but 1 value(s)"));
+
+        // 字符串及注释按方言的词法规则识别，其中的占位符不参与检查
+        schema.check_sql("select * from test_user where name = $tag$ $1 ? $tag$ and id = :id -- $2").unwrap();
+        schema.dialect = SqlDialect::mysql.to_string();
+        schema.check_sql("select * from test_user where name = 'it\\'s ?' and id = :id").unwrap();
+    }
+
+    #[test]
+    fn test_check_template() {
+        let schema = snapshot();
+        let template = Template::new(
+            "select * from test_user where 1 = 1 {{#name}}and name = :name{{/name}} {{#age}}and agee > :age{{/age}}"
+        ).unwrap();
+
+        let rst = schema.check_template(&template);
+        let err = rst.unwrap_err();
+        assert!(err.contains("column `agee`"));
+        assert!(err.contains("sql: select * from test_user where 1 = 1 and agee > :age"));
+    }
+}
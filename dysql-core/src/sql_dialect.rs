@@ -1,7 +1,9 @@
 use std::fmt::{Display, Formatter};
 
+use dysql_tpl::SqlSyntax;
+
 #[allow(non_camel_case_types)]
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum SqlDialect {
     postgres,
     mysql,
@@ -33,4 +35,56 @@ impl PartialEq<String> for SqlDialect {
     fn eq(&self, other: &String) -> bool {
         *other == self.to_string()
     }
-}
\ No newline at end of file
+}
+impl SqlDialect {
+    /// 渲染 sql 模板时使用的词法规则
+    pub fn syntax(&self) -> SqlSyntax {
+        match self {
+            SqlDialect::postgres => SqlSyntax::Postgres,
+            SqlDialect::mysql => SqlSyntax::Mysql,
+            SqlDialect::sqlite => SqlSyntax::Sqlite,
+        }
+    }
+
+    /// 生成 upsert 语句中处理冲突的子句，`conflict` 为冲突的键列，`update` 为冲突时需要更新的列，
+    /// `update` 为空时 postgres 和 sqlite 忽略冲突的记录，mysql 则不更新任何列
+    pub fn upsert_clause(&self, conflict: &[&str], update: &[&str]) -> String {
+        match self {
+            SqlDialect::mysql => {
+                let sets: Vec<String> = if update.is_empty() {
+                    conflict.iter().take(1).map(|col| format!("{} = {}", col, col)).collect()
+                } else {
+                    update.iter().map(|col| format!("{} = VALUES({})", col, col)).collect()
+                };
+                format!("ON DUPLICATE KEY UPDATE {}", sets.join(", "))
+            },
+            SqlDialect::postgres | SqlDialect::sqlite => {
+                if update.is_empty() {
+                    format!("ON CONFLICT ({}) DO NOTHING", conflict.join(", "))
+                } else {
+                    let sets: Vec<String> = update.iter().map(|col| format!("{} = EXCLUDED.{}", col, col)).collect();
+                    format!("ON CONFLICT ({}) DO UPDATE SET {}", conflict.join(", "), sets.join(", "))
+                }
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_upsert_clause() {
+        assert_eq!(
+            "ON CONFLICT (id) DO UPDATE SET name = EXCLUDED.name, age = EXCLUDED.age",
+            SqlDialect::postgres.upsert_clause(&["id"], &["name", "age"])
+        );
+        assert_eq!("ON CONFLICT (id, name) DO NOTHING", SqlDialect::sqlite.upsert_clause(&["id", "name"], &[]));
+        assert_eq!(
+            "ON DUPLICATE KEY UPDATE name = VALUES(name), age = VALUES(age)",
+            SqlDialect::mysql.upsert_clause(&["id"], &["name", "age"])
+        );
+        assert_eq!("ON DUPLICATE KEY UPDATE id = id", SqlDialect::mysql.upsert_clause(&["id"], &[]));
+    }
+}
@@ -0,0 +1,174 @@
+//! Split the named sql into plain text and `:name` parameters.
+
+use dysql_tpl::{SqlScanner, SqlState, sql_syntax::param_end};
+
+use crate::{SqlDialect, ParseSqlError};
+
+/// 切分后的 sql 片段
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlPiece<'a> {
+    /// 原样输出的 sql，字符串、引号中的标识符、注释及 `::` 类型转换都不会被切开
+    Text(&'a str),
+    /// `:name` 命名参数，`offset` 为 `:` 在 sql 中的字节偏移量
+    Param { name: &'a str, offset: usize },
+}
+
+/// 按 sql 的词法规则查找命名参数，跳过以下内容中的 `:`：
+///
+/// + `'..'` 字符串 (mysql 及 postgres 的 `E'..'` 中支持 `\` 转义)，`".."` 及 `` `..` `` 引号中的标识符
+/// + `-- ..` 单行注释，`/* .. */` 多行注释 (postgres 中可以嵌套)，mysql 的 `# ..` 单行注释
+/// + postgres 的 `$tag$ .. $tag$` 字符串
+/// + postgres 的 `::` 类型转换
+///
+/// 其它位置需要输出 `:` 时写作 `\:`，`\` 会被去掉
+pub struct SqlLexer<'a> {
+    sql: &'a str,
+    scanner: SqlScanner,
+    pos: usize,
+    text_start: usize,
+}
+
+impl<'a> SqlLexer<'a> {
+    pub fn new(sql: &'a str, dialect: SqlDialect) -> Self {
+        Self { sql, scanner: SqlScanner::new(dialect.syntax()), pos: 0, text_start: 0 }
+    }
+
+    fn error(&mut self, msg: &str, offset: usize) -> ParseSqlError {
+        // 出错后不再继续切分
+        self.pos = self.sql.len();
+        self.text_start = self.sql.len();
+        ParseSqlError { msg: msg.to_owned(), offset }
+    }
+
+    /// 返回 `start` 处的内容中不含参数的部分的结束位置
+    fn skip_at(&mut self, start: usize) -> Result<usize, (&'static str, usize)> {
+        let bytes = self.sql.as_bytes();
+        if bytes[start] == b':' && bytes.get(start + 1) == Some(&b':') {
+            // `::` 后可能紧跟着类型名，连续的 `:` 一起跳过
+            return Ok(start + bytes[start..].iter().take_while(|c| **c == b':').count());
+        }
+
+        let len = self.scanner.open_literal(bytes, start);
+        if self.scanner.is_code() {
+            return Ok(start + len);
+        }
+
+        let end = self.scanner.close_literal(bytes, start + len);
+        let msg = match self.scanner.state() {
+            // 单行注释可以在 sql 结尾处结束
+            SqlState::Code | SqlState::LineComment => return Ok(end),
+            SqlState::Quoted { quote: b'\'', .. } => "unterminated string literal",
+            SqlState::Quoted { .. } => "unterminated quoted identifier",
+            SqlState::BlockComment(_) => "unterminated block comment",
+            SqlState::DollarQuoted => "unterminated dollar-quoted string",
+        };
+
+        Err((msg, start))
+    }
+}
+
+impl<'a> Iterator for SqlLexer<'a> {
+    type Item = Result<SqlPiece<'a>, ParseSqlError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bytes = self.sql.as_bytes();
+        let len = bytes.len();
+
+        let mut i = self.pos;
+        while i < len {
+            let is_escape = bytes[i] == b'\\' && bytes.get(i + 1) == Some(&b':');
+            let is_param = bytes[i] == b':' && bytes.get(i + 1) != Some(&b':');
+            if !is_escape && !is_param {
+                i = match self.skip_at(i) {
+                    Ok(end) => end,
+                    Err((msg, offset)) => return Some(Err(self.error(msg, offset))),
+                };
+                continue;
+            }
+
+            // 先输出参数前的 sql
+            if self.text_start < i {
+                let text = &self.sql[self.text_start..i];
+                self.pos = i;
+                self.text_start = i;
+                return Some(Ok(SqlPiece::Text(text)));
+            }
+
+            if is_escape {
+                self.pos = i + 2;
+                self.text_start = i + 2;
+                return Some(Ok(SqlPiece::Text(&self.sql[i + 1..i + 2])));
+            }
+
+            let end = param_end(bytes, i + 1);
+            if end == i + 1 {
+                return Some(Err(self.error("not found named parameter after ':', use '\\:' for a literal ':'", i)));
+            }
+            self.pos = end;
+            self.text_start = end;
+            return Some(Ok(SqlPiece::Param { name: &self.sql[i + 1..end], offset: i }));
+        }
+
+        self.pos = len;
+        if self.text_start < len {
+            let text = &self.sql[self.text_start..];
+            self.text_start = len;
+            return Some(Ok(SqlPiece::Text(text)));
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn params(sql: &str, dialect: SqlDialect) -> Result<Vec<&str>, ParseSqlError> {
+        SqlLexer::new(sql, dialect)
+            .filter_map(|piece| match piece {
+                Ok(SqlPiece::Param { name, .. }) => Some(Ok(name)),
+                Ok(SqlPiece::Text(_)) => None,
+                Err(e) => Some(Err(e)),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_sql_lexer() {
+        let sql = "select created_at::date, '12:30' as \"a:b\" from t -- :c\n where /* :d /* :e */ :f */ id = :id and name like :data.name||'%'";
+        assert_eq!(vec!["id", "data.name"], params(sql, SqlDialect::postgres).unwrap());
+
+        let sql = "select $$ :a $$, $tag$ :b $$ $tag$, E'\\' :c', :ids.0 from t where x = $1";
+        assert_eq!(vec!["ids.0"], params(sql, SqlDialect::postgres).unwrap());
+
+        let sql = "select 'it\\'s :a', `:b` from t # :c\n where id = :id";
+        assert_eq!(vec!["id"], params(sql, SqlDialect::mysql).unwrap());
+
+        let pieces: Vec<_> = SqlLexer::new("select arr[1\\:2] from t where id = :id.", SqlDialect::postgres)
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            vec![
+                SqlPiece::Text("select arr[1"),
+                SqlPiece::Text(":"),
+                SqlPiece::Text("2] from t where id = "),
+                SqlPiece::Param { name: "id", offset: 35 },
+                SqlPiece::Text("."),
+            ],
+            pieces
+        );
+    }
+
+    #[test]
+    fn test_sql_lexer_error() {
+        let err = params("select * from t where id = : id", SqlDialect::postgres).unwrap_err();
+        assert_eq!(27, err.offset);
+
+        let err = params("select * from t where name = 'abc", SqlDialect::postgres).unwrap_err();
+        assert_eq!(29, err.offset);
+
+        let err = params("select * from t /* :id", SqlDialect::sqlite).unwrap_err();
+        assert_eq!(16, err.offset);
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::SystemTime;
+
+use log::{trace, warn};
+#[cfg(debug_assertions)]
+use once_cell::sync::OnceCell;
+use dysql_tpl::Template;
+
+use crate::{hash_it, join_sql_lines};
+
+/// 开发模式下用于覆盖编译期 sql 模板的目录，未配置时不启用覆盖
+pub const SQL_OVERRIDE_DIR_ENV: &str = "DYSQL_SQL_OVERRIDE_DIR";
+
+#[cfg(debug_assertions)]
+static SQL_OVERRIDES: OnceCell<Option<SqlOverrides>> = OnceCell::new();
+
+/// 从磁盘加载的 sql 模板，文件变化时重新加载
+#[derive(Debug)]
+struct OverrideEntry {
+    modified: Option<SystemTime>,
+    len: u64,
+    template: Arc<Template>,
+    template_id: u64,
+}
+
+/// 开发模式下的 sql 覆盖：`{dir}/{sql_name}.sql` 或 `{dir}/{template_id}.sql` 中的 sql 会代替编译期生成的模板，
+/// 每次获取模板时检查文件是否变化，这样无需重新编译就可以调整 sql
+#[derive(Debug)]
+pub struct SqlOverrides {
+    dir: PathBuf,
+    entries: RwLock<HashMap<PathBuf, OverrideEntry>>,
+}
+
+impl SqlOverrides {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            entries: Default::default(),
+        }
+    }
+
+    /// 获取覆盖的模板及其模板 id，优先按 sql_name 查找，其次按 template_id 查找
+    pub fn get(&self, template_id: u64, sql_name: Option<&str>) -> Option<(Arc<Template>, u64)> {
+        let by_name = sql_name.map(|name| self.dir.join(format!("{}.sql", name)));
+        let by_id = self.dir.join(format!("{}.sql", template_id));
+
+        by_name.into_iter()
+            .chain(std::iter::once(by_id))
+            .find_map(|path| self.load(&path))
+    }
+
+    fn load(&self, path: &Path) -> Option<(Arc<Template>, u64)> {
+        let meta = match std::fs::metadata(path) {
+            Ok(meta) => meta,
+            Err(_) => {
+                self.entries.write().unwrap().remove(path);
+                return None;
+            }
+        };
+        let modified = meta.modified().ok();
+        let len = meta.len();
+
+        if let Some(entry) = self.entries.read().unwrap().get(path) {
+            if entry.modified == modified && entry.len == len {
+                return Some((entry.template.clone(), entry.template_id));
+            }
+        }
+
+        let sql = match std::fs::read_to_string(path) {
+            Ok(sql) => sql,
+            Err(e) => {
+                warn!("read sql override file {:?} failed: {}", path, e);
+                return None;
+            }
+        };
+        let body = join_sql_lines(&sql);
+        let template = match Template::new(&body) {
+            Ok(tpl) => Arc::new(tpl),
+            Err(e) => {
+                // 解析失败时继续使用编译期的模板
+                warn!("parse sql override file {:?} failed: {}", path, e);
+                return None;
+            }
+        };
+        // 覆盖的模板使用新的 id，避免命中按原 id 缓存的静态 sql
+        let template_id = hash_it(&body);
+        trace!("load sql override: {:?}", path);
+
+        self.entries.write().unwrap().insert(path.to_owned(), OverrideEntry { modified, len, template: template.clone(), template_id });
+
+        Some((template, template_id))
+    }
+}
+
+/// 配置了 DYSQL_SQL_OVERRIDE_DIR 时，获取从磁盘覆盖的 sql 模板，只在 debug 构建下启用
+#[cfg(debug_assertions)]
+pub fn get_override_template(template_id: u64, sql_name: Option<&str>) -> Option<(Arc<Template>, u64)> {
+    SQL_OVERRIDES
+        .get_or_init(|| std::env::var(SQL_OVERRIDE_DIR_ENV).ok().map(SqlOverrides::new))
+        .as_ref()?
+        .get(template_id, sql_name)
+}
+
+/// release 构建下不启用覆盖，始终使用编译期生成的模板
+#[cfg(not(debug_assertions))]
+#[inline(always)]
+pub fn get_override_template(_template_id: u64, _sql_name: Option<&str>) -> Option<(Arc<Template>, u64)> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sql_overrides() {
+        let dir = std::env::temp_dir().join(format!("dysql_override_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let overrides = SqlOverrides::new(&dir);
+
+        assert!(overrides.get(1, Some("find_user")).is_none());
+
+        std::fs::write(dir.join("1.sql"), "select * from test_user\nwhere id = :id").unwrap();
+        let (tpl, id) = overrides.get(1, Some("find_user")).unwrap();
+        assert_eq!("select * from test_user where id = :id", tpl.source());
+        assert_ne!(1, id);
+
+        // sql_name 优先于 template_id
+        std::fs::write(dir.join("find_user.sql"), "select * from test_user where age > :age").unwrap();
+        let (tpl, _) = overrides.get(1, Some("find_user")).unwrap();
+        assert_eq!("select * from test_user where age > :age", tpl.source());
+
+        // 文件变化后重新加载
+        std::fs::write(dir.join("find_user.sql"), "select id, name from test_user where age > :age").unwrap();
+        let (tpl, _) = overrides.get(1, Some("find_user")).unwrap();
+        assert_eq!("select id, name from test_user where age > :age", tpl.source());
+
+        // 同编译期的模板一样去掉 `--` 注释后再合并各行
+        std::fs::write(dir.join("find_user.sql"), "select id, name from test_user -- 只查询 id 和 name\nwhere age > :age").unwrap();
+        let (tpl, _) = overrides.get(1, Some("find_user")).unwrap();
+        assert_eq!("select id, name from test_user where age > :age", tpl.source());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(overrides.get(1, Some("find_user")).is_none());
+    }
+}
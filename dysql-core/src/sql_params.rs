@@ -0,0 +1,90 @@
+//! Write dialect placeholders and collect parameter values while rendering the sql template.
+
+use std::fmt::Write;
+
+use dysql_tpl::{Content, SimpleError, SimpleTemplate, SimpleValue, SqlParams, SqlSyntax, Template};
+
+use crate::{DySqlError, EmptyInListPolicy, ErrorInner, Kind, SqlDialect, ends_with_in_paren};
+
+/// 渲染 sql 模板时遇到 `:name` 命名参数，写入对应方言的占位符并从 dto 中获取参数值，
+/// `IN (:name)` 中绑定为列表的参数按元素个数展开为多个占位符
+pub(crate) struct ParamCollector<'a, D> {
+    pub template: &'a Template,
+    pub dto: &'a D,
+    pub dialect: SqlDialect,
+    pub values: &'a mut Vec<SimpleValue>,
+    /// 按占位符顺序记录的参数名，展开了列表时占位符的个数与参数值有关，置为 None
+    pub names: Option<Vec<String>>,
+}
+
+impl<D: Content> ParamCollector<'_, D> {
+    /// 写入一个占位符并保存参数值
+    fn push_value(&mut self, sql: &mut String, value: SimpleValue) {
+        self.values.push(value);
+        match self.dialect {
+            SqlDialect::postgres => write!(sql, "${}", self.values.len()).unwrap(),
+            _ => sql.push('?'),
+        }
+    }
+}
+
+impl<D: Content> SqlParams for ParamCollector<'_, D> {
+    fn syntax(&self) -> SqlSyntax {
+        self.dialect.syntax()
+    }
+
+    fn write_param(&mut self, sql: &mut String, name: &str) -> Result<(), SimpleError> {
+        let in_list = ends_with_in_paren(sql);
+        if in_list {
+            // `IN (:name)` 中的占位符个数由参数值决定，无论是否绑定了列表，渲染结果都不能缓存
+            self.names = None;
+        }
+
+        match param_value(self.template, self.dto, name)? {
+            SimpleValue::t_list(list) if in_list => {
+                if list.is_empty() {
+                    match EmptyInListPolicy::current() {
+                        EmptyInListPolicy::Null => sql.push_str("NULL"),
+                        EmptyInListPolicy::Error => Err(DySqlError(ErrorInner::new(
+                            Kind::BindParamterError,
+                            None,
+                            Some(format!("empty list is bound to 'IN (:{})'", name)),
+                        )))?,
+                    }
+                }
+                for (idx, value) in list.into_iter().enumerate() {
+                    if idx > 0 {
+                        sql.push_str(", ");
+                    }
+                    self.push_value(sql, value);
+                }
+            }
+            value => {
+                if let Some(names) = self.names.as_mut() {
+                    names.push(name.to_owned());
+                }
+                self.push_value(sql, value)
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// 获取命名参数的值，优先使用模板中预先生成的简化模版
+pub(crate) fn param_value<D: Content>(template: &Template, dto: &D, name: &str) -> Result<SimpleValue, DySqlError> {
+    let value = match template.param_template(name) {
+        Some(stpl) => stpl.apply(dto),
+        None => SimpleTemplate::new(name).apply(dto),
+    };
+
+    value.map_err(|e| DySqlError(ErrorInner::new(Kind::BindParamterError, Some(e), None)))
+}
+
+/// 渲染时返回的错误，`ParamCollector` 返回的 `DySqlError` 原样取出，其它为 sql 中命名参数的语法错误
+pub(crate) fn render_error(e: SimpleError) -> DySqlError {
+    match e.downcast::<DySqlError>() {
+        Ok(e) => *e,
+        Err(e) => DySqlError(ErrorInner::new(Kind::ExtractSqlParamterError, Some(e), None)),
+    }
+}
@@ -0,0 +1,328 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use once_cell::sync::OnceCell;
+use dysql_tpl::{Template, Content, SimpleValue, SqlParams, SqlShape};
+
+use crate::{CursorDto, DySqlError, ErrorInner, Kind, SqlDialect, SqlLexer, SqlPiece, extract_params_buf};
+use crate::sql_params::{ParamCollector, param_value, render_error};
+
+/// 不含任何模板标签的 sql，在编译期就生成了各数据库方言下的最终 sql 及有序的参数名
+#[derive(Debug)]
+pub struct StaticSql {
+    pub postgres: &'static str,
+    pub mysql: &'static str,
+    pub sqlite: &'static str,
+    pub param_names: &'static [&'static str],
+}
+
+impl StaticSql {
+    /// 获取指定方言下的 sql
+    pub fn sql(&self, dialect: SqlDialect) -> &'static str {
+        match dialect {
+            SqlDialect::postgres => self.postgres,
+            SqlDialect::mysql => self.mysql,
+            SqlDialect::sqlite => self.sqlite,
+        }
+    }
+}
+
+/// 模板 id 及编译期生成的静态 sql，由宏生成后直接传给 adapter，运行时无需再查找静态 sql
+#[derive(Debug, Clone, Copy)]
+pub struct TemplateId {
+    pub id: u64,
+    pub static_sql: Option<&'static StaticSql>,
+}
+
+impl From<u64> for TemplateId {
+    fn from(id: u64) -> Self {
+        Self { id, static_sql: None }
+    }
+}
+
+/// 每个模板最多缓存的渲染结果个数，超出时清空该模板的缓存
+const MAX_RENDERED_SQL_PER_TEMPLATE: usize = 64;
+
+/// 运行时渲染出的 sql 及按占位符顺序排列的参数名
+#[derive(Debug)]
+struct RenderedSql {
+    /// 渲染所用的模板，开发模式下覆盖的 sql 重新加载后不再使用之前的结果
+    template: Arc<Template>,
+    sql: String,
+    param_names: Vec<String>,
+}
+
+type RenderedSqlCache = HashMap<(u64, SqlDialect), HashMap<SqlShape, Arc<RenderedSql>>>;
+
+static RENDERED_SQL_CACHE: OnceCell<RwLock<RenderedSqlCache>> = OnceCell::new();
+
+fn get_rendered_sql_cache() -> &'static RwLock<RenderedSqlCache> {
+    RENDERED_SQL_CACHE.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// 获取模板按 `shape` 所走的分支渲染出的 sql
+fn get_rendered_sql(template_id: u64, template: &Arc<Template>, dialect: SqlDialect, shape: &SqlShape) -> Option<Arc<RenderedSql>> {
+    get_rendered_sql_cache()
+        .read()
+        .unwrap()
+        .get(&(template_id, dialect))
+        .and_then(|rendered| rendered.get(shape))
+        .filter(|rendered| Arc::ptr_eq(&rendered.template, template))
+        .cloned()
+}
+
+/// 模板在指定方言下是否有缓存的渲染结果
+fn has_rendered_sql(template_id: u64, dialect: SqlDialect) -> bool {
+    get_rendered_sql_cache()
+        .read()
+        .unwrap()
+        .get(&(template_id, dialect))
+        .is_some_and(|rendered| !rendered.is_empty())
+}
+
+fn put_rendered_sql(template_id: u64, dialect: SqlDialect, shape: SqlShape, rendered: RenderedSql) {
+    let mut cache = get_rendered_sql_cache().write().unwrap();
+    let rendered_sqls = cache.entry((template_id, dialect)).or_default();
+    if rendered_sqls.len() >= MAX_RENDERED_SQL_PER_TEMPLATE && !rendered_sqls.contains_key(&shape) {
+        rendered_sqls.clear();
+    }
+    rendered_sqls.insert(shape, Arc::new(rendered));
+}
+
+/// 保存运行时渲染出的 sql 及参数值，可以在多次执行间复用以避免额外的内存分配
+#[derive(Debug, Default)]
+pub struct SqlBuffer {
+    sql: String,
+    values: Vec<SimpleValue>,
+    named_sql: String,
+    buf: Vec<u8>,
+    shape: SqlShape,
+}
+
+impl SqlBuffer {
+    /// 获取最终执行的 sql 及按占位符顺序排列的参数值：
+    /// 静态 sql 直接使用编译期生成的结果，否则渲染模板的同时写入占位符并收集参数值，只遍历一次 dto。
+    /// 渲染结果只由 dto 中各 section 的真假决定时，按模板、数据库方言及所走的分支缓存 sql 及参数名，
+    /// 之后相同形态的 dto 不再渲染，只获取参数值
+    pub fn get_sql_and_values<D>(&mut self, template_id: TemplateId, named_template: Arc<Template>, dto: &Option<D>, dialect: SqlDialect)
+        -> Result<(&str, &mut Vec<SimpleValue>), DySqlError>
+    where
+        D: Content + Send + Sync
+    {
+        self.values.clear();
+
+        if let Some(static_sql) = template_id.static_sql {
+            if let Some(dto) = dto {
+                for param_name in static_sql.param_names {
+                    self.values.push(param_value(&named_template, dto, param_name)?);
+                }
+            }
+            return Ok((static_sql.sql(dialect), &mut self.values));
+        }
+
+        match dto {
+            Some(dto) => {
+                // 模板还没有缓存的结果时一定不会命中，直接渲染并在渲染的同时记录分支；
+                // 否则先只遍历分支查找缓存，未命中时再渲染
+                if has_rendered_sql(template_id.id, dialect) {
+                    named_template.sql_shape(dto, &mut self.shape);
+                    let rendered = if self.shape.is_cacheable() { get_rendered_sql(template_id.id, &named_template, dialect, &self.shape) } else { None };
+                    if let Some(rendered) = rendered {
+                        for param_name in &rendered.param_names {
+                            self.values.push(param_value(&named_template, dto, param_name)?);
+                        }
+                        self.sql.clear();
+                        self.sql.push_str(&rendered.sql);
+                        return Ok((&self.sql, &mut self.values));
+                    }
+                }
+
+                if let Some(param_names) = self.render(&named_template, dto, dialect)? {
+                    let rendered = RenderedSql { template: named_template.clone(), sql: self.sql.clone(), param_names };
+                    put_rendered_sql(template_id.id, dialect, self.shape.clone(), rendered);
+                }
+            }
+            None => {
+                // 没有 dto 时不渲染模板，也不绑定参数
+                self.buf.clear();
+                extract_params_buf(named_template.source(), &mut self.buf, dialect).map_err(|e|
+                    DySqlError(ErrorInner::new(Kind::ExtractSqlParamterError, Some(Box::new(e)), None))
+                )?;
+                self.sql.clear();
+                self.sql.push_str(unsafe { std::str::from_utf8_unchecked(&self.buf) });
+            }
+        }
+
+        Ok((&self.sql, &mut self.values))
+    }
+
+    /// 不使用缓存，渲染模板的同时写入占位符并收集参数值
+    pub fn render_sql_and_values<D>(&mut self, named_template: &Template, dto: &D, dialect: SqlDialect)
+        -> Result<(&str, &mut Vec<SimpleValue>), DySqlError>
+    where
+        D: Content + Send + Sync
+    {
+        self.values.clear();
+        self.render(named_template, dto, dialect)?;
+
+        Ok((&self.sql, &mut self.values))
+    }
+
+    /// 渲染 sql 并收集参数值，同时在 `self.shape` 中记录所走的分支，
+    /// 渲染结果可以缓存时返回按占位符顺序排列的参数名
+    fn render<D>(&mut self, named_template: &Template, dto: &D, dialect: SqlDialect) -> Result<Option<Vec<String>>, DySqlError>
+    where
+        D: Content + Send + Sync
+    {
+        let mut params = ParamCollector { template: named_template, dto, dialect, values: &mut self.values, names: Some(Vec::new()) };
+        named_template.render_sql_params_shape(dto, &mut params, &mut self.sql, &mut self.shape).map_err(render_error)?;
+
+        Ok(params.names.filter(|_| self.shape.is_cacheable()))
+    }
+
+    /// 获取游标分页查询最终执行的 sql 及参数值，游标比较条件中的参数接着渲染出的占位符继续编号
+    pub fn get_cursor_sql_and_values<D>(&mut self, named_template: Arc<Template>, cursor_dto: &CursorDto<D>, dialect: SqlDialect)
+        -> Result<(&str, &mut Vec<SimpleValue>), DySqlError>
+    where
+        D: Content + Send + Sync
+    {
+        self.values.clear();
+        let mut params = ParamCollector { template: &named_template, dto: cursor_dto, dialect, values: &mut self.values, names: None };
+        named_template.render_sql_params(cursor_dto, &mut params, &mut self.named_sql).map_err(render_error)?;
+
+        let (prefix, suffix) = cursor_dto.page_sql_parts();
+        self.sql.clear();
+        self.sql.push_str(prefix);
+        self.sql.push_str(&self.named_sql);
+        for piece in SqlLexer::new(&suffix, dialect) {
+            let piece = piece.map_err(|e| DySqlError(ErrorInner::new(Kind::ExtractSqlParamterError, Some(Box::new(e)), None)))?;
+            match piece {
+                SqlPiece::Text(text) => self.sql.push_str(text),
+                SqlPiece::Param { name, .. } => params.write_param(&mut self.sql, name).map_err(render_error)?,
+            }
+        }
+
+        Ok((&self.sql, &mut self.values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SortModel, Value};
+
+    #[test]
+    fn test_static_sql() {
+        static STATIC_SQL: StaticSql = StaticSql {
+            postgres: "select * from test_user where id = $1",
+            mysql: "select * from test_user where id = ?",
+            sqlite: "select * from test_user where id = ?",
+            param_names: &["value"],
+        };
+        let static_id = TemplateId { id: 1, static_sql: Some(&STATIC_SQL) };
+
+        let template = Arc::new(Template::new("select * from test_user where id = :value").unwrap());
+        let mut sql_buf = SqlBuffer::default();
+        let dto = Some(Value::new(3_i64));
+        let (sql, values) = sql_buf.get_sql_and_values(static_id, template.clone(), &Option::<()>::None, SqlDialect::postgres).unwrap();
+        assert_eq!("select * from test_user where id = $1", sql);
+        assert!(values.is_empty());
+
+        let (sql, values) = sql_buf.get_sql_and_values(static_id, template.clone(), &dto, SqlDialect::postgres).unwrap();
+        assert_eq!("select * from test_user where id = $1", sql);
+        assert!(matches!(values[..], [SimpleValue::t_i64(3)]));
+
+        // 没有静态 sql 的模板在运行时渲染
+        let (sql, values) = sql_buf.get_sql_and_values(2.into(), template.clone(), &Option::<()>::None, SqlDialect::mysql).unwrap();
+        assert_eq!("select * from test_user where id = ?", sql);
+        assert!(values.is_empty());
+
+        let (sql, values) = sql_buf.get_sql_and_values(2.into(), template, &dto, SqlDialect::mysql).unwrap();
+        assert_eq!("select * from test_user where id = ?", sql);
+        assert!(matches!(values[..], [SimpleValue::t_i64(3)]));
+    }
+
+    #[test]
+    fn test_render_sql_and_values() {
+        let dto = Some(Value::new(vec![1_i64, 2]));
+        let mut sql_buf = SqlBuffer::default();
+
+        let template = Arc::new(Template::new("select * from t where id in (:value) and id = any(:value) and note = ':value' -- :value").unwrap());
+        let (sql, values) = sql_buf.get_sql_and_values(3.into(), template.clone(), &dto, SqlDialect::postgres).unwrap();
+        assert_eq!("select * from t where id in ($1, $2) and id = any($3) and note = ':value' -- :value\n", sql);
+        assert!(matches!(values[..], [SimpleValue::t_i64(1), SimpleValue::t_i64(2), SimpleValue::t_list(_)]));
+
+        let template = Arc::new(Template::new("select {{?value}}created_at::date, arr[1\\:2]{{/value}} from t where id IN (:value.0)").unwrap());
+        let (sql, values) = sql_buf.get_sql_and_values(3.into(), template, &dto, SqlDialect::sqlite).unwrap();
+        assert_eq!("select created_at::date, arr[1:2] from t where id IN (?)", sql);
+        assert!(matches!(values[..], [SimpleValue::t_i64(1)]));
+
+        let template = Arc::new(Template::new("select * from t where id = : value").unwrap());
+        let err = sql_buf.get_sql_and_values(3.into(), template, &dto, SqlDialect::mysql).unwrap_err();
+        assert_eq!(Kind::ExtractSqlParamterError, err.0.kind);
+
+        let template = Arc::new(Template::new("select * from t where id = :id").unwrap());
+        let err = sql_buf.get_sql_and_values(3.into(), template, &dto, SqlDialect::mysql).unwrap_err();
+        assert_eq!(Kind::BindParamterError, err.0.kind);
+    }
+
+    #[test]
+    fn test_rendered_sql_cache() {
+        let mut sql_buf = SqlBuffer::default();
+        let mut shape = SqlShape::default();
+
+        let template = Arc::new(Template::new("select * from t {{#where}} {{#value}}and id = :value{{/value}} {{/where}}").unwrap());
+        let dto = Some(Value::new(Some(3_i64)));
+        let (sql, _) = sql_buf.get_sql_and_values(4.into(), template.clone(), &dto, SqlDialect::postgres).unwrap();
+        assert_eq!("select * from t WHERE id = $1", sql);
+        template.sql_shape(dto.as_ref().unwrap(), &mut shape);
+        assert!(get_rendered_sql(4, &template, SqlDialect::postgres, &shape).is_some());
+
+        // 相同形态的 dto 使用缓存的 sql，只获取参数值
+        let (sql, values) = sql_buf.get_sql_and_values(4.into(), template.clone(), &Some(Value::new(Some(5_i64))), SqlDialect::postgres).unwrap();
+        assert_eq!("select * from t WHERE id = $1", sql);
+        assert!(matches!(values[..], [SimpleValue::t_i64(5)]));
+
+        let (sql, values) = sql_buf.get_sql_and_values(4.into(), template.clone(), &Some(Value::new(Option::<i64>::None)), SqlDialect::postgres).unwrap();
+        assert_eq!("select * from t", sql);
+        assert!(values.is_empty());
+
+        // 模板被替换后不再使用之前的结果
+        let template = Arc::new(Template::new("select * from t2 {{#where}} {{#value}}and id = :value{{/value}} {{/where}}").unwrap());
+        let (sql, _) = sql_buf.get_sql_and_values(4.into(), template.clone(), &dto, SqlDialect::postgres).unwrap();
+        assert_eq!("select * from t2 WHERE id = $1", sql);
+
+        // 展开了列表的结果不缓存
+        let template = Arc::new(Template::new("select * from t where id in (:value)").unwrap());
+        let dto = Some(Value::new(vec![1_i64, 2]));
+        let (sql, _) = sql_buf.get_sql_and_values(5.into(), template.clone(), &dto, SqlDialect::mysql).unwrap();
+        assert_eq!("select * from t where id in (?, ?)", sql);
+        template.sql_shape(dto.as_ref().unwrap(), &mut shape);
+        assert!(get_rendered_sql(5, &template, SqlDialect::mysql, &shape).is_none());
+
+        // `IN (:name)` 中绑定的不是列表时同样不缓存，之后绑定列表时仍按元素个数展开
+        let template = Arc::new(Template::new("select * from t where id in (:value)").unwrap());
+        let (sql, _) = sql_buf.get_sql_and_values(6.into(), template.clone(), &Some(Value::new(Option::<Vec<i64>>::None)), SqlDialect::mysql).unwrap();
+        assert_eq!("select * from t where id in (?)", sql);
+        let (sql, values) = sql_buf.get_sql_and_values(6.into(), template, &dto, SqlDialect::mysql).unwrap();
+        assert_eq!("select * from t where id in (?, ?)", sql);
+        assert!(matches!(values[..], [SimpleValue::t_i64(1), SimpleValue::t_i64(2)]));
+    }
+
+    #[test]
+    fn test_cursor_sql_and_values() {
+        let dto = Value::new(vec![1_i64, 2]);
+        let sort_model = vec![SortModel { field: "value".to_owned(), sort: "asc".to_owned() }];
+        let mut cursor_dto = CursorDto::new(10, Some(&dto), sort_model, None);
+        cursor_dto.init().unwrap();
+        cursor_dto.cursor = Some(cursor_dto.next_cursor(&Value::new(5_i64)).unwrap());
+        cursor_dto.init().unwrap();
+
+        // 游标的参数接着展开后的列表继续编号
+        let template = Arc::new(Template::new("select * from t where id in (:data.value)").unwrap());
+        let mut sql_buf = SqlBuffer::default();
+        let (sql, values) = sql_buf.get_cursor_sql_and_values(template, &cursor_dto, SqlDialect::postgres).unwrap();
+        assert_eq!("SELECT * FROM (select * from t where id in ($1, $2)) AS __dy_cursor_page WHERE (value) > ($3) ORDER BY value ASC LIMIT 11", sql);
+        assert!(matches!(values[..], [SimpleValue::t_i64(1), SimpleValue::t_i64(2), SimpleValue::t_i64(5)]));
+    }
+}
@@ -0,0 +1,102 @@
+use crate::DySqlError;
+
+/// 用于将查询结果的一行按列的顺序映射为元组，如 `fetch_all!(|&conn| -> (i64, String) { ... })`，
+/// 宏展开时会自动用 TupleRow 包装元组类型，查询后再取出其中的元组
+#[derive(Debug)]
+pub struct TupleRow<T>(pub T);
+
+impl<T> TupleRow<T> {
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+
+    /// 取出 stream 中每一行的元组
+    pub fn unwrap_stream<'e>(stream: futures_core::stream::BoxStream<'e, Result<Self, DySqlError>>)
+        -> futures_core::stream::BoxStream<'e, Result<T, DySqlError>>
+    where
+        T: Send + 'e,
+    {
+        Box::pin(futures_util::StreamExt::map(stream, |row| row.map(TupleRow::into_inner)))
+    }
+}
+
+#[cfg(feature = "sqlx")]
+impl<'r, R, T> sqlx::FromRow<'r, R> for TupleRow<T>
+where
+    R: sqlx::Row,
+    T: sqlx::FromRow<'r, R>,
+{
+    fn from_row(row: &'r R) -> Result<Self, sqlx::Error> {
+        T::from_row(row).map(TupleRow)
+    }
+}
+
+#[cfg(feature = "tokio-postgres")]
+macro_rules! impl_tokio_pg_tuple_row {
+    ($($idx:tt $t:ident),+) => {
+        impl<$($t),+> tokio_pg_mapper::FromTokioPostgresRow for TupleRow<($($t,)+)>
+        where
+            $(for<'a> $t: tokio_postgres::types::FromSql<'a>),+
+        {
+            fn from_row(row: tokio_postgres::Row) -> Result<Self, tokio_pg_mapper::Error> {
+                Self::from_row_ref(&row)
+            }
+
+            fn from_row_ref(row: &tokio_postgres::Row) -> Result<Self, tokio_pg_mapper::Error> {
+                Ok(TupleRow(($(
+                    row.try_get::<usize, $t>($idx).map_err(|e| tokio_pg_mapper::Error::Conversion(Box::new(e)))?,
+                )+)))
+            }
+
+            fn sql_table() -> String {
+                String::new()
+            }
+
+            fn sql_fields() -> String {
+                String::new()
+            }
+
+            fn sql_table_fields() -> String {
+                String::new()
+            }
+        }
+    };
+}
+
+#[cfg(feature = "tokio-postgres")]
+mod tokio_pg_impls {
+    use super::TupleRow;
+
+    impl_tokio_pg_tuple_row!(0 T0);
+    impl_tokio_pg_tuple_row!(0 T0, 1 T1);
+    impl_tokio_pg_tuple_row!(0 T0, 1 T1, 2 T2);
+    impl_tokio_pg_tuple_row!(0 T0, 1 T1, 2 T2, 3 T3);
+    impl_tokio_pg_tuple_row!(0 T0, 1 T1, 2 T2, 3 T3, 4 T4);
+    impl_tokio_pg_tuple_row!(0 T0, 1 T1, 2 T2, 3 T3, 4 T4, 5 T5);
+    impl_tokio_pg_tuple_row!(0 T0, 1 T1, 2 T2, 3 T3, 4 T4, 5 T5, 6 T6);
+    impl_tokio_pg_tuple_row!(0 T0, 1 T1, 2 T2, 3 T3, 4 T4, 5 T5, 6 T6, 7 T7);
+    impl_tokio_pg_tuple_row!(0 T0, 1 T1, 2 T2, 3 T3, 4 T4, 5 T5, 6 T6, 7 T7, 8 T8);
+    impl_tokio_pg_tuple_row!(0 T0, 1 T1, 2 T2, 3 T3, 4 T4, 5 T5, 6 T6, 7 T7, 8 T8, 9 T9);
+    impl_tokio_pg_tuple_row!(0 T0, 1 T1, 2 T2, 3 T3, 4 T4, 5 T5, 6 T6, 7 T7, 8 T8, 9 T9, 10 T10);
+    impl_tokio_pg_tuple_row!(0 T0, 1 T1, 2 T2, 3 T3, 4 T4, 5 T5, 6 T6, 7 T7, 8 T8, 9 T9, 10 T10, 11 T11);
+}
+
+/// rbatis 返回的每一行是按列的顺序排列的 map，转换为数组后再按位置反序列化为元组
+#[cfg(feature = "rbs")]
+impl<'de, T> serde::Deserialize<'de> for TupleRow<T>
+where
+    T: serde::de::DeserializeOwned,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let row = <rbs::Value as serde::Deserialize>::deserialize(deserializer)?;
+        let values = match row {
+            rbs::Value::Map(map) => rbs::Value::Array(map.into_iter().map(|(_, v)| v).collect()),
+            v => v,
+        };
+
+        rbs::from_value(values).map(TupleRow).map_err(serde::de::Error::custom)
+    }
+}
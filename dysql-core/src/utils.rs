@@ -92,6 +92,14 @@ pub fn hash_it<T: Hash>(name: T) -> u64 {
     hasher.finish()
 }
 
+/// 只产生一个错误的 stream，fetch_stream 在执行查询前出错时返回
+pub fn error_stream<'e, U>(e: DySqlError) -> futures_core::stream::BoxStream<'e, Result<U, DySqlError>>
+where
+    U: Send + 'e,
+{
+    Box::pin(futures_util::stream::once(async move { Err(e) }))
+}
+
 pub fn gen_named_sql<D>(named_template: Arc<Template>, dto: &Option<D>) -> Result<String, DySqlError>
 where 
     D: Content + Send + Sync
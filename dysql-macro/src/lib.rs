@@ -1,104 +1,127 @@
-#![feature(proc_macro_span)]
-
 //! Dysql 是一个轻量级的编译时生成 SQL 模板的库，它在运行时根据传入的 DTO 自动生成动态的 SQL 并设置数据参数，
 //! 在底层 Dysql 使用 sqlx, tokio-postgres, rbac 等框架执行最终的 SQL。
 
 mod sql_fragment;
 mod sql_expand;
+mod sql_file;
+mod mapper;
 
 use proc_macro::TokenStream;
 use sql_expand::SqlExpand;
-use sql_fragment::{STATIC_SQL_FRAGMENT_MAP, SqlFragment};
-use syn::{parse_macro_input, Token};
-use std::{collections::HashMap, sync::RwLock, path::PathBuf};
+use sql_fragment::{SqlFragment, FragmentCall, Fragments, parse_fragments, path_key};
+use syn::{parse_macro_input, Token, ext::IdentExt};
+use std::path::PathBuf;
+use dysql_core::join_sql_lines;
 use quote::quote;
-
-use sql_fragment::get_sql_fragment;
+use sql_file::SqlFile;
 
 /// 用于解析 dysql 所有过程宏的语句
 #[allow(dead_code)]
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub(crate) struct DyClosure {
     executor_info: ExecutorInfo,
     dto_info: DtoInfo,
     sql_name: Option<String>,
-    ret_type: Option<syn::Path>, // return type
+    ret_type: Option<syn::Type>, // return type
     body: String,
+    body_lits: Vec<syn::LitStr>, // sql body 中的字符串字面量，用于定位出错的命名参数
+    sql_files: Vec<String>, // sql body 中引用的 .sql 文件，文件变化时需要重新编译
     source_file: PathBuf,
+    fragment_call: Option<FragmentCall>, // sql body 中引用了尚未展开的 sql 片段
+    header_span: proc_macro2::Span, // 闭包头部结束的 `|`，用于报告缺少返回值类型等错误
+    body_span: proc_macro2::Span, // sql body 的大括号，无法定位到字面量时用于报告 sql 的错误
 }
 
-#[derive(Debug)]
-enum RefKind {
-    Immutable,
-    Mutable,
-    None
+impl DyClosure {
+    /// 校验是否指定了返回值类型
+    pub(crate) fn check_ret_type(&self) -> syn::Result<()> {
+        match self.ret_type {
+            Some(_) => Ok(()),
+            None => Err(syn::Error::new(self.header_span, "return type can't be null, specify it like `|executor, dto| -> T { ... }`")),
+        }
+    }
 }
 
-
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct DtoInfo {
-    src: Option<syn::Ident>,
-    ref_kind: RefKind,
+    src: Option<syn::Expr>,
 }
 
 impl DtoInfo {
-    pub fn new(src: Option<syn::Ident>, ref_kind: RefKind) -> Self {
+    pub fn new(src: Option<syn::Expr>) -> Self {
         Self {
             src,
-            ref_kind,
         }
     }
 
-    pub fn gen_token(&self) -> proc_macro2::TokenStream {
-        if let Some(_) = self.src {
-            let mut rst = match self.ref_kind {
-                RefKind::Immutable => quote!(&),
-                RefKind::Mutable => quote!(&mut),
-                RefKind::None => quote!(),
-            };
-    
-            let dto = &self.src;
-            rst.extend(quote!(#dto));
+    /// 在生成的代码中对 dto 表达式求值一次并绑定到 `__dy_dto`，之后只使用该变量
+    pub fn gen_binding(&self) -> proc_macro2::TokenStream {
+        match &self.src {
+            Some(dto) => quote!(let __dy_dto = #dto;),
+            None => quote!(),
+        }
+    }
 
-            rst.into()
-        } else {
-            quote!()
+    /// 去掉 dto 表达式最外层的 & 或 &mut 后以 &mut 绑定到 `__dy_dto`，用于在分页查询中修改 dto
+    pub fn gen_mut_binding(&self) -> proc_macro2::TokenStream {
+        match &self.src {
+            Some(syn::Expr::Reference(dto)) => {
+                let dto = &dto.expr;
+                quote!(let __dy_dto = &mut (#dto);)
+            },
+            Some(dto) => quote!(let __dy_dto = &mut (#dto);),
+            None => quote!(),
         }
     }
 }
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct ExecutorInfo {
-    src: syn::Ident,
-    ref_kind: RefKind,
-    is_deref: bool,
+    src: syn::Expr,
 }
 
 impl ExecutorInfo {
-    pub fn new(src: syn::Ident, ref_kind: RefKind, is_deref: bool) -> Self {
+    pub fn new(src: syn::Expr) -> Self {
         Self {
             src,
-            ref_kind,
-            is_deref,
         }
     }
 
-    pub fn gen_token(&self) -> proc_macro2::TokenStream {
-        let mut rst = match self.ref_kind {
-            RefKind::Immutable => quote!(&),
-            RefKind::Mutable => quote!(&mut),
-            RefKind::None => quote!(),
-        };
+    /// 在生成的代码中对 executor 表达式求值一次并绑定到 `__dy_executor`，之后只使用该变量。
+    /// 变量、字段等位置表达式求值没有副作用，直接使用而不绑定，避免 move 走 executor (如 `|pool|`、`|db|`)
+    pub fn gen_binding(&self) -> proc_macro2::TokenStream {
+        let executor = &self.src;
 
-        if self.is_deref {
-            rst.extend(quote!(*))
+        if is_place_expr(executor) {
+            quote!()
+        } else {
+            // 绑定的值可能需要以 &mut 方式自动引用 (如 RefMut)
+            quote!(#[allow(unused_mut)] let mut __dy_executor = #executor;)
         }
+    }
 
+    /// 生成代码中使用 executor 的 token
+    pub fn gen_token(&self) -> proc_macro2::TokenStream {
         let executor = &self.src;
-        rst.extend(quote!(#executor));
 
-        quote!((#rst))
+        if is_place_expr(executor) {
+            quote!((#executor))
+        } else {
+            quote!(__dy_executor)
+        }
+    }
+}
+
+/// 是否为变量、字段、下标或解引用等位置表达式
+fn is_place_expr(expr: &syn::Expr) -> bool {
+    match expr {
+        syn::Expr::Path(_) => true,
+        syn::Expr::Field(expr) => is_place_expr(&expr.base),
+        syn::Expr::Index(expr) => is_place_expr(&expr.expr),
+        syn::Expr::Paren(expr) => is_place_expr(&expr.expr),
+        syn::Expr::Unary(syn::ExprUnary { op: syn::UnOp::Deref(_), expr, .. }) => is_place_expr(expr),
+        _ => false,
     }
 }
 
@@ -107,160 +130,202 @@ impl syn::parse::Parse for DyClosure {
         // 加载 .env 文件中的环境变量，读取自动持久化 sql 文件的参数
         dotenv::dotenv().ok();
 
+        // 保存原始输入，引用了未展开的 sql 片段时需要原样传给片段宏
+        let raw_input = input.cursor().token_stream();
+        // 解析片段宏回传的 sql 片段
+        let fragments = parse_fragments(input)?;
+
         // 测试是否 | 开始
         input.parse::<syn::Token!(|)>()?;
 
-        // 解析 executor 的引用(可能为 &mut, &)
-        let executor_ref_kind: RefKind;
-        match input.parse::<syn::Token!(&)>() {
-            Err(_) => executor_ref_kind = RefKind::None,
-            Ok(_) => match input.parse::<syn::Token!(mut)>() {
-                Err(_) => executor_ref_kind = RefKind::Immutable,
-                Ok(_) => executor_ref_kind = RefKind::Mutable,
-            }
-        }
-
-        // 解析 executor (可能为 *executor, executor)
-        let is_executor_deref: bool;
-        let executor: syn::Ident;
-        match input.parse::<syn::Token!(*)>() {
-            Ok(_) => is_executor_deref = true,
-            Err(_) => is_executor_deref = false,
-        }
-        match input.parse::<syn::Ident>() {
-            Err(e) => return Err(e),
-            Ok(ex) => executor = ex,
-        }
-
-        // 测试是否 | 结束, 并解析 ',dto '(dto 可能为 _, &dto, &mut dto)
-        let sql_name: Option<String>;
-        let dto: Option<syn::Ident>;
-        let dto_ref_kind: RefKind;
-        match input.parse::<syn::Token!(|)>() {
-            Ok(_) => {
-                sql_name = None;
-                dto = None;
-                dto_ref_kind = RefKind::None;
-            },
-            Err(_) => match input.parse::<syn::Token!(,)>() {
-                Err(e) =>  return Err(e),
-                Ok(_) => {
-                    // 解析 dto
-                    match input.parse::<syn::Token!(_)>() {
-                        Ok(_) => {
-                            dto = None;
-                            dto_ref_kind = RefKind::None;
-                        },
-                        Err(_) => {
-                            match input.parse::<syn::Token!(&)>() {
-                                Ok(_) => match input.parse::<syn::Token!(mut)>(){
-                                    Ok(_) => dto_ref_kind = RefKind::Mutable,
-                                    Err(_) => dto_ref_kind = RefKind::Immutable,
-                                },
-                                Err(_) =>  dto_ref_kind = RefKind::None,
-                            }
-                            match input.parse::<syn::Ident>() {
-                                Err(e) => return Err(e),
-                                Ok(d) => dto = Some(d),
-                            }
-                        }
-                    }
-
-                    // 测试是否 | 结束，并解析 , 'sql_name |'
-                    match input.parse::<syn::Token!(|)>() {
-                        // | 结束
-                        Ok(_) => sql_name = None,
-                        // 解析是否为接下来是否为 " , sql_name |"
-                        Err(_) => match input.parse::<syn::Token!(,)>() {
-                            Err(e) => return Err(e),
-                            // 解析 sql_name
-                            Ok(_) => {
-                                match input.parse::<syn::Token!(_)>() {
-                                    Ok(_) => { sql_name = None },
-                                    Err(_) => match input.parse::<syn::LitStr>() {
-                                        Ok(s) => sql_name = Some(s.value()),
-                                        Err(_) => return Err(syn::Error::new(proc_macro2::Span::call_site(), "need specify the sql_name")),
-                                    }
-                                }
-                                // | 结束
-                                input.parse::<syn::Token!(|)>()?; 
-                            }
-                        }
+        // 解析 executor 表达式 (如 &conn, &mut *tran, &state.pool)
+        let executor = parse_header_expr(input, "executor")?
+            .ok_or_else(|| syn::Error::new(input.span(), "need specify the executor"))?;
+
+        // 测试是否 | 结束, 并解析 ',dto '(dto 可能为 _, &dto, &mut dto, &req.filter 等表达式)
+        let mut sql_name: Option<String> = None;
+        let mut dto: Option<syn::Expr> = None;
+        if input.parse::<syn::Token!(,)>().is_ok() {
+            dto = parse_header_expr(input, "dto")?;
+
+            // 解析 , 'sql_name'
+            if input.parse::<syn::Token!(,)>().is_ok() {
+                match input.parse::<syn::Token!(_)>() {
+                    Ok(_) => (),
+                    Err(_) => match input.parse::<syn::LitStr>() {
+                        Ok(s) => sql_name = Some(s.value()),
+                        Err(_) => return Err(syn::Error::new(input.span(), "need specify the sql_name")),
                     }
                 }
             }
         }
+        // | 结束
+        let header_end = input.parse::<syn::Token!(|)>()?;
 
         // 解析 -> 符号
-        let ret_type:Option<syn::Path>;
+        let ret_type:Option<syn::Type>;
         match input.parse::<syn::Token!(->)>() {
-            // 解析 ret_type
-            Ok(_) => match input.parse::<syn::Path>() {
+            // 解析 ret_type (可以是 User、(i64, String)、Wrapper<User> 等任意类型)
+            Ok(_) => match input.parse::<syn::Type>() {
                 Ok(p) => ret_type = Some(p),
                 Err(_) => 
-                    return Err(syn::Error::new(proc_macro2::Span::call_site(), "Need specify the return type")),
+                    return Err(syn::Error::new(input.span(), "Need specify the return type")),
             }
             Err(_) => ret_type = None,
         };
 
         // 解析 { sql body } 
-        let body = parse_body(input)?;
-        let body: Vec<String> = body.split('\n').into_iter().map(|f| f.trim().to_owned()).collect();
-        let body = body.join(" ").to_owned();
+        let (body, body_lits, sql_files, unresolved, body_span) = parse_body(input, &fragments)?;
+        let body = join_sql_lines(&body);
 
         // 获取当前被解析的文件位置
-        let span: proc_macro::Span = input.span().unwrap();
-        let source_file = span.source_file().path();
+        let source_file = source_file(input.span().unwrap());
 
-        let executor_info = ExecutorInfo::new(executor, executor_ref_kind, is_executor_deref);
-        let dto_info = DtoInfo::new(dto, dto_ref_kind);
+        let executor_info = ExecutorInfo::new(executor);
+        let dto_info = DtoInfo::new(dto);
 
-        let dsf = DyClosure { executor_info, dto_info, sql_name, ret_type, body, source_file };
+        let fragment_call = unresolved.map(|path| FragmentCall { path, input: raw_input });
+
+        let header_span = header_end.span;
+        let dsf = DyClosure { 
+            executor_info, dto_info, sql_name, ret_type, body, body_lits, sql_files, source_file, fragment_call, header_span, body_span,
+        };
         // eprintln!("{:#?}", dsf);
 
         Ok(dsf)
     }
 }
 
-/// 解析 sql body
-fn parse_body(input: &syn::parse::ParseBuffer) -> Result<String, syn::Error> {
+/// 用于解析 upsert!(|executor, dto| { sql }, conflict(key, ...), update(column, ...)) 的语句，
+/// update(...) 可以省略，省略时发生冲突的记录不会被更新
+pub(crate) struct UpsertClosure {
+    closure: DyClosure,
+    conflict: Vec<String>,
+    update: Vec<String>,
+}
+
+impl syn::parse::Parse for UpsertClosure {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let closure = input.parse::<DyClosure>()?;
+
+        input.parse::<syn::Token!(,)>()?;
+        let conflict = parse_columns(input, "conflict")?;
+        if conflict.is_empty() {
+            return Err(syn::Error::new(input.span(), "need specify the conflict columns"));
+        }
+
+        let update = match input.parse::<syn::Token!(,)>() {
+            Ok(_) if !input.is_empty() => parse_columns(input, "update")?,
+            _ => vec![],
+        };
+
+        Ok(Self { closure, conflict, update })
+    }
+}
+
+/// 解析 name(column, ...)
+fn parse_columns(input: syn::parse::ParseStream, name: &str) -> syn::Result<Vec<String>> {
+    let ident = input.parse::<syn::Ident>()?;
+    if ident != name {
+        return Err(syn::Error::new(ident.span(), format!("expected `{}(...)`", name)));
+    }
+
+    let columns;
+    syn::parenthesized!(columns in input);
+    let columns = columns.parse_terminated(syn::Ident::parse_any, syn::Token!(,))?;
+
+    Ok(columns.iter().map(|c| c.to_string()).collect())
+}
+
+/// 解析闭包头部中以 `,` 或 `|` 结束的表达式，`_` 表示没有该表达式
+fn parse_header_expr(input: syn::parse::ParseStream, name: &str) -> syn::Result<Option<syn::Expr>> {
+    if input.peek(syn::Token!(_)) && (input.peek2(syn::Token!(,)) || input.peek2(syn::Token!(|))) {
+        input.parse::<syn::Token!(_)>()?;
+        return Ok(None);
+    }
+
+    // 括号中的 `,` 和 `|` (如方法调用的参数、闭包) 属于同一个 TokenTree，不会被当成结束符
+    let tokens = input.step(|cursor| {
+        let mut tokens = proc_macro2::TokenStream::new();
+        let mut rest = *cursor;
+        while let Some((tt, next)) = rest.token_tree() {
+            match &tt {
+                proc_macro2::TokenTree::Punct(p) if p.as_char() == ',' || p.as_char() == '|' => break,
+                _ => {
+                    tokens.extend(std::iter::once(tt));
+                    rest = next;
+                },
+            }
+        }
+        Ok((tokens, rest))
+    })?;
+
+    if tokens.is_empty() {
+        return Err(syn::Error::new(input.span(), format!("need specify the {}", name)));
+    }
+
+    syn::parse2::<syn::Expr>(tokens).map(Some)
+}
+
+/// 获取 span 所在的源文件路径 (相对于 workspace 的路径，不随机器变化)，用于持久化 sql
+pub(crate) fn source_file(span: proc_macro::Span) -> PathBuf {
+    PathBuf::from(span.file())
+}
+
+/// 解析 sql body，返回 sql、sql 中的字符串字面量、引用的 .sql 文件、第一个尚未展开的 sql 片段以及 body 的位置
+#[allow(clippy::type_complexity)]
+fn parse_body(input: &syn::parse::ParseBuffer, fragments: &Fragments) 
+    -> Result<(String, Vec<syn::LitStr>, Vec<String>, Option<syn::Path>, proc_macro2::Span), syn::Error> 
+{
     let body_buf;
     // 解析大括号
-    syn::braced!(body_buf in input);
+    let brace = syn::braced!(body_buf in input);
     
-    let ts = body_buf.cursor().token_stream().into_iter();
     let mut sql = String::new();
-    for it in ts {
-        match it {
-            proc_macro2::TokenTree::Group(_) => {
-                return Err(syn::Error::new(input.span(), "error not support group in sql".to_owned()));
-            },
-            proc_macro2::TokenTree::Ident(_) => {
-                let v: syn::Ident = body_buf.parse()?;
-                let sql_fragment = get_sql_fragment(&v.to_string());
-                
-                if let Some(s) = sql_fragment {
-                    sql.push_str(&s);
-                } else {
-                    return Err(syn::Error::new(input.span(), "error not found sql identity".to_owned()));
-                }
-            },
-            proc_macro2::TokenTree::Punct(v) => {
-                if v.to_string() == "+" {
-                    body_buf.parse::<Token!(+)>()?;
-                } else {
-                    return Err(syn::Error::new(input.span(), "error only support '+' expr".to_owned()));
-                }
-            },
-            proc_macro2::TokenTree::Literal(_) => {
-                let rst: syn::LitStr = body_buf.parse()?;
-                
-                sql.push_str(&rst.value());
-            },
-        };
+    let mut lits = Vec::new();
+    let mut files = Vec::new();
+    let mut unresolved = None;
+    while !body_buf.is_empty() {
+        if body_buf.peek(syn::LitStr) {
+            let rst: syn::LitStr = body_buf.parse()?;
+            
+            sql.push_str(&rst.value());
+            lits.push(rst);
+        } else if body_buf.peek(Token!(+)) {
+            body_buf.parse::<Token!(+)>()?;
+        } else if body_buf.peek(syn::Ident) && body_buf.peek2(syn::token::Paren) && body_buf.fork().parse::<syn::Ident>()? == "file" {
+            body_buf.parse::<syn::Ident>()?;
+            let args;
+            syn::parenthesized!(args in body_buf);
+            let (file_sql, file_path) = args.parse::<SqlFile>()?.load()?;
+
+            sql.push_str(&file_sql);
+            files.push(file_path);
+        } else if body_buf.peek(syn::Ident::peek_any) || body_buf.peek(Token!(::)) {
+            // sql 片段引用：name、path::to::name 或带参数的 name("arg", ...)
+            let path = body_buf.parse::<syn::Path>()?;
+            let args: Vec<syn::LitStr> = if body_buf.peek(syn::token::Paren) {
+                let args;
+                syn::parenthesized!(args in body_buf);
+                args.parse_terminated(|a| a.parse::<syn::LitStr>(), Token!(,))?.into_iter().collect()
+            } else {
+                vec![]
+            };
+
+            match fragments.get(&path_key(&path)) {
+                Some(fragment) => sql.push_str(&fragment.apply(&path, &args)?),
+                None => if unresolved.is_none() { unresolved = Some(path) },
+            }
+        } else if body_buf.peek(syn::token::Paren) || body_buf.peek(syn::token::Brace) || body_buf.peek(syn::token::Bracket) {
+            let group = body_buf.parse::<proc_macro2::TokenTree>()?;
+            return Err(syn::Error::new(group.span(), "error not support group in sql".to_owned()));
+        } else {
+            let token = body_buf.parse::<proc_macro2::TokenTree>()?;
+            return Err(syn::Error::new(token.span(), "error only support '+' expr".to_owned()));
+        }
     }
 
-    Ok(sql)
+    Ok((sql, lits, files, unresolved, brace.span.join()))
 }
 
 // /// 根据 s 生成 syn::Path 对象，用于 dysql 中有返回值的过程宏
@@ -306,9 +371,12 @@ fn parse_body(input: &syn::parse::ParseBuffer) -> Result<String, syn::Error> {
 pub fn fetch_all(input: TokenStream) -> TokenStream {
     // 将 input 解析成 SqlClosure
     let st = syn::parse_macro_input!(input as DyClosure);
+    // 引用了尚未展开的 sql 片段时，先展开片段宏
+    if let Some(call) = &st.fragment_call { return call.expand("fetch_all") }
+
 
     // 必须要指定单个 item 的返回值类型
-    if st.ret_type.is_none() { panic!("return type can't be null.") }
+    if let Err(e) = st.check_ret_type() { return e.into_compile_error().into() }
 
     match SqlExpand.fetch_all(&st) {
         Ok(ret) => ret.into(),
@@ -316,6 +384,47 @@ pub fn fetch_all(input: TokenStream) -> TokenStream {
     }
 }
 
+/// fetch datas that filtered by dto as a stream, rows are pulled from database lazily
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```ignore
+/// use futures::TryStreamExt;
+///
+/// let mut conn = connect_db().await;
+///
+/// let dto = UserDto {id: None, name: None, age: 13};
+/// let mut stream = fetch_stream!(|&mut conn, &dto| -> User {
+///     r#"select * from test_user
+///     where 1 = 1
+///         {{#name}}and name = :name{{/name}}
+///         {{#age}}and age > :age{{/age}}
+///     order by id"#
+/// });
+///
+/// while let Some(user) = stream.try_next().await.unwrap() {
+///     println!("{:?}", user);
+/// }
+/// ```
+#[proc_macro]
+pub fn fetch_stream(input: TokenStream) -> TokenStream {
+    // 将 input 解析成 SqlClosure
+    let st = syn::parse_macro_input!(input as DyClosure);
+    // 引用了尚未展开的 sql 片段时，先展开片段宏
+    if let Some(call) = &st.fragment_call { return call.expand("fetch_stream") }
+
+
+    // 必须要指定单个 item 的返回值类型
+    if let Err(e) = st.check_ret_type() { return e.into_compile_error().into() }
+
+    match SqlExpand.fetch_stream(&st) {
+        Ok(ret) => ret.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
 ///
 /// fetch one data that filtered by dto
 /// 
@@ -339,9 +448,12 @@ pub fn fetch_all(input: TokenStream) -> TokenStream {
 pub fn fetch_one(input: TokenStream) -> TokenStream {
     // 将 input 解析成 SqlClosure
     let st = syn::parse_macro_input!(input as DyClosure);
+    // 引用了尚未展开的 sql 片段时，先展开片段宏
+    if let Some(call) = &st.fragment_call { return call.expand("fetch_one") }
+
 
     // 必须要指定单个 item 的返回值类型
-    if st.ret_type.is_none() { panic!("return type can't be null.") }
+    if let Err(e) = st.check_ret_type() { return e.into_compile_error().into() }
 
     match SqlExpand.fetch_one(&st) {
         Ok(ret) => ret.into(),
@@ -349,6 +461,40 @@ pub fn fetch_one(input: TokenStream) -> TokenStream {
     }
 }
 
+///
+/// fetch one data that filtered by dto, returns None if no record found
+/// 
+/// # Examples
+///
+/// Basic usage:
+/// 
+/// ```ignore
+/// let conn = connect_db().await;
+/// 
+/// let dto = UserDto {id: 100, name: None, age: None};
+/// let rst = fetch_optional!(|&conn, dto| -> User {
+///     r#"select * from test_user where id = :id"#
+/// }).unwrap();
+/// 
+/// assert_eq!(None, rst);
+/// ```
+#[proc_macro]
+pub fn fetch_optional(input: TokenStream) -> TokenStream {
+    // 将 input 解析成 SqlClosure
+    let st = syn::parse_macro_input!(input as DyClosure);
+    // 引用了尚未展开的 sql 片段时，先展开片段宏
+    if let Some(call) = &st.fragment_call { return call.expand("fetch_optional") }
+
+
+    // 必须要指定单个 item 的返回值类型
+    if let Err(e) = st.check_ret_type() { return e.into_compile_error().into() }
+
+    match SqlExpand.fetch_optional(&st) {
+        Ok(ret) => ret.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
 ///
 /// Fetch a scalar value from query
 /// 
@@ -368,7 +514,10 @@ pub fn fetch_one(input: TokenStream) -> TokenStream {
 pub fn fetch_scalar(input: TokenStream) -> TokenStream {
     // 将 input 解析成 SqlClosure
     let st = syn::parse_macro_input!(input as DyClosure);
-    if st.ret_type.is_none() { panic!("return type can't be null.") }
+    // 引用了尚未展开的 sql 片段时，先展开片段宏
+    if let Some(call) = &st.fragment_call { return call.expand("fetch_scalar") }
+
+    if let Err(e) = st.check_ret_type() { return e.into_compile_error().into() }
 
     match SqlExpand.fetch_scalar(&st) {
         Ok(ret) => ret.into(),
@@ -398,6 +547,9 @@ pub fn fetch_scalar(input: TokenStream) -> TokenStream {
 pub fn execute(input: TokenStream) -> TokenStream {
     // 将 input 解析成 SqlClosure
     let st = syn::parse_macro_input!(input as DyClosure);
+    // 引用了尚未展开的 sql 片段时，先展开片段宏
+    if let Some(call) = &st.fragment_call { return call.expand("execute") }
+
 
     match SqlExpand.execute(&st) {
         Ok(ret) => ret.into(),
@@ -405,6 +557,42 @@ pub fn execute(input: TokenStream) -> TokenStream {
     }
 }
 
+///
+/// Execute one sql template over a collection of dto, returns the affected rows of each dto.
+/// Statements with the same rendered sql are prepared only once.
+/// 
+/// # Examples
+///
+/// Basic usage:
+/// 
+/// ```ignore
+/// let mut tran = get_transaction().await.unwrap();
+/// 
+/// let dtos = vec![
+///     UserDto::new(None, Some("a".to_owned()), Some(1), None),
+///     UserDto::new(None, Some("b".to_owned()), Some(2), None),
+/// ];
+/// let rst = execute_batch!(|&mut *tran, dtos| {
+///     r#"insert into test_user (name, age) values (:name, :age)"#
+/// }).unwrap();
+/// assert_eq!(vec![1, 1], rst);
+/// 
+/// tran.rollback().await?;
+/// ```
+#[proc_macro]
+pub fn execute_batch(input: TokenStream) -> TokenStream {
+    // 将 input 解析成 SqlClosure
+    let st = syn::parse_macro_input!(input as DyClosure);
+    // 引用了尚未展开的 sql 片段时，先展开片段宏
+    if let Some(call) = &st.fragment_call { return call.expand("execute_batch") }
+
+
+    match SqlExpand.execute_batch(&st) {
+        Ok(ret) => ret.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
 ///
 /// Insert data
 /// **Note:** if you use this macro under **postgres** database, you should add "returning id" at the end of sql statement by yourself.
@@ -429,7 +617,10 @@ pub fn execute(input: TokenStream) -> TokenStream {
 pub fn insert(input: TokenStream) -> TokenStream {
     // 将 input 解析成 SqlClosure
     let st = syn::parse_macro_input!(input as DyClosure);
-    if st.ret_type.is_none() { panic!("return type can't be null.") }
+    // 引用了尚未展开的 sql 片段时，先展开片段宏
+    if let Some(call) = &st.fragment_call { return call.expand("insert") }
+
+    if let Err(e) = st.check_ret_type() { return e.into_compile_error().into() }
 
     match SqlExpand.insert(&st) {
         Ok(ret) => ret.into(),
@@ -438,31 +629,64 @@ pub fn insert(input: TokenStream) -> TokenStream {
 }
 
 ///
-/// Define a global sql fragment
+/// Insert or update data, the conflict clause is generated by the sql dialect of executor:
+/// `ON CONFLICT (..) DO UPDATE SET ..` for postgres and sqlite, `ON DUPLICATE KEY UPDATE ..` for mysql.
+/// Returns the id like `insert!` if return type is specified, otherwise returns the affected rows.
+/// **Note:** under **postgres** database, "returning id" at the end of sql statement is kept at the end.
 /// 
 /// # Examples
 ///
 /// Basic usage:
 /// 
 /// ```ignore
-/// sql!("select_sql", "select * from table1 ")
+/// let mut tran = get_transaction().await.unwrap();
 /// 
-/// let last_insert_id = fetch_all!(|&conn, &dto| {
-///     select_sql + "where age > 10 "
-/// }).unwrap();
+/// let dto = UserDto{ id: Some(4), name: Some("lisi".to_owned()), age: Some(50) };
+/// let affected_rows_num = upsert!(|&mut *tran, dto| {
+///     r#"insert into test_user (id, name, age) values (:id, :name, :age)"#
+/// }, conflict(id), update(name, age)).unwrap();
+/// assert_eq!(1, affected_rows_num);
 /// 
 /// tran.rollback().await?;
 /// ```
 #[proc_macro]
+pub fn upsert(input: TokenStream) -> TokenStream {
+    // 将 input 解析成 UpsertClosure
+    let st = syn::parse_macro_input!(input as UpsertClosure);
+    // 引用了尚未展开的 sql 片段时，先展开片段宏
+    if let Some(call) = &st.closure.fragment_call { return call.expand("upsert") }
+
+
+    match SqlExpand.upsert(&st.closure, &st.conflict, &st.update) {
+        Ok(ret) => ret.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
+///
+/// Define a sql fragment, it is scoped like other rust items and can be referenced by path.
+/// A `pub` fragment is exported by `#[macro_export]` so that other crates can use it as `crate_name::fragment`.
+/// Fragment parameters (`${param}`) are substituted before the sql template is compiled.
+/// 
+/// # Examples
+///
+/// Basic usage:
+/// 
+/// ```ignore
+/// mod fragments {
+///     sql!(pub(crate) select_sql, "select * from table1 ");
+///     sql!(pub(crate) age_filter(alias), "${alias}.age > :age ");
+/// }
+/// 
+/// let rst = fetch_all!(|&conn, &dto| -> User {
+///     fragments::select_sql + "t where " + fragments::age_filter("t")
+/// }).unwrap();
+/// ```
+#[proc_macro]
 pub fn sql(input: TokenStream) -> TokenStream {
     let st = parse_macro_input!(input as SqlFragment);
-    let cache = STATIC_SQL_FRAGMENT_MAP.get_or_init(|| {
-        RwLock::new(HashMap::new())
-    });
 
-    cache.write().unwrap().insert(st.name, st.value.to_string());
-
-    quote!().into()
+    st.expand().into()
 }
 
 /// page query
@@ -492,10 +716,94 @@ pub fn sql(input: TokenStream) -> TokenStream {
 pub fn page(input: TokenStream) -> TokenStream {
     // 将 input 解析成 SqlClosure
     let st = syn::parse_macro_input!(input as DyClosure);
-    if st.ret_type.is_none() { panic!("return type can't be null.") }
+    // 引用了尚未展开的 sql 片段时，先展开片段宏
+    if let Some(call) = &st.fragment_call { return call.expand("page") }
+
+    if let Err(e) = st.check_ret_type() { return e.into_compile_error().into() }
 
     match SqlExpand.page(&st) {
         Ok(ret) => ret.into(),
         Err(e) => e.into_compile_error().into(),
     }
-}
\ No newline at end of file
+}
+
+/// cursor (keyset) page query，返回值类型需要同时实现 Content 以便从最后一条记录生成下一页的游标
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```ignore
+/// let sort_model = vec![SortModel { field: "age".to_owned(), sort: "asc".to_owned() }, SortModel { field: "id".to_owned(), sort: "asc".to_owned() }];
+/// let mut cursor_dto = CursorDto::new(3, Some(dto), sort_model, None);
+///
+/// let rst = cursor_page!(|&conn, cursor_dto| -> User {
+///     "select * from test_user
+///     where 1 = 1
+///         {{#data}}
+///             {{#age}}and age > :data.age{{/age}}
+///         {{/data}}"
+/// }).unwrap();
+///
+/// // 用 rst.next_cursor 查询下一页
+/// cursor_dto.cursor = rst.next_cursor;
+/// ```
+#[proc_macro]
+pub fn cursor_page(input: TokenStream) -> TokenStream {
+    // 将 input 解析成 SqlClosure
+    let st = syn::parse_macro_input!(input as DyClosure);
+    // 引用了尚未展开的 sql 片段时，先展开片段宏
+    if let Some(call) = &st.fragment_call { return call.expand("cursor_page") }
+
+    if let Err(e) = st.check_ret_type() { return e.into_compile_error().into() }
+
+    match SqlExpand.cursor_page(&st) {
+        Ok(ret) => ret.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
+
+/// 为 trait 生成基于 dysql 的实现 (MyBatis mapper 风格)，
+/// 参数分别为实现该 trait 的结构体以及结构体中保存 executor (sqlx pool, tokio-postgres client, RBatis 等) 的字段。
+///
+/// trait 中的每个方法都需要用 `#[sql("...")]` 指定 sql 模板，dysql 会根据返回值类型和 sql 语句推断查询方式：
+/// `Vec<T>` 对应 fetch_all，`Pagination<T>` 对应 page，`CursorPagination<T>` 对应 cursor_page，select 语句返回基本类型时对应 fetch_scalar，否则对应 fetch_one，
+/// insert 语句对应 insert，其余语句对应 execute。
+/// 也可以用 `#[fetch_all("...")]`，`#[fetch_one("...")]`，`#[fetch_scalar("...")]`，`#[execute("...")]`，
+/// `#[insert("...")]`，`#[page("...")]`，`#[cursor_page("...")]` 直接指定查询方式。
+/// 方法的 receiver 为 `&mut self` 时 executor 以 `&mut` 方式引用，以支持 `&mut SqliteConnection` 这类 executor。
+///
+/// # Examples
+///
+/// Basic usage:
+///
+/// ```ignore
+/// pub struct UserRepo {
+///     pool: sqlx::PgPool,
+/// }
+///
+/// #[dysql::mapper(UserRepo, pool)]
+/// pub trait UserMapper {
+///     #[sql("select * from test_user where 1 = 1 {{#age}}and age > :age{{/age}} order by id")]
+///     async fn find_by_age(&self, dto: &UserDto) -> DySqlResult<Vec<User>>;
+///
+///     #[sql("select count(*) from test_user")]
+///     async fn count(&self) -> DySqlResult<i64>;
+///
+///     #[sql("delete from test_user where id = :id")]
+///     async fn delete(&self, dto: &UserDto) -> DySqlResult<u64>;
+/// }
+///
+/// let repo = UserRepo { pool };
+/// let users = repo.find_by_age(&dto).await?;
+/// ```
+#[proc_macro_attribute]
+pub fn mapper(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let args = parse_macro_input!(attr as mapper::MapperArgs);
+    let item = parse_macro_input!(item as syn::ItemTrait);
+
+    match mapper::expand(args, item) {
+        Ok(ret) => ret.into(),
+        Err(e) => e.into_compile_error().into(),
+    }
+}
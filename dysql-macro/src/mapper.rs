@@ -0,0 +1,212 @@
+use quote::quote;
+
+use dysql_core::join_sql_lines;
+
+use crate::{DyClosure, DtoInfo, ExecutorInfo};
+use crate::sql_expand::SqlExpand;
+
+/// mapper 方法上可以使用的 sql 属性，`sql` 会根据返回值类型和 sql 语句推断具体的查询方式
+const SQL_ATTRS: &[&str] = &["sql", "fetch_all", "fetch_one", "fetch_optional", "fetch_scalar", "execute", "insert", "page", "cursor_page"];
+
+/// 可以直接作为 fetch_scalar 返回值的类型
+const SCALAR_TYPES: &[&str] = &[
+    "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+    "f32", "f64", "bool", "String", "Uuid", "NaiveDateTime", "NaiveDate", "DateTime",
+];
+
+/// 用于解析 #[dysql::mapper(Repository, executor_field)] 的参数
+pub(crate) struct MapperArgs {
+    target: syn::Type,
+    executor_field: syn::Ident,
+}
+
+impl syn::parse::Parse for MapperArgs {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let target = input.parse::<syn::Type>()?;
+        input.parse::<syn::Token!(,)>()?;
+        let executor_field = input.parse::<syn::Ident>()?;
+
+        Ok(Self { target, executor_field })
+    }
+}
+
+/// 根据 trait 中各方法上的 sql 属性，为 `args.target` 生成 trait 的实现
+pub(crate) fn expand(args: MapperArgs, mut item: syn::ItemTrait) -> syn::Result<proc_macro2::TokenStream> {
+    if !item.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(&item.generics, "mapper trait can not have generic parameters"));
+    }
+
+    let mut methods = Vec::new();
+    for trait_item in item.items.iter_mut() {
+        let method = match trait_item {
+            syn::TraitItem::Fn(method) => method,
+            _ => continue,
+        };
+
+        // 取出 sql 属性，trait 中保留其余的属性
+        let sql_attr_idx = method.attrs.iter().position(|attr| {
+            SQL_ATTRS.iter().any(|name| attr.path().is_ident(name))
+        });
+        let sql_attr = match sql_attr_idx {
+            Some(idx) => method.attrs.remove(idx),
+            None if method.default.is_some() => continue,
+            None => return Err(syn::Error::new_spanned(&method.sig, "mapper method need a sql attribute, e.g. #[sql(\"select ...\")]")),
+        };
+
+        methods.push(expand_method(&args, &sql_attr, &method.sig)?);
+    }
+
+    let trait_ident = &item.ident;
+    let target = &args.target;
+    let ret = quote!(
+        #[allow(async_fn_in_trait)]
+        #item
+
+        impl #trait_ident for #target {
+            #(#methods)*
+        }
+    );
+
+    Ok(ret)
+}
+
+/// 生成 mapper 方法的实现
+fn expand_method(args: &MapperArgs, sql_attr: &syn::Attribute, sig: &syn::Signature) -> syn::Result<proc_macro2::TokenStream> {
+    let sql_lit = sql_attr.parse_args::<syn::LitStr>()?;
+    let body = join_sql_lines(&sql_lit.value());
+
+    // 根据 self 的可变性决定以 & 还是 &mut 方式引用 executor
+    let executor_field = &args.executor_field;
+    let executor_declare = match sig.receiver() {
+        Some(syn::Receiver { reference: Some(_), mutability: Some(_), .. }) => quote!(let __dy_executor = &mut self.#executor_field;),
+        Some(syn::Receiver { reference: Some(_), mutability: None, .. }) => quote!(let __dy_executor = &self.#executor_field;),
+        _ => return Err(syn::Error::new_spanned(sig, "mapper method must take &self or &mut self")),
+    };
+
+    // 除 self 外最多只能有一个 dto 参数
+    let mut dto: Option<(syn::Ident, bool)> = None;
+    for input in sig.inputs.iter().skip(1) {
+        let typed = match input {
+            syn::FnArg::Typed(typed) => typed,
+            _ => unreachable!(),
+        };
+        if dto.is_some() {
+            return Err(syn::Error::new_spanned(input, "mapper method can only have one dto parameter"));
+        }
+        match &*typed.pat {
+            syn::Pat::Ident(pat) => dto = Some((pat.ident.clone(), matches!(*typed.ty, syn::Type::Reference(_)))),
+            _ => return Err(syn::Error::new_spanned(&typed.pat, "mapper dto parameter must be an identifier")),
+        }
+    }
+
+    let result_type = result_inner_type(sig)?;
+    let kind = sql_attr.path().get_ident().expect("Unexpected error").to_string();
+    let kind = if kind == "sql" { infer_kind(&body, result_type) } else { kind };
+
+    let ret_type = match kind.as_str() {
+        "fetch_all" => Some(generic_arg(result_type, "Vec")?.clone()),
+        "page" => Some(generic_arg(result_type, "Pagination")?.clone()),
+        "cursor_page" => Some(generic_arg(result_type, "CursorPagination")?.clone()),
+        "fetch_optional" => Some(generic_arg(result_type, "Option")?.clone()),
+        "fetch_one" | "fetch_scalar" | "insert" => Some(result_type.clone()),
+        _ => None,
+    };
+
+    let source_file = crate::source_file(proc_macro::Span::call_site());
+    let body_span = sql_lit.span();
+    let st = DyClosure {
+        executor_info: ExecutorInfo::new(syn::parse_quote!(__dy_executor)),
+        dto_info: DtoInfo::new(dto.as_ref().map(|(ident, _)| syn::parse_quote!(#ident))),
+        sql_name: Some(sig.ident.to_string()),
+        ret_type,
+        body,
+        body_lits: vec![sql_lit],
+        sql_files: vec![],
+        source_file,
+        fragment_call: None,
+        header_span: sig.ident.span(),
+        body_span,
+    };
+
+    let query = match kind.as_str() {
+        "fetch_all" => SqlExpand.fetch_all(&st)?,
+        "fetch_one" => SqlExpand.fetch_one(&st)?,
+        "fetch_optional" => SqlExpand.fetch_optional(&st)?,
+        "fetch_scalar" => SqlExpand.fetch_scalar(&st)?,
+        "execute" => SqlExpand.execute(&st)?,
+        "insert" => SqlExpand.insert(&st)?,
+        "page" => SqlExpand.page(&st)?,
+        "cursor_page" => SqlExpand.cursor_page(&st)?,
+        _ => unreachable!(),
+    };
+
+    // 分页查询需要修改 dto 中的分页信息，按值传入的 dto 需要声明为 mut
+    let dto_declare = match &dto {
+        Some((ident, false)) if kind == "page" || kind == "cursor_page" => quote!(let mut #ident = #ident;),
+        _ => quote!(),
+    };
+
+    Ok(quote!(
+        #sig {
+            #executor_declare
+            #dto_declare
+            #query
+        }
+    ))
+}
+
+/// 获取方法返回值 `DySqlResult<T>` 或 `Result<T, DySqlError>` 中的 T
+fn result_inner_type(sig: &syn::Signature) -> syn::Result<&syn::Type> {
+    let err = || syn::Error::new_spanned(&sig.output, "mapper method must return DySqlResult<T> or Result<T, DySqlError>");
+    match &sig.output {
+        syn::ReturnType::Type(_, ty) => first_generic_arg(ty).ok_or_else(err),
+        syn::ReturnType::Default => Err(err()),
+    }
+}
+
+/// 获取 `Vec<T>`，`Pagination<T>`，`CursorPagination<T>`，`Option<T>` 中的 T
+fn generic_arg<'a>(ty: &'a syn::Type, container: &str) -> syn::Result<&'a syn::Type> {
+    match last_segment(ty) {
+        Some(seg) if seg.ident == container => first_generic_arg(ty)
+            .ok_or_else(|| syn::Error::new_spanned(ty, format!("need specify the item type of {}", container))),
+        _ => Err(syn::Error::new_spanned(ty, format!("the return type must be {}<T>", container))),
+    }
+}
+
+fn first_generic_arg(ty: &syn::Type) -> Option<&syn::Type> {
+    match &last_segment(ty)?.arguments {
+        syn::PathArguments::AngleBracketed(args) => args.args.iter().find_map(|arg| match arg {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        }),
+        _ => None,
+    }
+}
+
+fn last_segment(ty: &syn::Type) -> Option<&syn::PathSegment> {
+    match ty {
+        syn::Type::Path(ty) => ty.path.segments.last(),
+        _ => None,
+    }
+}
+
+/// 根据返回值类型和 sql 语句推断查询方式
+fn infer_kind(sql: &str, result_type: &syn::Type) -> String {
+    let container = last_segment(result_type).map(|seg| seg.ident.to_string()).unwrap_or_default();
+    let verb = sql.trim_start().split(|c: char| c.is_whitespace() || c == '(').next().unwrap_or("").to_ascii_lowercase();
+
+    let kind = match container.as_str() {
+        "Vec" => "fetch_all",
+        "Pagination" => "page",
+        "CursorPagination" => "cursor_page",
+        "Option" => "fetch_optional",
+        _ => match verb.as_str() {
+            "select" | "with" if SCALAR_TYPES.contains(&container.as_str()) => "fetch_scalar",
+            "select" | "with" => "fetch_one",
+            "insert" => "insert",
+            _ => "execute",
+        },
+    };
+
+    kind.to_owned()
+}
@@ -1,6 +1,7 @@
-use dysql_core::{save_sql_template, hash_it};
-use dysql_tpl::Template;
-use quote::quote;
+use dysql_core::{save_sql_template, hash_it, extract_params_buf, has_in_list_params, ParseSqlError, SqlDialect};
+use dysql_tpl::{SqlSegmentKind, SqlSegments, Template, TemplateError};
+use dysql_tpl::sql_syntax::is_ident_char;
+use quote::{quote, quote_spanned};
 
 use crate::DyClosure;
 
@@ -10,25 +11,30 @@ impl SqlExpand {
 
     /// expend fetch_all
     pub fn fetch_all(&self, st: &DyClosure) -> syn::Result<proc_macro2::TokenStream>{
+        let executor = st.executor_info.gen_token();
         let dto_ident = &st.dto_info.src;
-        let executor_token = st.executor_info.gen_token();
-        let ret_type = &st.ret_type;
+        let (ret_type, is_tuple) = row_type(&st.ret_type);
 
         // declare named_template at runtime
         let named_template_declare = self.gen_named_template_declare(st)?;
-
-        let dto_token = st.dto_info.gen_token();
         let execute_query = match dto_ident {
             Some(_) => quote!(
-                // query.fetch_all::<_, _, #ret_type>(#executor_token, named_template, Some(#dto_token)).await 
-                #executor_token.dy_fetch_all::<_, #ret_type>(template_id, named_template, Some(#dto_token)).await 
+                // query.fetch_all::<_, _, #ret_type>(__dy_executor, named_template, Some(__dy_dto)).await 
+                #executor.dy_fetch_all::<_, #ret_type>(template_id, named_template, Some(__dy_dto)).await 
             ),
             None => quote!(
-                // query.fetch_all::<_, dysql::EmptyObject, #ret_type>(#executor_token, named_template, None).await 
-                #executor_token.dy_fetch_all::<dysql::EmptyObject, #ret_type>(template_id, named_template, None).await 
+                // query.fetch_all::<_, dysql::EmptyObject, #ret_type>(__dy_executor, named_template, None).await 
+                #executor.dy_fetch_all::<dysql::EmptyObject, #ret_type>(template_id, named_template, None).await 
             ),
         };
 
+        // 元组类型的返回值需要从 TupleRow 中取出
+        let execute_query = if is_tuple {
+            quote!(#execute_query.map(|rows| rows.into_iter().map(dysql::TupleRow::into_inner).collect::<Vec<_>>()))
+        } else {
+            execute_query
+        };
+        let bindings = gen_bindings(st, false);
         let ret = quote!('rst_block: {
             #[cfg(feature = "tokio-postgres")]
             use dysql::TokioPgExecutorAdatper;
@@ -39,6 +45,8 @@ impl SqlExpand {
             #[cfg(feature="rbatis")]
             use dysql::RbatisExecutorAdatper;
 
+            #bindings
+
             #named_template_declare  // let named_sql = ....;
 
             #execute_query
@@ -47,25 +55,133 @@ impl SqlExpand {
         Ok(ret)
     }
 
+    /// expend fetch_stream
+    pub fn fetch_stream(&self, st: &DyClosure) -> syn::Result<proc_macro2::TokenStream>{
+        let executor = st.executor_info.gen_token();
+        let dto_ident = &st.dto_info.src;
+        let (ret_type, is_tuple) = row_type(&st.ret_type);
+
+        // declare named_template at runtime
+        let named_template_declare = self.gen_named_template_declare(st)?;
+        // 返回的 stream 在被 poll 时才会执行查询，所以这里不需要 await
+        let execute_query = match dto_ident {
+            Some(_) => quote!(
+                #executor.dy_fetch_stream::<_, #ret_type>(template_id, named_template, Some(__dy_dto))
+            ),
+            None => quote!(
+                #executor.dy_fetch_stream::<dysql::EmptyObject, #ret_type>(template_id, named_template, None)
+            ),
+        };
+
+        // 元组类型的返回值需要从 TupleRow 中取出
+        let execute_query = if is_tuple {
+            quote!(dysql::TupleRow::unwrap_stream(#execute_query))
+        } else {
+            execute_query
+        };
+        let bindings = gen_bindings(st, false);
+        // 返回值是 stream 而不是 Result，获取模板时出错则返回只包含该错误的 stream
+        let ret = quote!({
+            #[cfg(feature = "tokio-postgres")]
+            use dysql::TokioPgExecutorAdatper;
+
+            #[cfg(feature="sqlx")]
+            use dysql::SqlxExecutorAdatper;
+
+            #[cfg(feature="rbatis")]
+            use dysql::RbatisExecutorAdatper;
+
+            #bindings
+
+            let declare_rst: dysql::DySqlResult<_> = 'rst_block: {
+                #named_template_declare  // let named_sql = ....;
+                Ok((named_template, template_id))
+            };
+
+            match declare_rst {
+                Ok((named_template, template_id)) => #execute_query,
+                Err(e) => dysql::error_stream(e),
+            }
+        });
+
+        Ok(ret)
+    }
+
     /// expend fetch_one
     pub fn fetch_one(&self, st: &DyClosure) -> syn::Result<proc_macro2::TokenStream>{
+        let executor = st.executor_info.gen_token();
+        // 返回值为 Option<T> 时，没有记录返回 None
+        if let Some(inner) = st.ret_type.as_ref().and_then(option_inner_type) {
+            let mut st = st.clone();
+            st.ret_type = Some(inner.clone());
+            return self.fetch_optional(&st);
+        }
+
         let dto_ident = &st.dto_info.src;
-        let executor_token = st.executor_info.gen_token();
-        let ret_type = &st.ret_type;
+        let (ret_type, is_tuple) = row_type(&st.ret_type);
         
         // declare named_template at runtime
         let named_template_declare = self.gen_named_template_declare(st)?;
+        let execute_query = match dto_ident {
+            Some(_) => quote!(
+                #executor.dy_fetch_one::<_, #ret_type>(template_id, named_template, Some(__dy_dto)).await 
+            ),
+            None => quote!(
+                #executor.dy_fetch_one::<dysql::EmptyObject, #ret_type>(template_id, named_template, None).await 
+            ),
+        };
+        
+        // 元组类型的返回值需要从 TupleRow 中取出
+        let execute_query = if is_tuple {
+            quote!(#execute_query.map(dysql::TupleRow::into_inner))
+        } else {
+            execute_query
+        };
+        let bindings = gen_bindings(st, false);
+        let ret = quote!('rst_block: {
+            #[cfg(feature = "tokio-postgres")]
+            use dysql::TokioPgExecutorAdatper;
 
-        let dto_token = st.dto_info.gen_token();
+            #[cfg(feature="sqlx")]
+            use dysql::SqlxExecutorAdatper;
+
+            #[cfg(feature="rbatis")]
+            use dysql::RbatisExecutorAdatper;
+
+            #bindings
+
+            #named_template_declare  // let named_template = ....;
+            
+            #execute_query
+        });
+
+        Ok(ret)
+    }
+
+    /// expend fetch_optional
+    pub fn fetch_optional(&self, st: &DyClosure) -> syn::Result<proc_macro2::TokenStream>{
+        let executor = st.executor_info.gen_token();
+        let dto_ident = &st.dto_info.src;
+        let (ret_type, is_tuple) = row_type(&st.ret_type);
+        
+        // declare named_template at runtime
+        let named_template_declare = self.gen_named_template_declare(st)?;
         let execute_query = match dto_ident {
             Some(_) => quote!(
-                #executor_token.dy_fetch_one::<_, #ret_type>(template_id, named_template, Some(#dto_token)).await 
+                #executor.dy_fetch_optional::<_, #ret_type>(template_id, named_template, Some(__dy_dto)).await 
             ),
             None => quote!(
-                #executor_token.dy_fetch_one::<dysql::EmptyObject, #ret_type>(template_id, named_template, None).await 
+                #executor.dy_fetch_optional::<dysql::EmptyObject, #ret_type>(template_id, named_template, None).await 
             ),
         };
         
+        // 元组类型的返回值需要从 TupleRow 中取出
+        let execute_query = if is_tuple {
+            quote!(#execute_query.map(|row| row.map(dysql::TupleRow::into_inner)))
+        } else {
+            execute_query
+        };
+        let bindings = gen_bindings(st, false);
         let ret = quote!('rst_block: {
             #[cfg(feature = "tokio-postgres")]
             use dysql::TokioPgExecutorAdatper;
@@ -76,6 +192,8 @@ impl SqlExpand {
             #[cfg(feature="rbatis")]
             use dysql::RbatisExecutorAdatper;
 
+            #bindings
+
             #named_template_declare  // let named_template = ....;
             
             #execute_query
@@ -86,23 +204,22 @@ impl SqlExpand {
 
     /// expend fetch_scalar
     pub fn fetch_scalar(&self, st: &DyClosure) -> syn::Result<proc_macro2::TokenStream>{
+        let executor = st.executor_info.gen_token();
         let dto_ident = &st.dto_info.src;
-        let executor_token = st.executor_info.gen_token();
         let ret_type = &st.ret_type;
 
         // declare named_template at runtime
         let named_template_declare = self.gen_named_template_declare(st)?;
-
-        let dto_token = st.dto_info.gen_token();
         let execute_query = match dto_ident {
             Some(_) => quote!(
-                #executor_token.dy_fetch_scalar::< _, #ret_type>(template_id, named_template, Some(#dto_token)).await 
+                #executor.dy_fetch_scalar::< _, #ret_type>(template_id, named_template, Some(__dy_dto)).await 
             ),
             None => quote!(
-                #executor_token.dy_fetch_scalar::<dysql::EmptyObject, #ret_type>(template_id, named_template, None).await 
+                #executor.dy_fetch_scalar::<dysql::EmptyObject, #ret_type>(template_id, named_template, None).await 
             ),
         };
 
+        let bindings = gen_bindings(st, false);
         let ret = quote!('rst_block: {
             #[cfg(feature = "tokio-postgres")]
             use dysql::TokioPgExecutorAdatper;
@@ -112,6 +229,8 @@ impl SqlExpand {
 
             #[cfg(feature="rbatis")]
             use dysql::RbatisExecutorAdatper;
+
+            #bindings
             
             #named_template_declare  // let named_sql = ....;
 
@@ -123,22 +242,25 @@ impl SqlExpand {
 
     /// expend execute
     pub fn execute(&self, st: &DyClosure) -> syn::Result<proc_macro2::TokenStream>{
-        let dto_ident = &st.dto_info.src;
-        let executor_token = st.executor_info.gen_token();
-
         // declare named_template at runtime
         let named_template_declare = self.gen_named_template_declare(st)?;
 
-        let dto_token = st.dto_info.gen_token();
+        self.execute_with(st, named_template_declare)
+    }
+
+    fn execute_with(&self, st: &DyClosure, named_template_declare: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream>{
+        let executor = st.executor_info.gen_token();
+        let dto_ident = &st.dto_info.src;
         let execute_query = match dto_ident {
             Some(_) => quote!(
-                #executor_token.dy_execute(template_id, named_template, Some(#dto_token)).await
+                #executor.dy_execute(template_id, named_template, Some(__dy_dto)).await
             ),
             None => quote!(
-                #executor_token.dy_execute::<_, dysql::EmptyObject>(template_id, named_template, None).await 
+                #executor.dy_execute::<_, dysql::EmptyObject>(template_id, named_template, None).await 
             ),
         };
 
+        let bindings = gen_bindings(st, false);
         let ret = quote!('rst_block: {
             #[cfg(feature = "tokio-postgres")]
             use dysql::TokioPgExecutorAdatper;
@@ -149,6 +271,8 @@ impl SqlExpand {
             #[cfg(feature="rbatis")]
             use dysql::RbatisExecutorAdatper;
 
+            #bindings
+
             #named_template_declare  // let named_sql = ....;
             
             #execute_query
@@ -157,25 +281,58 @@ impl SqlExpand {
         Ok(ret)
     }
 
+    /// expend execute_batch
+    pub fn execute_batch(&self, st: &DyClosure) -> syn::Result<proc_macro2::TokenStream>{
+        let executor = st.executor_info.gen_token();
+        if st.dto_info.src.is_none() {
+            return Err(syn::Error::new(st.header_span, "execute_batch need a collection of dto"));
+        }
+
+        // dto 是集合或迭代器，不能在编译期校验命名参数
+        let named_template_declare = self.gen_named_template_declare_with(st, true, false)?;
+        let bindings = gen_bindings(st, false);
+        let ret = quote!('rst_block: {
+            #[cfg(feature = "tokio-postgres")]
+            use dysql::TokioPgExecutorAdatper;
+
+            #[cfg(feature="sqlx")]
+            use dysql::SqlxExecutorAdatper;
+
+            #[cfg(feature="rbatis")]
+            use dysql::RbatisExecutorAdatper;
+
+            #bindings
+
+            #named_template_declare  // let named_sql = ....;
+            
+            #executor.dy_execute_batch(template_id, named_template, __dy_dto).await
+        });
+
+        Ok(ret)
+    }
+
     /// expend insert
     pub fn insert(&self, st: &DyClosure) -> syn::Result<proc_macro2::TokenStream>{
-        let dto_ident = &st.dto_info.src;
-        let executor_token = st.executor_info.gen_token();
-        let ret_type = &st.ret_type;
-
         // declare named_template at runtime
         let named_template_declare = self.gen_named_template_declare(st)?;
 
-        let dto_token = st.dto_info.gen_token();
+        self.insert_with(st, named_template_declare)
+    }
+
+    fn insert_with(&self, st: &DyClosure, named_template_declare: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream>{
+        let executor = st.executor_info.gen_token();
+        let dto_ident = &st.dto_info.src;
+        let ret_type = &st.ret_type;
         let execute_query = match dto_ident {
             Some(_) => quote!(
-                let insert_rst = #executor_token.dy_insert::<_, #ret_type>(template_id, named_template, Some(#dto_token)).await;
+                let insert_rst = #executor.dy_insert::<_, #ret_type>(template_id, named_template, Some(__dy_dto)).await;
             ),
             None => quote!(
-                let insert_rst = #executor_token.dy_insert::<dysql::EmptyObject, #ret_type>(template_id, named_template, None).await;
+                let insert_rst = #executor.dy_insert::<dysql::EmptyObject, #ret_type>(template_id, named_template, None).await;
             ),
         };
 
+        let bindings = gen_bindings(st, false);
         let ret = quote!('rst_block: {
             #[cfg(feature = "tokio-postgres")]
             use dysql::TokioPgExecutorAdatper;
@@ -186,13 +343,15 @@ impl SqlExpand {
             #[cfg(feature="rbatis")]
             use dysql::RbatisExecutorAdatper;
 
+            #bindings
+
             #named_template_declare  // let named_sql = ....;
 
             #execute_query
             
             let rst = match insert_rst {
                 Ok(Some(insert_id)) => Ok(insert_id),
-                Ok(None) => match #executor_token.dy_fetch_insert_id::<#ret_type>().await {
+                Ok(None) => match #executor.dy_fetch_insert_id::<#ret_type>().await {
                     Ok(Some(insert_id)) => Ok(insert_id),
                     Ok(None) => {
                         break 'rst_block  Err(dysql::DySqlError(dysql::ErrorInner::new(dysql::Kind::QueryError, None, None)));
@@ -212,12 +371,54 @@ impl SqlExpand {
         Ok(ret)
     }
 
+    /// expend upsert，分别生成 `on conflict` 和 `on duplicate key` 两种 sql 模板，运行时根据 executor 的 SqlDialect 选择，
+    /// 有返回值时同 insert 返回记录的 id，否则同 execute 返回影响的记录数
+    pub fn upsert(&self, st: &DyClosure, conflict: &[String], update: &[String]) -> syn::Result<proc_macro2::TokenStream>{
+        let executor = st.executor_info.gen_token();
+        let conflict: Vec<&str> = conflict.iter().map(|c| c.as_str()).collect();
+        let update: Vec<&str> = update.iter().map(|c| c.as_str()).collect();
+
+        // 只对用户编写的 sql 进行表结构和命名参数的校验
+        let template = parse_template(st, &st.body)?;
+        let schema_track = check_schema(st, &template)?;
+        let param_check = gen_param_check(st);
+
+        let mut conflict_st = st.clone();
+        conflict_st.body = splice_upsert_clause(&st.body, &SqlDialect::postgres.upsert_clause(&conflict, &update), SqlDialect::postgres)
+            .map_err(|e| syn::Error::new(st.body_span, e))?;
+        let conflict_declare = self.gen_named_template_declare_with(&conflict_st, false, false)?;
+
+        let mut mysql_st = st.clone();
+        mysql_st.body = splice_upsert_clause(&st.body, &SqlDialect::mysql.upsert_clause(&conflict, &update), SqlDialect::mysql)
+            .map_err(|e| syn::Error::new(st.body_span, e))?;
+        let mysql_declare = self.gen_named_template_declare_with(&mysql_st, false, false)?;
+
+        let named_template_declare = quote!(
+            #schema_track
+            #param_check
+            let (named_template, template_id) = match #executor.get_dialect() {
+                dysql::SqlDialect::mysql => {
+                    #mysql_declare
+                    (named_template, template_id)
+                },
+                _ => {
+                    #conflict_declare
+                    (named_template, template_id)
+                },
+            };
+        );
+
+        match st.ret_type {
+            Some(_) => self.insert_with(st, named_template_declare),
+            None => self.execute_with(st, named_template_declare),
+        }
+    }
+
     /// expend page query
     pub fn page(&self, st: &DyClosure) -> syn::Result<proc_macro2::TokenStream>{
+        let executor = st.executor_info.gen_token();
         let dto_ident = &st.dto_info.src;
-        let executor_token = st.executor_info.gen_token();
-        let ret_type = &st.ret_type;
-        let dto_token = st.dto_info.gen_token();
+        let (ret_type, is_tuple) = row_type(&st.ret_type);
 
         // declare named_template at runtime
         let named_template_declare = self.gen_named_template_declare(st)?;
@@ -225,13 +426,22 @@ impl SqlExpand {
         // 生成 count 查询的调用
         let execute_count_query = match dto_ident {
             Some(_) => quote!(
-                let count_rst = #executor_token.dy_page_count::<_, i64>(template_id, named_template.clone(), Some(&#dto_token)).await;
+                let count_rst = #executor.dy_page_count::<_, i64>(template_id, named_template.clone(), Some(&*__dy_dto)).await;
             ),
             None => quote!(
-                let count_rst = #executor_token.dy_page_count::<dysql::EmptyObject, i64>(template_id, named_template.clone(), None).await;
+                let count_rst = #executor.dy_page_count::<dysql::EmptyObject, i64>(template_id, named_template.clone(), None).await;
             ),
         };
 
+        let execute_page_query = quote!(#executor.dy_page_all::<_, #ret_type>(template_id, named_template, &*__dy_dto).await);
+        // 元组类型的返回值需要从 TupleRow 中取出
+        let execute_page_query = if is_tuple {
+            quote!(#execute_page_query.map(|pg| pg.map(dysql::TupleRow::into_inner)))
+        } else {
+            execute_page_query
+        };
+
+        let bindings = gen_bindings(st, true);
         let ret = quote!('rst_block: {
             #[cfg(feature = "tokio-postgres")]
             use dysql::TokioPgExecutorAdatper;
@@ -242,6 +452,8 @@ impl SqlExpand {
             #[cfg(feature="rbatis")]
             use dysql::RbatisExecutorAdatper;
 
+            #bindings
+
             #named_template_declare  // let named_sql = ....;
 
             #execute_count_query
@@ -249,10 +461,48 @@ impl SqlExpand {
                 break 'rst_block  Err(dysql::DySqlError(dysql::ErrorInner::new(dysql::Kind::QueryError, Some(Box::new(e)), None)))
             }
             let count = count_rst.expect("Unexpected error");
-            #dto_ident.init(count as u64);
+            __dy_dto.init(count as u64);
 
             // execute page_all query
-            #executor_token.dy_page_all::<_, #ret_type>(template_id, named_template, &#dto_token).await 
+            #execute_page_query
+        });
+
+        Ok(ret)
+    }
+
+    /// expend cursor_page query
+    pub fn cursor_page(&self, st: &DyClosure) -> syn::Result<proc_macro2::TokenStream>{
+        let executor = st.executor_info.gen_token();
+        let (ret_type, is_tuple) = row_type(&st.ret_type);
+        // 下一页的游标需要按字段名从记录中取值，元组没有字段名
+        if is_tuple {
+            return Err(syn::Error::new(st.header_span, "cursor_page does not support tuple return type, use a struct which derives Content"));
+        }
+
+        // declare named_template at runtime
+        let named_template_declare = self.gen_named_template_declare(st)?;
+
+        let bindings = gen_bindings(st, true);
+        let ret = quote!('rst_block: {
+            #[cfg(feature = "tokio-postgres")]
+            use dysql::TokioPgExecutorAdatper;
+
+            #[cfg(feature="sqlx")]
+            use dysql::SqlxExecutorAdatper;
+
+            #[cfg(feature="rbatis")]
+            use dysql::RbatisExecutorAdatper;
+
+            #bindings
+
+            #named_template_declare  // let named_sql = ....;
+
+            if let Err(e) = __dy_dto.init() {
+                break 'rst_block Err(e)
+            }
+            let _ = template_id;
+
+            #executor.dy_cursor_page_all::<_, #ret_type>(named_template, &*__dy_dto).await
         });
 
         Ok(ret)
@@ -262,6 +512,11 @@ impl SqlExpand {
     /// 
     /// st: 在编译时生成的包含 sql 的结构体;
     fn gen_named_template_declare(&self, st: &crate::DyClosure) -> syn::Result<proc_macro2::TokenStream> {
+        self.gen_named_template_declare_with(st, true, true)
+    }
+
+    /// 同 gen_named_template_declare，可以指定是否校验表结构以及命名参数 (如 dto 为集合时不校验命名参数)
+    fn gen_named_template_declare_with(&self, st: &crate::DyClosure, with_schema_check: bool, with_param_check: bool) -> syn::Result<proc_macro2::TokenStream> {
         // 根据 sql body 生成唯一 hash 标识
         let template_id = hash_it(&st.body);
         
@@ -274,28 +529,444 @@ impl SqlExpand {
         
         match std::env::var("DYSQL_PESIST_SQL") {
             Ok(val) if val.to_ascii_uppercase() == "TRUE" => {
-                save_sql_template(source_file, template_id, &st.body, st.sql_name.clone()).unwrap();
+                save_sql_template(source_file, template_id, &st.body, st.sql_name.clone())
+                    .map_err(|e| syn::Error::new(st.body_span, format!("persist sql template failed: {}", e)))?;
             },
             _ => (),
         }
         
         // 根据 sql 生成模板
-        let template = Template::new(&st.body).expect("error: generate template from sql failed");
+        let template = parse_template(st, &st.body)?;
+        // 命名参数格式错误时在编译期报错
+        if let Err(e) = extract_params_any_dialect(&mask_template_tags(&st.body)) {
+            let (span, near) = sql_error_location(st, e.offset);
+            return Err(syn::Error::new(span, format!("{} near `{}`", e, near)));
+        }
+        // 根据配置决定是否用表结构快照校验 sql
+        let schema_track = if with_schema_check { check_schema(st, &template)? } else { quote!() };
+        // 校验 sql 中的命名参数是否都是 dto 的字段
+        let param_check = if with_param_check { gen_param_check(st) } else { quote!() };
+        // 引用 sql 文件，使 sql 文件变化时触发重新编译
+        let sql_files = &st.sql_files;
         // 将模板序列化，接下来通过 TokenSteam 放在编译后的文件里，可以加快加载速度
         let serd_template = template.serialize();
+        // 静态 sql 在编译期生成各方言的最终 sql，运行时只需绑定参数
+        let (static_sql, static_sql_ref) = match gen_static_sql(&template) {
+            Some(static_sql) => (static_sql, quote!(Some(&STATIC_SQL))),
+            None => (quote!(), quote!(None)),
+        };
+        let sql_name = match &st.sql_name {
+            Some(name) => quote!(Some(#name)),
+            None => quote!(None),
+        };
 
         // 生成 TokenStream
         let rst = quote!(
-            // 优先从 cache 中加载 sql 模板，如果 cache 中没有，则直接从序列化的二进制变量中加载并缓存 sql 模板
-            let (named_template, template_id) = match dysql::get_sql_template(#template_id) {
-                Some(tpl) => (tpl, #template_id),
+            #schema_track
+            #param_check
+            #(const _: &[u8] = include_bytes!(#sql_files);)*
+            // 开发模式下优先使用磁盘上覆盖的 sql 模板；
+            // 否则优先从 cache 中加载 sql 模板，如果 cache 中没有，则直接从序列化的二进制变量中加载并缓存 sql 模板
+            #static_sql
+            let (named_template, template_id) = match dysql::get_override_template(#template_id, #sql_name) {
+                Some((tpl, id)) => (tpl, dysql::TemplateId::from(id)),
                 None => {
-                    let serd_template =  [#(#serd_template,)*];
-                    let tpl = dysql::put_sql_template(#template_id, &serd_template).expect("Unexpected error when put_sql_template");
-                    (tpl, #template_id)
+                    let tpl = match dysql::get_sql_template(#template_id) {
+                        Some(tpl) => tpl,
+                        None => {
+                            let serd_template =  [#(#serd_template,)*];
+                            match dysql::put_sql_template(#template_id, &serd_template) {
+                                Ok(tpl) => tpl,
+                                Err(e) => break 'rst_block Err(e),
+                            }
+                        },
+                    };
+                    (tpl, dysql::TemplateId { id: #template_id, static_sql: #static_sql_ref })
                 },
             };
         );
         Ok(rst)
     }
 }
+
+/// 模板中没有任何标签时，生成包含各方言下最终 sql 及参数名的 `STATIC_SQL`，否则返回 None
+fn gen_static_sql(template: &Template) -> Option<proc_macro2::TokenStream> {
+    if !template.is_static() {
+        return None;
+    }
+
+    let named_sql = template.render_sql_variant(|_| false, "");
+    // IN 列表参数需要在运行时根据列表长度展开
+    if has_in_list_params(&named_sql) {
+        return None;
+    }
+    let extract = |dialect| {
+        let mut buf = Vec::<u8>::with_capacity(named_sql.len());
+        let param_names = extract_params_buf(&named_sql, &mut buf, dialect).ok()?;
+        let sql = String::from_utf8(buf).ok()?;
+        Some((sql, param_names))
+    };
+    let (Some((pg_sql, param_names)), Some((mysql_sql, _)), Some((sqlite_sql, _))) =
+        (extract(SqlDialect::postgres), extract(SqlDialect::mysql), extract(SqlDialect::sqlite))
+    else {
+        // 参数提取失败时交由运行时处理并报错
+        return None;
+    };
+
+    Some(quote!(
+        static STATIC_SQL: dysql::StaticSql = dysql::StaticSql {
+            postgres: #pg_sql,
+            mysql: #mysql_sql,
+            sqlite: #sqlite_sql,
+            param_names: &[#(#param_names,)*],
+        };
+    ))
+}
+
+/// 在生成的代码块开始处对 executor 和 dto 表达式各求值一次，之后只使用 `__dy_executor` 和 `__dy_dto`，
+/// `mut_dto` 为 true 时以 &mut 绑定 dto (分页查询需要修改 dto)
+fn gen_bindings(st: &DyClosure, mut_dto: bool) -> proc_macro2::TokenStream {
+    let executor = st.executor_info.gen_binding();
+    let dto = if mut_dto { st.dto_info.gen_mut_binding() } else { st.dto_info.gen_binding() };
+
+    quote!(#executor #dto)
+}
+
+/// 返回值类型为元组时，用 dysql::TupleRow 包装后按列的位置映射，返回映射用的类型以及是否为元组
+fn row_type(ret_type: &Option<syn::Type>) -> (proc_macro2::TokenStream, bool) {
+    match ret_type {
+        Some(syn::Type::Tuple(tuple)) if !tuple.elems.is_empty() => (quote!(dysql::TupleRow<#tuple>), true),
+        _ => (quote!(#ret_type), false),
+    }
+}
+
+/// 获取 Option<T> 中的 T
+fn option_inner_type(ty: &syn::Type) -> Option<&syn::Type> {
+    let seg = match ty {
+        syn::Type::Path(ty) if ty.qself.is_none() => ty.path.segments.last()?,
+        _ => return None,
+    };
+    if seg.ident != "Option" {
+        return None;
+    }
+
+    match &seg.arguments {
+        syn::PathArguments::AngleBracketed(args) if args.args.len() == 1 => match &args.args[0] {
+            syn::GenericArgument::Type(ty) => Some(ty),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// 将 upsert 子句拼接到 sql 中，如果 sql 中有 returning 子句，则放在 returning 之前。
+/// 按方言的词法规则只在字符串、注释及模板标签之外查找 returning，returning 位于模板 section 中时无法确定拼接的位置，返回错误
+fn splice_upsert_clause(sql: &str, clause: &str, dialect: SqlDialect) -> Result<String, String> {
+    let sql = sql.trim_end();
+    let bytes = sql.as_bytes();
+
+    // 模板标签的 (开始, 结束, 标签之后 section 的嵌套层数)
+    let mut tags = vec![];
+    let mut depth = 0_usize;
+    let mut pos = 0;
+    while let Some(start) = sql[pos..].find("{{").map(|i| pos + i) {
+        let end = sql[start..].find("}}").map_or(sql.len(), |i| start + i + 2);
+        match bytes.get(start + 2) {
+            Some(b'#') | Some(b'^') => depth += 1,
+            Some(b'/') => depth = depth.saturating_sub(1),
+            _ => (),
+        }
+        tags.push((start, end, depth));
+        pos = end;
+    }
+
+    const RETURNING: &str = "returning";
+    let mut returning = None;
+    let mut line_comments = vec![];
+    for segment in SqlSegments::new(sql, dialect.syntax()) {
+        if segment.kind == SqlSegmentKind::LineComment {
+            line_comments.push(segment.offset..segment.offset + segment.text.len());
+        }
+        if segment.kind != SqlSegmentKind::Code {
+            continue;
+        }
+        for (i, _) in segment.text.to_ascii_lowercase().match_indices(RETURNING) {
+            let idx = segment.offset + i;
+            // 排除 `:returning` 参数、`t.returning` 字段等
+            let is_word = (idx == 0 || !(is_ident_char(bytes[idx - 1]) || matches!(bytes[idx - 1], b':' | b'.' | b'$')))
+                && bytes.get(idx + RETURNING.len()).is_none_or(|c| !is_ident_char(*c));
+            let in_tag = tags.iter().any(|(start, end, _)| *start <= idx && idx < *end);
+            if is_word && !in_tag {
+                returning = Some(idx);
+            }
+        }
+    }
+
+    // 拼接在单行注释之后时需要换行，否则子句会被注释掉
+    let join_after = |head: &str| {
+        let end = head.len();
+        match line_comments.iter().any(|c| c.start < end && end <= c.end) {
+            true => format!("{}\n{}", head, clause),
+            false => format!("{} {}", head, clause),
+        }
+    };
+
+    let idx = match returning {
+        Some(idx) => idx,
+        None => return Ok(join_after(sql)),
+    };
+    let depth = tags.iter().rev().find(|(_, end, _)| *end <= idx).map_or(0, |(_, _, depth)| *depth);
+    if depth > 0 {
+        return Err("'returning' inside a template section is not supported by upsert, move it out of the section".to_owned());
+    }
+
+    Ok(format!("{} {}", join_after(sql[..idx].trim_end()), &sql[idx..]))
+}
+
+/// 当配置了 DYSQL_SCHEMA_FILE 时，用表结构快照校验模板能渲染出的所有 sql 组合，
+/// 并返回引用快照文件的 TokenStream，使快照变化时触发重新编译
+fn check_schema(st: &DyClosure, template: &Template) -> syn::Result<proc_macro2::TokenStream> {
+    let schema_file = match std::env::var("DYSQL_SCHEMA_FILE") {
+        Ok(val) if !val.is_empty() => val,
+        _ => return Ok(quote!()),
+    };
+
+    let mut path = std::path::PathBuf::from(&schema_file);
+    if path.is_relative() {
+        if let Ok(dir) = std::env::var("CARGO_MANIFEST_DIR") {
+            path = std::path::PathBuf::from(dir).join(path);
+        }
+    }
+
+    let schema = dysql_core::SchemaSnapshot::load(&path)
+        .map_err(|e| syn::Error::new(st.body_span, format!("load schema snapshot '{}' failed: {}", path.display(), e)))?;
+    schema.check_template(template)
+        .map_err(|e| syn::Error::new(st.body_span, format!("schema check failed: {}", e)))?;
+
+    let path = path.to_string_lossy().to_string();
+    Ok(quote!(const _: &[u8] = include_bytes!(#path);))
+}
+
+/// 生成在编译期校验 sql 中的命名参数 (包括 `:data.name` 这样的字段路径) 都是 dto 字段的代码，
+/// 对于无法得知字段信息的 dto 类型 (如 HashMap) 不做校验
+fn gen_param_check(st: &DyClosure) -> proc_macro2::TokenStream {
+    if st.dto_info.src.is_none() {
+        return quote!();
+    }
+
+    // 命名参数格式错误已在生成模板声明时报告
+    let body = mask_template_tags(&st.body);
+    let params = match extract_params_any_dialect(&body) {
+        Ok(params) => params,
+        Err(_) => return quote!(),
+    };
+
+    let mut params: Vec<&str> = params.iter().map(String::as_str).filter(|p| !p.is_empty()).collect();
+    params.sort_unstable();
+    params.dedup();
+    if params.is_empty() {
+        return quote!();
+    }
+
+    let check_idents: Vec<syn::Ident> = (0..params.len())
+        .map(|i| quote::format_ident!("CHECK_{}", i))
+        .collect();
+    let checks = params.iter().zip(check_idents.iter()).map(|(param, ident)| {
+        let span = param_span(st, param);
+        let msg = format!("sql parameter `:{}` is not a field of the dto", param);
+        quote_spanned!(span=>
+            const #ident: () = if !<T as dysql::Content>::FIELDS.contains_path(#param) {
+                panic!(#msg)
+            };
+        )
+    });
+
+    quote!(
+        {
+            struct __DyParamCheck<T: ?Sized>(std::marker::PhantomData<T>);
+            impl<T: ?Sized + dysql::Content> __DyParamCheck<T> {
+                #(#checks)*
+            }
+            fn __dy_param_check<T: ?Sized + dysql::Content>(_: &T) {
+                #(let _ = __DyParamCheck::<T>::#check_idents;)*
+            }
+            __dy_param_check(&__dy_dto);
+        }
+    )
+}
+
+/// 编译期无法得知调用处的数据库方言，sql 在任一方言的词法规则下能够提取命名参数即可，
+/// 返回各个能够解析该 sql 的方言都提取到的参数；都无法解析时返回 postgres 下的错误
+fn extract_params_any_dialect(body: &str) -> Result<Vec<String>, ParseSqlError> {
+    let mut buf = Vec::with_capacity(body.len());
+    let mut common: Option<Vec<String>> = None;
+    let mut first_err = None;
+    for dialect in [SqlDialect::postgres, SqlDialect::mysql, SqlDialect::sqlite] {
+        buf.clear();
+        match extract_params_buf(body, &mut buf, dialect) {
+            Ok(params) => {
+                common = Some(match common {
+                    Some(common) => common.into_iter().filter(|p| params.contains(&p.as_str())).collect(),
+                    None => params.into_iter().map(str::to_owned).collect(),
+                });
+            }
+            Err(e) => {
+                first_err.get_or_insert(e);
+            }
+        }
+    }
+
+    common.ok_or_else(|| first_err.expect("Unexpected error"))
+}
+
+/// 获取命名参数在 sql 字符串字面量中的位置
+fn param_span(st: &DyClosure, param: &str) -> proc_macro2::Span {
+    let named = format!(":{}", param);
+    sql_span(st, |text| {
+        text.match_indices(&named)
+            .find(|(idx, _)| {
+                let next = text[idx + named.len()..].chars().next();
+                !matches!(next, Some(c) if c.is_alphanumeric() || c == '_' || c == '.')
+            })
+            .map(|(idx, _)| (idx, named.len()))
+    })
+}
+
+/// 获取 sql 解析错误在字符串字面量中的位置及出错处之前同一行的内容，
+/// 错误信息中的 `at byte N` 是相对于合并各行后的 sql 的偏移量，附上这段内容便于在源码中定位
+fn sql_error_location(st: &DyClosure, offset: usize) -> (proc_macro2::Span, &str) {
+    let body = &st.body;
+    let mut offset = offset.min(body.len());
+    while !body.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let line_start = body[..offset].rfind('\n').map_or(0, |idx| idx + 1);
+    let mut ctx_start = offset.saturating_sub(16).max(line_start);
+    while !body.is_char_boundary(ctx_start) {
+        ctx_start += 1;
+    }
+    let ctx_end = body[offset..].chars().next().map_or(offset, |c| offset + c.len_utf8());
+    let snippet = &body[ctx_start..ctx_end];
+
+    let span = sql_span(st, |text| text.find(snippet).map(|idx| (idx + offset - ctx_start, 1)));
+    (span, snippet.trim())
+}
+
+/// 将模板中的 `{{..}}` 标签替换为等长的空格，提取命名参数时不受标签内容的影响，且出错位置的偏移量不变
+fn mask_template_tags(body: &str) -> String {
+    let mut masked = body.as_bytes().to_vec();
+    let mut start = 0;
+    while let Some(open) = body[start..].find("{{").map(|idx| start + idx) {
+        let close = match body[open..].find("}}") {
+            Some(idx) if body[open + idx + 2..].starts_with('}') => open + idx + 3,
+            Some(idx) => open + idx + 2,
+            None => break,
+        };
+        masked[open..close].fill(b' ');
+        start = close;
+    }
+
+    String::from_utf8(masked).expect("Unexpected error")
+}
+
+/// 解析 sql 模板，解析失败时将错误定位到 sql 字符串字面量中出错的标签
+fn parse_template(st: &DyClosure, body: &str) -> syn::Result<Template> {
+    Template::new(body).map_err(|e| {
+        let span = match &e {
+            TemplateError::UnclosedSection(name) => sql_span(st, |text| {
+                ["{{#", "{{^", "{{?"].iter().find_map(|tag| {
+                    let open = format!("{}{}", tag, name);
+                    text.find(&open).map(|idx| (idx, open.len()))
+                })
+            }),
+            TemplateError::UnopenedSection(name) => sql_span(st, |text| {
+                let close = format!("{{{{/{}", name);
+                text.find(&close).map(|idx| (idx, close.len()))
+            }),
+            TemplateError::UnclosedTag => sql_span(st, |text| {
+                text.match_indices("{{").find(|(idx, _)| !text[*idx..].contains("}}")).map(|(idx, _)| (idx, 2))
+            }),
+            TemplateError::IllegalTrimAttribute(attr) => sql_span(st, |text| text.find(attr.as_str()).map(|idx| (idx, attr.len()))),
+            TemplateError::IllegalExpression(expr) if !expr.is_empty() => sql_span(st, |text| text.find(expr.as_str()).map(|idx| (idx, expr.len()))),
+            _ => st.body_span,
+        };
+
+        syn::Error::new(span, format!("parse sql template failed: {}", e))
+    })
+}
+
+/// 在 sql 字符串字面量的源码中查找 `find` 返回的位置 (偏移量, 长度)，找不到时返回 sql body 的位置。
+/// stable 版本的编译器不支持 `subspan`，此时只能返回整个字面量的位置，具体出错处以错误信息为准
+fn sql_span<F>(st: &DyClosure, find: F) -> proc_macro2::Span
+where
+    F: Fn(&str) -> Option<(usize, usize)>,
+{
+    for lit in &st.body_lits {
+        let token = lit.token();
+        let text = token.to_string();
+        if let Some((idx, len)) = find(&text) {
+            return token.subspan(idx..idx + len).unwrap_or_else(|| lit.span());
+        }
+    }
+
+    st.body_span
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_params_any_dialect() {
+        // 只有 mysql 能够解析，`#` 注释中的参数不需要校验
+        let params = extract_params_any_dialect(r"select * from t where id = :id and name <> 'it\'s' # :note").unwrap();
+        assert_eq!(vec!["id"], params);
+
+        // 只返回各方言都提取到的参数
+        let params = extract_params_any_dialect("select * from t where id = :id # :note").unwrap();
+        assert_eq!(vec!["id"], params);
+
+        let err = extract_params_any_dialect("select * from t where id = : id").unwrap_err();
+        assert_eq!(27, err.offset);
+    }
+
+    #[test]
+    fn test_splice_upsert_clause() {
+        let clause = "ON CONFLICT (id) DO NOTHING";
+        assert_eq!(
+            "insert into t (id) values (:id) ON CONFLICT (id) DO NOTHING",
+            splice_upsert_clause("insert into t (id) values (:id)\n", clause, SqlDialect::postgres).unwrap()
+        );
+
+        // returning 之前可以是换行、制表符或模板标签
+        assert_eq!(
+            "insert into t (id) values (:id) ON CONFLICT (id) DO NOTHING RETURNING id",
+            splice_upsert_clause("insert into t (id) values (:id)\n\tRETURNING id", clause, SqlDialect::postgres).unwrap()
+        );
+        assert_eq!(
+            "insert into t (id{{#name}}, name{{/name}}) values (:id{{#name}}, :name{{/name}}) ON CONFLICT (id) DO NOTHING returning id",
+            splice_upsert_clause(
+                "insert into t (id{{#name}}, name{{/name}}) values (:id{{#name}}, :name{{/name}})returning id",
+                clause,
+                SqlDialect::postgres,
+            ).unwrap()
+        );
+
+        // 字符串、注释、参数名及模板标签中的 returning 不是 returning 子句，拼接在单行注释之后时换行
+        assert_eq!(
+            "insert into t (note) values (' returning ') -- returning x\nON CONFLICT (id) DO NOTHING",
+            splice_upsert_clause("insert into t (note) values (' returning ') -- returning x\n", clause, SqlDialect::postgres).unwrap()
+        );
+        assert_eq!(
+            "insert into t (note) values (:returning{{#returning}}{{/returning}}) ON CONFLICT (id) DO NOTHING",
+            splice_upsert_clause("insert into t (note) values (:returning{{#returning}}{{/returning}})", clause, SqlDialect::postgres).unwrap()
+        );
+        assert_eq!(
+            "insert into t (id) values (:id) -- c\nON CONFLICT (id) DO NOTHING returning id",
+            splice_upsert_clause("insert into t (id) values (:id) -- c\nreturning id", clause, SqlDialect::postgres).unwrap()
+        );
+
+        let err = splice_upsert_clause("insert into t (id) values (:id) {{#ret}}returning id{{/ret}}", clause, SqlDialect::postgres);
+        assert!(err.unwrap_err().contains("template section"));
+    }
+}
@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+
+/// .sql 文件中命名 sql 块的起始标记，如: `-- name: find_by_age`
+const BLOCK_MARKER: &str = "-- name:";
+
+/// 用于解析 sql body 中的 file("sql/user.sql", "find_by_age")，
+/// 文件路径相对于当前 crate 的 Cargo.toml 所在目录，不指定块名时使用整个文件
+#[derive(Debug)]
+pub(crate) struct SqlFile {
+    pub(crate) path: syn::LitStr,
+    pub(crate) block: Option<syn::LitStr>,
+}
+
+impl syn::parse::Parse for SqlFile {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let path = input.parse::<syn::LitStr>()?;
+        let block = match input.parse::<syn::Token!(,)>() {
+            Ok(_) => Some(input.parse::<syn::LitStr>()?),
+            Err(_) => None,
+        };
+
+        Ok(Self { path, block })
+    }
+}
+
+impl SqlFile {
+    /// 读取 sql，返回 sql 以及 sql 文件的绝对路径 (用于让 cargo 跟踪文件变化)
+    pub(crate) fn load(&self) -> syn::Result<(String, String)> {
+        let mut path = PathBuf::from(self.path.value());
+        if path.is_relative() {
+            if let Ok(dir) = std::env::var("CARGO_MANIFEST_DIR") {
+                path = PathBuf::from(dir).join(path);
+            }
+        }
+
+        let content = std::fs::read_to_string(&path).map_err(|e| {
+            syn::Error::new(self.path.span(), format!("read sql file '{}' failed: {}", path.display(), e))
+        })?;
+
+        let sql = match &self.block {
+            Some(block) => get_sql_block(&content, &block.value()).ok_or_else(|| {
+                syn::Error::new(block.span(), format!("sql block '{}' is not found in '{}'", block.value(), path.display()))
+            })?,
+            None => strip_comment_lines(content.lines()),
+        };
+
+        Ok((sql, path.to_string_lossy().to_string()))
+    }
+}
+
+/// 获取 `-- name: xxx` 标记之后到下一个标记之前的 sql
+fn get_sql_block(content: &str, block: &str) -> Option<String> {
+    let mut lines = content.lines();
+    lines.find(|line| match line.trim().strip_prefix(BLOCK_MARKER) {
+        Some(name) => name.trim() == block,
+        None => false,
+    })?;
+
+    let lines = lines.take_while(|line| !line.trim().starts_with(BLOCK_MARKER));
+
+    Some(strip_comment_lines(lines))
+}
+
+/// 去掉整行的 `--` 注释，sql 的各行在之后会被合并为一行
+fn strip_comment_lines<'a>(lines: impl Iterator<Item = &'a str>) -> String {
+    let lines: Vec<&str> = lines
+        .filter(|line| !line.trim_start().starts_with("--"))
+        .collect();
+
+    lines.join("\n").trim().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_sql_block() {
+        let content = "-- user queries\n\
+            -- name: find_by_age\n\
+            -- users older than :age\n\
+            select * from test_user\n\
+            where age > :age\n\
+            \n\
+            -- name: count\n\
+            select count(*) from test_user\n";
+
+        assert_eq!(Some("select * from test_user\nwhere age > :age".to_owned()), get_sql_block(content, "find_by_age"));
+        assert_eq!(Some("select count(*) from test_user".to_owned()), get_sql_block(content, "count"));
+        assert_eq!(None, get_sql_block(content, "find"));
+    }
+}
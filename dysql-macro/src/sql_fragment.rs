@@ -1,37 +1,168 @@
-use std::{sync::RwLock, collections::HashMap};
+use std::collections::HashMap;
 
-use once_cell::sync::OnceCell;
-
-/// 存放 sql!() 宏定义的 sql fragment
-pub(crate) static STATIC_SQL_FRAGMENT_MAP: OnceCell<RwLock<HashMap<String, String>>> = OnceCell::new();
-
-/// 获取 sql!() 宏定义的 sql fragment
-pub(crate) fn get_sql_fragment(name: &str)-> Option<String> {
-    let cache = STATIC_SQL_FRAGMENT_MAP.get().expect("Unexpect error: get_sql_fragment()");
-    let fragment = cache.read().expect("Unexpect error: get_sql_fragment()");
-    let fragment = fragment.get(name);
-    let rst = match fragment {
-        Some(v) => Some(v.to_owned()),
-        None => None,
-    };
-
-    rst
-}
+use quote::quote;
 
 /// 用于解析 sql!(sql_fragment_name, sql_fragment) 宏
-/// 该宏用于定义公共的 sql 语句部分
+/// 该宏用于定义公共的 sql 语句部分，形如：
+/// `sql!([pub] name, "...")` 或带参数的 `sql!([pub] name(prefix, ...), "... ${prefix} ...")`
 #[derive(Debug)]
 pub(crate) struct SqlFragment {
-    pub(crate) name: String,
+    pub(crate) vis: syn::Visibility,
+    pub(crate) name: syn::Ident,
+    pub(crate) params: Vec<syn::Ident>,
     pub(crate) value: String,
 }
 
 impl syn::parse::Parse for SqlFragment {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
-        let name= input.parse::<syn::LitStr>()?.value();
+        let vis = input.parse::<syn::Visibility>()?;
+        // 兼容以字符串指定片段名称的写法
+        let name = if input.peek(syn::LitStr) {
+            let name = input.parse::<syn::LitStr>()?;
+            syn::Ident::new(&name.value(), name.span())
+        } else {
+            input.parse::<syn::Ident>()?
+        };
+
+        let params = if input.peek(syn::token::Paren) {
+            let params;
+            syn::parenthesized!(params in input);
+            params.parse_terminated(|p| p.parse::<syn::Ident>(), syn::Token!(,))?.into_iter().collect()
+        } else {
+            vec![]
+        };
+
         input.parse::<syn::Token!(,)>()?;
         let value= input.parse::<syn::LitStr>()?.value();
 
-        Ok(Self { name, value })
+        Ok(Self { vis, name, params, value })
+    }
+}
+
+impl SqlFragment {
+    /// 将 sql 片段定义为一个 macro_rules 宏，使片段同 rust 的其它条目一样按模块作用域及路径引用。
+    /// dysql 的宏遇到尚未展开的片段时，会调用该片段宏，由片段宏将片段内容回传给 dysql 的宏
+    pub(crate) fn expand(&self) -> proc_macro2::TokenStream {
+        let SqlFragment { vis, name, params, value } = self;
+        let params = params.iter().map(|p| p.to_string());
+        let rules = quote!(
+            ([$($cb:tt)*] [$($path:tt)*] $($input:tt)*) => {
+                $($cb)*! { @fragment [$($path)*] (#value, [#(#params),*]) $($input)* }
+            };
+        );
+
+        match vis {
+            // pub 的片段以唯一的隐藏名称通过 #[macro_export] 导出，再在定义处以片段名重新导出，
+            // 本 crate 中通过模块路径引用，其它 crate 中通过 `crate_name::模块路径::name` 引用
+            syn::Visibility::Public(_) => {
+                let export = export_name(name);
+                quote!(
+                    #[doc(hidden)]
+                    #[macro_export]
+                    macro_rules! #export { #rules }
+                    #[allow(unused_imports)]
+                    #vis use #export as #name;
+                )
+            },
+            _ => quote!(
+                #[allow(unused_macros)]
+                macro_rules! #name { #rules }
+                #[allow(unused_imports)]
+                #vis use #name;
+            ),
+        }
     }
-} 
\ No newline at end of file
+}
+
+/// `#[macro_export]` 的宏都导出在 crate 的根模块中，用片段定义的位置生成唯一的名称，避免不同模块中的同名片段冲突
+fn export_name(name: &syn::Ident) -> syn::Ident {
+    let span = name.span().unwrap();
+    let id = dysql_core::hash_it((span.file(), span.line(), span.column(), name.to_string()));
+
+    quote::format_ident!("__dysql_fragment_{}_{:x}", name, id)
+}
+
+/// 片段宏回传的片段内容
+#[derive(Debug, Clone)]
+pub(crate) struct FragmentDef {
+    value: String,
+    params: Vec<String>,
+}
+
+/// 已展开的 sql 片段，以片段引用的路径为 key
+pub(crate) type Fragments = HashMap<String, FragmentDef>;
+
+/// 解析片段宏回传的 `@fragment [path] ("sql fragment", [param, ...])`
+pub(crate) fn parse_fragments(input: syn::parse::ParseStream) -> syn::Result<Fragments> {
+    let mut fragments = Fragments::new();
+    while input.peek(syn::Token!(@)) {
+        input.parse::<syn::Token!(@)>()?;
+        let tag = input.parse::<syn::Ident>()?;
+        if tag != "fragment" {
+            return Err(syn::Error::new(tag.span(), "expected `@fragment`"));
+        }
+
+        let path;
+        syn::bracketed!(path in input);
+        let path = path.parse::<syn::Path>()?;
+
+        let def;
+        syn::parenthesized!(def in input);
+        let value = def.parse::<syn::LitStr>()?.value();
+        def.parse::<syn::Token!(,)>()?;
+        let params;
+        syn::bracketed!(params in def);
+        let params = params.parse_terminated(|p| p.parse::<syn::LitStr>(), syn::Token!(,))?
+            .iter()
+            .map(|p| p.value())
+            .collect();
+
+        fragments.insert(path_key(&path), FragmentDef { value, params });
+    }
+
+    Ok(fragments)
+}
+
+/// sql 片段引用路径的 key
+pub(crate) fn path_key(path: &syn::Path) -> String {
+    quote!(#path).to_string()
+}
+
+impl FragmentDef {
+    /// 用引用处的实参替换片段中的 `${param}`
+    pub(crate) fn apply(&self, path: &syn::Path, args: &[syn::LitStr]) -> syn::Result<String> {
+        if args.len() != self.params.len() {
+            return Err(syn::Error::new_spanned(
+                path,
+                format!("sql fragment `{}` takes {} argument(s) but {} were supplied", path_key(path), self.params.len(), args.len()),
+            ));
+        }
+
+        let mut value = self.value.clone();
+        for (param, arg) in self.params.iter().zip(args) {
+            value = value.replace(&format!("${{{}}}", param), &arg.value());
+        }
+
+        Ok(value)
+    }
+}
+
+/// sql body 中引用了尚未展开的 sql 片段，需要先调用片段宏取得片段内容
+#[derive(Debug, Clone)]
+pub(crate) struct FragmentCall {
+    pub(crate) path: syn::Path,
+    /// 原始的宏输入，会原样传回给 dysql 的宏
+    pub(crate) input: proc_macro2::TokenStream,
+}
+
+impl FragmentCall {
+    /// 生成对片段宏的调用，片段宏展开后会以 `@fragment` 前缀回调 `dysql::#callback!`
+    pub(crate) fn expand(&self, callback: &str) -> proc_macro::TokenStream {
+        let FragmentCall { path, input } = self;
+        let callback = syn::Ident::new(callback, proc_macro2::Span::call_site());
+
+        quote!(
+            #path! { [dysql::#callback] [#path] #input }
+        ).into()
+    }
+}
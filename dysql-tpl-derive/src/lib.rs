@@ -23,6 +23,8 @@ type UnitFields = Punctuated<syn::Field, Comma>;
 
 struct Field {
     hash: u64,
+    name: String,
+    ty: syn::Type,
     field: TokenStream2,
     callback: Option<Path>,
 }
@@ -77,6 +79,7 @@ pub fn content_derive(input: TokenStream) -> TokenStream {
     };
 
     let mut flatten = Vec::new();
+    let mut flatten_types = Vec::new();
     let md_callback: Path = syn::parse(quote!(::dysql::encoding::encode_cmark).into()).unwrap();
     let mut fields = fields
         .enumerate()
@@ -84,6 +87,7 @@ pub fn content_derive(input: TokenStream) -> TokenStream {
             let mut callback = None;
             let mut rename = None;
             let mut skip = false;
+            let ty = field.ty.clone();
 
             match Ramhorns::try_from_attributes(&field.attrs) {
                 Ok(Some(ramhorns)) => {
@@ -102,6 +106,7 @@ pub fn content_derive(input: TokenStream) -> TokenStream {
                             },
                             |ident| quote!(#ident),
                         ));
+                        flatten_types.push(field.ty.clone());
                         skip = true;
                     }
                     if let Some(lit_str) = ramhorns.rename {
@@ -141,6 +146,8 @@ pub fn content_derive(input: TokenStream) -> TokenStream {
 
             Some(Field {
                 hash,
+                name,
+                ty,
                 field,
                 callback,
             })
@@ -235,6 +242,27 @@ pub fn content_derive(input: TokenStream) -> TokenStream {
         }
     });
 
+    // `{{#if expr}}` 获取字段值
+    let field_operand = fields.iter().map(|Field { field, hash, .. }| {
+        quote! {
+            #hash => self.#field.path_operand(path),
+        }
+    });
+
+    // 编译期校验 sql 命名参数所需的字段信息
+    let content_fields = fields.iter().map(|Field { name: field_name, ty, .. }| {
+        // 通过引用包含自身的字段不展开字段信息，避免 FIELDS 的求值出现循环
+        if refers_to(quote!(#ty), name) {
+            quote! {
+                ::dysql::ContentField { name: #field_name, fields: ::dysql::ContentFields::Unknown },
+            }
+        } else {
+            quote! {
+                ::dysql::ContentField { name: #field_name, fields: <#ty as ::dysql::Content>::FIELDS },
+            }
+        }
+    });
+
     let flatten = &*flatten;
     let fields = fields.iter().map(|Field { field, .. }| field);
 
@@ -250,6 +278,11 @@ pub fn content_derive(input: TokenStream) -> TokenStream {
     // FIXME: decouple lifetimes from actual generics with trait boundaries
     let tokens = quote! {
         impl#generics ::dysql::Content for #name#generics #where_clause {
+
+            const FIELDS: ::dysql::ContentFields = ::dysql::ContentFields::Named {
+                fields: &[ #( #content_fields )* ],
+                flatten: &[ #( <#flatten_types as ::dysql::Content>::FIELDS, )* ],
+            };
             
             #[inline]
             fn capacity_hint(&self, tpl: &::dysql::Template) -> usize {
@@ -371,6 +404,14 @@ pub fn content_derive(input: TokenStream) -> TokenStream {
                     )
                 }
             }
+
+            #[inline]
+            fn field_operand(&self, hash: u64, name: &str, path: &[::dysql::FieldPath]) -> Option<::dysql::Operand<'_>> {
+                match hash {
+                    #( #field_operand )*
+                    _ => None #( .or_else(|| self.#flatten.field_operand(hash, name, path)) )*
+                }
+            }
         }
     };
 
@@ -378,3 +419,12 @@ pub fn content_derive(input: TokenStream) -> TokenStream {
 
     TokenStream::from(tokens)
 }
+
+/// 判断字段类型中是否出现了 `Self` 或结构体自身的名字
+fn refers_to(ty: TokenStream2, name: &syn::Ident) -> bool {
+    ty.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => ident == *name || ident == "Self",
+        proc_macro2::TokenTree::Group(group) => refers_to(group.stream(), name),
+        _ => false,
+    })
+}
@@ -10,7 +10,7 @@
 use crate::encoding::Encoder;
 use crate::simple::simple_section::SimpleSection;
 use crate::simple::{SimpleValue, SimpleError, SimpleInnerError, RawStr, RawString};
-use crate::template::{Section, Template};
+use crate::template::{FieldPath, Operand, Section, Template};
 use crate::traits::ContentSequence;
 
 use arrayvec::ArrayVec;
@@ -29,10 +29,85 @@ use paste::paste;
 //     hasher.finish()
 // }
 
+/// 描述 `Content` 类型所包含的字段，用于在编译期校验 sql 中的命名参数
+#[derive(Debug, Clone, Copy)]
+pub enum ContentFields {
+    /// 无法得知字段信息 (如 HashMap 或基本类型)，不做校验
+    Unknown,
+    /// 由 `#[derive(Content)]` 生成的字段列表，`flatten` 为被展开的字段
+    Named {
+        fields: &'static [ContentField],
+        flatten: &'static [ContentFields],
+    },
+}
+
+/// `ContentFields` 中的一个字段
+#[derive(Debug, Clone, Copy)]
+pub struct ContentField {
+    pub name: &'static str,
+    pub fields: ContentFields,
+}
+
+impl ContentFields {
+    /// 判断以 `.` 分隔的字段路径 (如 `data.name`) 是否存在，字段信息未知时视为存在
+    pub const fn contains_path(&self, path: &str) -> bool {
+        self.contains_from(path.as_bytes(), 0)
+    }
+
+    const fn contains_from(&self, path: &[u8], start: usize) -> bool {
+        let (fields, flatten) = match self {
+            ContentFields::Unknown => return true,
+            ContentFields::Named { fields, flatten } => (*fields, *flatten),
+        };
+
+        let mut end = start;
+        while end < path.len() && path[end] != b'.' {
+            end += 1;
+        }
+
+        let mut i = 0;
+        while i < fields.len() {
+            if name_eq(fields[i].name.as_bytes(), path, start, end) {
+                return end == path.len() || fields[i].fields.contains_from(path, end + 1);
+            }
+            i += 1;
+        }
+
+        let mut i = 0;
+        while i < flatten.len() {
+            if flatten[i].contains_from(path, start) {
+                return true;
+            }
+            i += 1;
+        }
+
+        false
+    }
+}
+
+const fn name_eq(name: &[u8], path: &[u8], start: usize, end: usize) -> bool {
+    if name.len() != end - start {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < name.len() {
+        if name[i] != path[start + i] {
+            return false;
+        }
+        i += 1;
+    }
+
+    true
+}
+
 /// Trait allowing the rendering to quickly access data stored in the type that
 /// implements it. You needn't worry about implementing it, in virtually all
 /// cases the `#[derive(Content)]` attribute above your types should be sufficient.
 pub trait Content {
+    /// 该类型包含的字段信息，用于在编译期校验 sql 中的命名参数
+    const FIELDS: ContentFields = ContentFields::Unknown;
+
     /// Marks whether this content is truthy. Used when attempting to render a section.
     #[inline]
     fn is_truthy(&self) -> bool {
@@ -70,6 +145,21 @@ pub trait Content {
         Err(SimpleInnerError(format!("the data type of field is not supported")).into())
     }
 
+    /// 作为 `{{#if expr}}` 中的操作数，不能比较的类型只判断真假
+    #[inline]
+    fn operand(&self) -> Operand<'_> {
+        Operand::Other(self.is_truthy())
+    }
+
+    /// 按字段路径获取 `{{#if expr}}` 中的操作数，路径为空时为 self，字段不存在时返回 None
+    #[inline]
+    fn path_operand(&self, path: &[FieldPath]) -> Option<Operand<'_>> {
+        match path.split_first() {
+            Some((field, rest)) => self.field_operand(field.hash, &field.name, rest),
+            None => Some(self.operand()),
+        }
+    }
+
     /// Render a section with self.
     #[inline]
     fn render_section<C, E, IC>(
@@ -244,6 +334,13 @@ pub trait Content {
     {
         Ok(false)
     }
+
+    /// Get a field by the hash **or** string of its name, then follow the rest `path`,
+    /// as an operand of `{{#if expr}}`. Returns `None` if the field doesn't exist.
+    #[inline]
+    fn field_operand(&self, _hash: u64, _name: &str, _path: &[FieldPath]) -> Option<Operand<'_>> {
+        None
+    }
 }
 
 impl Content for () {
@@ -279,6 +376,11 @@ impl Content for str {
     {
         Ok(SimpleValue::t_str(RawStr(self as *const str)))
     }
+
+    #[inline]
+    fn operand(&self) -> Operand<'_> {
+        Operand::Str(self)
+    }
 }
 
 impl Content for String {
@@ -307,6 +409,11 @@ impl Content for String {
     {
         Ok(SimpleValue::t_String(RawString(self as *const String)))
     }
+
+    #[inline]
+    fn operand(&self) -> Operand<'_> {
+        Operand::Str(self)
+    }
 }
 
 impl Content for bool {
@@ -331,6 +438,11 @@ impl Content for bool {
     {
         Ok(SimpleValue::t_bool(*self))
     }
+
+    #[inline]
+    fn operand(&self) -> Operand<'_> {
+        Operand::Bool(*self)
+    }
 }
 
 macro_rules! impl_number_types {
@@ -360,6 +472,11 @@ macro_rules! impl_number_types {
                     {
                         Ok(SimpleValue::[<t_ $ty>](*self))
                     }
+
+                    #[inline]
+                    fn operand(&self) -> Operand<'_> {
+                        Operand::Int(*self as i128)
+                    }
                 }
             }
         )*
@@ -391,6 +508,11 @@ impl Content for f32 {
     {
         Ok(SimpleValue::t_f32(*self))
     }
+
+    #[inline]
+    fn operand(&self) -> Operand<'_> {
+        Operand::Float(*self as f64)
+    }
 }
 
 impl Content for f64 {
@@ -416,9 +538,16 @@ impl Content for f64 {
     {
         Ok(SimpleValue::t_f64(*self))
     }
+
+    #[inline]
+    fn operand(&self) -> Operand<'_> {
+        Operand::Float(*self)
+    }
 }
 
 impl<T: Content> Content for Option<T> {
+    const FIELDS: ContentFields = T::FIELDS;
+
     #[inline]
     fn is_truthy(&self) -> bool {
         self.is_some()
@@ -459,6 +588,19 @@ impl<T: Content> Content for Option<T> {
         }
     }
 
+    #[inline]
+    fn operand(&self) -> Operand<'_> {
+        match self {
+            Some(inner) => inner.operand(),
+            None => Operand::Null,
+        }
+    }
+
+    #[inline]
+    fn field_operand(&self, hash: u64, name: &str, path: &[FieldPath]) -> Option<Operand<'_>> {
+        self.as_ref().and_then(|inner| inner.field_operand(hash, name, path))
+    }
+
     #[inline]
     fn render_section<C, E, IC>(
         &self,
@@ -551,6 +693,19 @@ where
         }
     }
 
+    #[inline]
+    fn operand(&self) -> Operand<'_> {
+        match self {
+            Ok(inner) => inner.operand(),
+            Err(_) => Operand::Null,
+        }
+    }
+
+    #[inline]
+    fn field_operand(&self, hash: u64, name: &str, path: &[FieldPath]) -> Option<Operand<'_>> {
+        self.as_ref().ok().and_then(|inner| inner.field_operand(hash, name, path))
+    }
+
     #[inline]
     fn render_section<C, E, IC>(
         &self,
@@ -604,6 +759,45 @@ impl<T: Content> Content for Vec<T> {
     fn is_truthy(&self) -> bool {
         !self.is_empty()
     }
+
+    #[inline]
+    fn apply_unescaped(&self) -> Result<SimpleValue, SimpleError> {
+        self.iter().map(|item| item.apply_unescaped()).collect::<Result<_, _>>().map(SimpleValue::t_list)
+    }
+
+    #[inline]
+    fn operand(&self) -> Operand<'_> {
+        Operand::List(self.len())
+    }
+
+    #[inline]
+    fn apply_section<C>(
+        &self,
+        section: SimpleSection<C>
+    ) -> Result<SimpleValue, SimpleError>
+    where
+        C: ContentSequence,
+    {
+        section.with(self).apply()
+    }
+
+    /// 按下标获取元素的值，用于绑定 IN 列表展开后的参数 (如 `ids.0`)
+    #[inline]
+    fn apply_field_unescaped(
+        &self,
+        _hash: u64,
+        name: &str,
+    ) -> Result<SimpleValue, SimpleError> {
+        match name.parse::<usize>().ok().and_then(|idx| self.get(idx)) {
+            Some(item) => item.apply_unescaped(),
+            None => Err(SimpleInnerError(format!("index '{}' is out of range", name)).into()),
+        }
+    }
+
+    #[inline]
+    fn field_operand(&self, _hash: u64, name: &str, path: &[FieldPath]) -> Option<Operand<'_>> {
+        name.parse::<usize>().ok().and_then(|idx| self.get(idx)).and_then(|item| item.path_operand(path))
+    }
     
     #[inline]
     fn render_section<C, E, IC>(
@@ -644,6 +838,45 @@ impl<T: Content> Content for [T] {
         !self.is_empty()
     }
 
+    #[inline]
+    fn apply_unescaped(&self) -> Result<SimpleValue, SimpleError> {
+        self.iter().map(|item| item.apply_unescaped()).collect::<Result<_, _>>().map(SimpleValue::t_list)
+    }
+
+    #[inline]
+    fn operand(&self) -> Operand<'_> {
+        Operand::List(self.len())
+    }
+
+    #[inline]
+    fn apply_section<C>(
+        &self,
+        section: SimpleSection<C>
+    ) -> Result<SimpleValue, SimpleError>
+    where
+        C: ContentSequence,
+    {
+        section.with(self).apply()
+    }
+
+    /// 按下标获取元素的值，用于绑定 IN 列表展开后的参数 (如 `ids.0`)
+    #[inline]
+    fn apply_field_unescaped(
+        &self,
+        _hash: u64,
+        name: &str,
+    ) -> Result<SimpleValue, SimpleError> {
+        match name.parse::<usize>().ok().and_then(|idx| self.get(idx)) {
+            Some(item) => item.apply_unescaped(),
+            None => Err(SimpleInnerError(format!("index '{}' is out of range", name)).into()),
+        }
+    }
+
+    #[inline]
+    fn field_operand(&self, _hash: u64, name: &str, path: &[FieldPath]) -> Option<Operand<'_>> {
+        name.parse::<usize>().ok().and_then(|idx| self.get(idx)).and_then(|item| item.path_operand(path))
+    }
+
     #[inline]
     fn render_section<C, E, IC>(
         &self,
@@ -683,6 +916,45 @@ impl<T: Content, const N: usize> Content for [T; N] {
         !self.is_empty()
     }
 
+    #[inline]
+    fn apply_unescaped(&self) -> Result<SimpleValue, SimpleError> {
+        self.iter().map(|item| item.apply_unescaped()).collect::<Result<_, _>>().map(SimpleValue::t_list)
+    }
+
+    #[inline]
+    fn operand(&self) -> Operand<'_> {
+        Operand::List(self.len())
+    }
+
+    #[inline]
+    fn apply_section<C>(
+        &self,
+        section: SimpleSection<C>
+    ) -> Result<SimpleValue, SimpleError>
+    where
+        C: ContentSequence,
+    {
+        section.with(self).apply()
+    }
+
+    /// 按下标获取元素的值，用于绑定 IN 列表展开后的参数 (如 `ids.0`)
+    #[inline]
+    fn apply_field_unescaped(
+        &self,
+        _hash: u64,
+        name: &str,
+    ) -> Result<SimpleValue, SimpleError> {
+        match name.parse::<usize>().ok().and_then(|idx| self.get(idx)) {
+            Some(item) => item.apply_unescaped(),
+            None => Err(SimpleInnerError(format!("index '{}' is out of range", name)).into()),
+        }
+    }
+
+    #[inline]
+    fn field_operand(&self, _hash: u64, name: &str, path: &[FieldPath]) -> Option<Operand<'_>> {
+        name.parse::<usize>().ok().and_then(|idx| self.get(idx)).and_then(|item| item.path_operand(path))
+    }
+
     #[inline]
     fn render_section<C, E, IC>(
         &self,
@@ -722,6 +994,45 @@ impl<T: Content, const N: usize> Content for ArrayVec<T, N> {
         !self.is_empty()
     }
 
+    #[inline]
+    fn apply_unescaped(&self) -> Result<SimpleValue, SimpleError> {
+        self.iter().map(|item| item.apply_unescaped()).collect::<Result<_, _>>().map(SimpleValue::t_list)
+    }
+
+    #[inline]
+    fn operand(&self) -> Operand<'_> {
+        Operand::List(self.len())
+    }
+
+    #[inline]
+    fn apply_section<C>(
+        &self,
+        section: SimpleSection<C>
+    ) -> Result<SimpleValue, SimpleError>
+    where
+        C: ContentSequence,
+    {
+        section.with(self).apply()
+    }
+
+    /// 按下标获取元素的值，用于绑定 IN 列表展开后的参数 (如 `ids.0`)
+    #[inline]
+    fn apply_field_unescaped(
+        &self,
+        _hash: u64,
+        name: &str,
+    ) -> Result<SimpleValue, SimpleError> {
+        match name.parse::<usize>().ok().and_then(|idx| self.get(idx)) {
+            Some(item) => item.apply_unescaped(),
+            None => Err(SimpleInnerError(format!("index '{}' is out of range", name)).into()),
+        }
+    }
+
+    #[inline]
+    fn field_operand(&self, _hash: u64, name: &str, path: &[FieldPath]) -> Option<Operand<'_>> {
+        name.parse::<usize>().ok().and_then(|idx| self.get(idx)).and_then(|item| item.path_operand(path))
+    }
+
     #[inline]
     fn render_section<C, E, IC>(
         &self,
@@ -878,6 +1189,10 @@ where
             None => Ok(false),
         }
     }
+
+    fn field_operand(&self, _: u64, name: &str, path: &[FieldPath]) -> Option<Operand<'_>> {
+        self.get(name).and_then(|v| v.path_operand(path))
+    }
 }
 
 impl<K, V> Content for BTreeMap<K, V>
@@ -1002,13 +1317,19 @@ where
             None => Ok(false),
         }
     }
+
+    fn field_operand(&self, _: u64, name: &str, path: &[FieldPath]) -> Option<Operand<'_>> {
+        self.get(name).and_then(|v| v.path_operand(path))
+    }
 }
 
 macro_rules! impl_pointer_types {
-    ($( $ty:ty $(: $bounds:ident)? ),*) => {
+    ($fields:expr; $( $ty:ty $(: $bounds:ident)? ),*) => {
         $(
             impl<T: Content $(+ $bounds)? + ?Sized> Content for $ty
             {
+                const FIELDS: ContentFields = $fields;
+
                 #[inline]
                 fn is_truthy(&self) -> bool {
                     self.deref().is_truthy()
@@ -1034,6 +1355,16 @@ macro_rules! impl_pointer_types {
                     self.deref().apply_unescaped()
                 }
 
+                #[inline]
+                fn operand(&self) -> Operand<'_> {
+                    self.deref().operand()
+                }
+
+                #[inline]
+                fn field_operand(&self, hash: u64, name: &str, path: &[FieldPath]) -> Option<Operand<'_>> {
+                    self.deref().field_operand(hash, name, path)
+                }
+
                 #[inline]
                 fn render_section<C, E, IC>(
                     &self,
@@ -1187,10 +1518,13 @@ macro_rules! impl_pointer_types {
     }
 }
 
-impl_pointer_types!(&T, Box<T>, std::rc::Rc<T>, std::sync::Arc<T>, Cow<'_, T>: ToOwned, beef::Cow<'_, [T]>: Clone);
+impl_pointer_types!(T::FIELDS; &T, &mut T, Cow<'_, T>: ToOwned, beef::Cow<'_, [T]>: Clone);
+
+// 自引用的类型通过 Box 等智能指针包含自身，不展开其字段信息，避免 FIELDS 的求值出现循环
+impl_pointer_types!(ContentFields::Unknown; Box<T>, std::rc::Rc<T>, std::sync::Arc<T>);
 
 #[cfg(target_pointer_width = "64")]
-impl_pointer_types!(beef::lean::Cow<'_, [T]>: Clone);
+impl_pointer_types!(T::FIELDS; beef::lean::Cow<'_, [T]>: Clone);
 
 // Can't implement for generic beef::Cow as it uses an internal trait.
 impl Content for beef::Cow<'_, str> {
@@ -1213,6 +1547,11 @@ impl Content for beef::Cow<'_, str> {
     fn render_unescaped<E: Encoder>(&self, encoder: &mut E) -> Result<(), E::Error> {
         encoder.write_unescaped(self)
     }
+
+    #[inline]
+    fn operand(&self) -> Operand<'_> {
+        Operand::Str(self)
+    }
 }
 
 #[cfg(target_pointer_width = "64")]
@@ -1236,6 +1575,11 @@ impl Content for beef::lean::Cow<'_, str> {
     fn render_unescaped<E: Encoder>(&self, encoder: &mut E) -> Result<(), E::Error> {
         encoder.write_unescaped(self)
     }
+
+    #[inline]
+    fn operand(&self) -> Operand<'_> {
+        Operand::Str(self)
+    }
 }
 
 // #[cfg(feature = "chrono")]
@@ -1423,4 +1767,34 @@ mod tests {
 
         println!("{}", formatted);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn content_fields_contains_path() {
+        use super::{ContentField, ContentFields};
+
+        const INNER: ContentFields = ContentFields::Named {
+            fields: &[ContentField { name: "name", fields: ContentFields::Unknown }],
+            flatten: &[],
+        };
+        const FLAT: ContentFields = ContentFields::Named {
+            fields: &[ContentField { name: "age", fields: ContentFields::Unknown }],
+            flatten: &[],
+        };
+        const FIELDS: ContentFields = ContentFields::Named {
+            fields: &[
+                ContentField { name: "data", fields: INNER },
+                ContentField { name: "map", fields: ContentFields::Unknown },
+            ],
+            flatten: &[FLAT],
+        };
+
+        assert!(FIELDS.contains_path("data"));
+        assert!(FIELDS.contains_path("data.name"));
+        assert!(FIELDS.contains_path("age"));
+        assert!(FIELDS.contains_path("map.anything"));
+        assert!(!FIELDS.contains_path("dat"));
+        assert!(!FIELDS.contains_path("data.nmae"));
+        assert!(FIELDS.contains_path("data.name.x"));
+        assert!(ContentFields::Unknown.contains_path("whatever"));
+    }
+}
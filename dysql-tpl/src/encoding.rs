@@ -18,6 +18,7 @@ use pulldown_cmark::{html, Event, Parser};
 
 use crate::SimpleError;
 use crate::SimpleInnerError;
+use crate::sql_syntax::{SqlScanner, SqlState, SqlSyntax, param_end};
 
 /// A trait that wraps around either a `String` or `std::io::Write`, providing UTF-8 safe
 /// writing boundaries and special HTML character escaping.
@@ -40,6 +41,23 @@ pub trait Encoder {
 
     /// Write a `Display` implementor to this `Encoder`, escaping special HTML characters.
     fn format_escaped<D: fmt::Display>(&mut self, display: D) -> Result<(), Self::Error>;
+
+    /// 开始处理一个 section 块 (包括 `{{^..}}`、`{{?..}}`、`{{#if}}` 及 `{{#where}}` 等元素)，
+    /// 与 `enter_section`、`end_section` 一起记录渲染时所走的分支，默认不做任何事
+    #[inline]
+    fn begin_section(&mut self) {}
+
+    /// 当前 section 块的内容被渲染了一次
+    #[inline]
+    fn enter_section(&mut self) {}
+
+    /// 当前 section 块处理完毕
+    #[inline]
+    fn end_section(&mut self) {}
+
+    /// 即将输出 `{{name}}` 变量的值
+    #[inline]
+    fn write_value(&mut self) {}
 }
 
 /// Local helper for escaping stuff into strings.
@@ -228,90 +246,289 @@ pub fn encode_cmark<E: Encoder>(source: &str, encoder: &mut E) -> Result<(), E::
     encoder.write_html(parser)
 }
 
-enum Token<'a> {
-    // (含控制符的 len, token str)
-    DEL(usize, &'a str), 
-    Normal(usize, &'a str),
+const BLANKET_CHARS: [u8; 3] = [b' ', b'\n', b'\t'];
+
+/// 渲染 sql 时处理 `:name` 命名参数：写入数据库方言的占位符并获取参数值，
+/// 使 sql 的渲染和参数值的收集只需遍历一次 `Content`
+pub trait SqlParams {
+    /// sql 的词法规则
+    fn syntax(&self) -> SqlSyntax;
+
+    /// 在已渲染的 `sql` 之后写入参数 `name` 的占位符，并获取其参数值
+    fn write_param(&mut self, sql: &mut String, name: &str) -> Result<(), SimpleError>;
 }
 
-const BLANKET_CHARS: [u8; 3] = [b' ', b'\n', b'\t'];
+/// sql 模板渲染时所走的分支，按出现顺序每个 section 块占一位，内容被渲染了的为 1。
+/// 模板中没有输出变量且每个 section 最多渲染一次时，相同的 `SqlShape` 一定渲染出相同的 sql
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SqlShape {
+    bits: Vec<u64>,
+    len: usize,
+    /// 尚未处理完的 section 块所占的位
+    open: Vec<usize>,
+    cacheable: bool,
+}
+
+impl Default for SqlShape {
+    fn default() -> Self {
+        Self { bits: Vec::new(), len: 0, open: Vec::new(), cacheable: true }
+    }
+}
+
+impl SqlShape {
+    /// 清空记录的分支，可以在多次计算间复用
+    pub fn clear(&mut self) {
+        self.bits.clear();
+        self.len = 0;
+        self.open.clear();
+        self.cacheable = true;
+    }
+
+    /// 渲染结果是否只由所走的分支决定，输出了变量或多次渲染同一个 section (如遍历列表) 时为 false
+    pub fn is_cacheable(&self) -> bool {
+        self.cacheable
+    }
+
+    #[inline]
+    fn fail(&mut self) -> Result<(), NeverError> {
+        self.cacheable = false;
+
+        Ok(())
+    }
+}
 
-pub(crate) struct SqlEncoder
+impl Encoder for SqlShape {
+    type Error = NeverError;
+
+    #[inline]
+    fn write_unescaped(&mut self, _part: &str) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    #[inline]
+    fn write_escaped(&mut self, _part: &str) -> Result<(), Self::Error> {
+        self.fail()
+    }
+
+    #[cfg(feature = "pulldown-cmark")]
+    #[inline]
+    fn write_html<'b, I: Iterator<Item = Event<'b>>>(&mut self, _iter: I) -> Result<(), Self::Error> {
+        self.fail()
+    }
+
+    #[inline]
+    fn format_unescaped<D: fmt::Display>(&mut self, _display: D) -> Result<(), Self::Error> {
+        self.fail()
+    }
+
+    #[inline]
+    fn format_escaped<D: fmt::Display>(&mut self, _display: D) -> Result<(), Self::Error> {
+        self.fail()
+    }
+
+    #[inline]
+    fn begin_section(&mut self) {
+        if self.len == self.bits.len() * 64 {
+            self.bits.push(0);
+        }
+        self.open.push(self.len);
+        self.len += 1;
+    }
+
+    #[inline]
+    fn enter_section(&mut self) {
+        if let Some(&pos) = self.open.last() {
+            let (word, bit) = (pos / 64, 1 << (pos % 64));
+            if self.bits[word] & bit != 0 {
+                self.cacheable = false;
+            }
+            self.bits[word] |= bit;
+        }
+    }
+
+    #[inline]
+    fn end_section(&mut self) {
+        self.open.pop();
+    }
+
+    #[inline]
+    fn write_value(&mut self) {
+        self.cacheable = false;
+    }
+}
+
+pub(crate) struct SqlEncoder<'p>
 {
     pub inner: String,
     pub trim_token: Option<String>,
+    scanner: SqlScanner,
+    params: Option<&'p mut dyn SqlParams>,
+    shape: Option<&'p mut SqlShape>,
 }
 
-impl SqlEncoder {
+impl<'p> SqlEncoder<'p> {
     pub fn with_capacity(capacity: usize) -> Self {
         Self {
             inner: String::with_capacity(capacity),
             trim_token: None,
+            scanner: SqlScanner::new(SqlSyntax::Standard),
+            params: None,
+            shape: None,
+        }
+    }
+
+    /// 复用 `buf` 作为输出，`:name` 命名参数交给 `params` 写入占位符
+    pub fn with_params(mut buf: String, params: &'p mut dyn SqlParams) -> Self {
+        buf.clear();
+        Self {
+            inner: buf,
+            trim_token: None,
+            scanner: SqlScanner::new(params.syntax()),
+            params: Some(params),
+            shape: None,
+        }
+    }
+
+    /// 渲染的同时在 `shape` 中记录所走的分支
+    pub fn with_shape(mut self, shape: &'p mut SqlShape) -> Self {
+        shape.clear();
+        self.shape = Some(shape);
+        self
+    }
+
+    #[inline]
+    fn shape_fail(&mut self) {
+        if let Some(shape) = self.shape.as_deref_mut() {
+            let _ = shape.fail();
         }
     }
 
     pub fn trim(mut self) -> String {
-        if self.inner.len() > 0 {
+        if self.inner.ends_with(' ') {
             self.inner.pop();
         }
+        // 以单行注释结尾时补上换行，避免拼接在其后的 sql 被注释掉
+        if self.scanner.state() == SqlState::LineComment {
+            self.inner.push('\n');
+        }
         self.inner
     }
 
+    /// 连续的空白字符合并为一个空格，字符串、引号中的标识符及注释中的内容原样输出
     fn trim_sql(&mut self, sql: &str) -> Result<(), SimpleError>
     {
-        let sql_buf = &mut self.inner;
+        let bytes = sql.as_bytes();
+        let sql_len = bytes.len();
         let mut end: usize = 0; // 当前位置
-        let sql_len: usize = sql.len();
-        // let mut trim_token: Option<&str> = None;
-    
-        while end < sql_len { 
-            if let Some(idx) = skip_blank(&sql[end..sql_len]) {
-                end += idx;
-            } else {
-                break;
-            }
-            
-            let sql_token = get_token(&sql[end..sql_len])?;
-            let token_len = match sql_token {
+
+        while end < sql_len {
+            // 上一段 sql 结束在字符串或注释中时，直接接着输出
+            if self.scanner.is_code() {
+                end += bytes[end..].iter().take_while(|c| BLANKET_CHARS.contains(c)).count();
+                if end == sql_len {
+                    break;
+                }
+
                 // 如果是 DEL 控制符，则记录需要 trim 的 token，在下一次写入 sql_buf 时过滤字符串
-                Token::DEL(len, token) => {
+                if sql[end..].starts_with("![DEL(") {
+                    let (len, token) = get_del_token(&sql[end..])?;
                     self.trim_token = Some(token.to_owned());
-                    len
+                    end += len;
+                    continue;
                 }
+
                 // 需要输出的 sql token 如果开始位置有需要 DEL 的 token，则跳过此 token 写入 sql_buf,
                 // 写入后重置 trim_token 为 None.
-                Token::Normal(len, token) => {
-                    if let Some(tm_token) = &self.trim_token {
-                        let trim_len = tm_token.len();
-                        if trim_len <= token.len() && tm_token == &token[0..trim_len] {
-                            if trim_len < token.len() {
-                                sql_buf.push_str(&token[trim_len..]);
-                                sql_buf.push_str(" ");
-                            } else {
-                                sql_buf.push_str(&token[trim_len..]);
-                            }
-                        } else {
-                            sql_buf.push_str(token);
-                            sql_buf.push_str(" ");
+                if let Some(tm_token) = self.trim_token.take() {
+                    if sql[end..].starts_with(tm_token.as_str()) {
+                        end += tm_token.len();
+                        if bytes.get(end).is_none_or(|c| BLANKET_CHARS.contains(c)) {
+                            continue;
                         }
-                    } else {
-                        sql_buf.push_str(token);
-                        sql_buf.push_str(" ");
                     }
-                    self.trim_token = None;
-    
-                    len
                 }
-            };
-    
-            end += token_len;
+            }
+
+            end = self.write_token(sql, end)?;
         }
 
         Ok(())
     }
+
+    /// 写入从 `start` 开始到空白字符为止的 token，token 中的字符串、引号中的标识符及注释可以包含空白字符，
+    /// 返回 token 的结束位置
+    fn write_token(&mut self, sql: &str, start: usize) -> Result<usize, SimpleError> {
+        let bytes = sql.as_bytes();
+        let mut text_start = start; // 尚未写入的内容的开始位置
+        let mut end = start;
+        while end < bytes.len() {
+            if !self.scanner.is_code() {
+                let is_line_comment = self.scanner.state() == SqlState::LineComment;
+                end = self.scanner.close_literal(bytes, end).min(bytes.len());
+                // 单行注释以换行结束，换行即为 token 的分隔符
+                if is_line_comment && self.scanner.is_code() {
+                    self.inner.push_str(&sql[text_start..end]);
+                    return Ok(end);
+                }
+                continue;
+            }
+
+            match bytes[end] {
+                c if BLANKET_CHARS.contains(&c) => break,
+                b':' | b'\\' if self.params.is_some() => {
+                    self.inner.push_str(&sql[text_start..end]);
+                    end = self.write_param(sql, end)?;
+                    text_start = end;
+                }
+                _ => end += self.scanner.open_literal(bytes, end),
+            }
+        }
+
+        self.inner.push_str(&sql[text_start..end]);
+        if self.scanner.is_code() {
+            self.inner.push(' ');
+        }
+
+        Ok(end)
+    }
+
+    /// 处理 `pos` 处的 `:` 或 `\`：`::` 类型转换原样写入，`\:` 写入 `:`，
+    /// `:name` 交给 `SqlParams` 写入占位符，返回处理之后的位置
+    fn write_param(&mut self, sql: &str, pos: usize) -> Result<usize, SimpleError> {
+        let bytes = sql.as_bytes();
+        if bytes[pos] == b'\\' {
+            let len = if bytes.get(pos + 1) == Some(&b':') { 2 } else { 1 };
+            self.inner.push_str(&sql[pos + len - 1..pos + len]);
+            return Ok(pos + len);
+        }
+
+        let colons = bytes[pos..].iter().take_while(|c| **c == b':').count();
+        if colons > 1 {
+            self.inner.push_str(&sql[pos..pos + colons]);
+            return Ok(pos + colons);
+        }
+
+        let end = param_end(bytes, pos + 1);
+        if end == pos + 1 {
+            Err(SimpleInnerError("not found named parameter after ':', use '\\:' for a literal ':'".to_owned()))?
+        }
+        if let Some(params) = self.params.as_mut() {
+            params.write_param(&mut self.inner, &sql[pos + 1..end])?;
+        }
+
+        Ok(end)
+    }
+
+    /// 写入变量值之后的分隔空格，在字符串或注释中时不写入
+    #[inline]
+    fn write_separator(&mut self) {
+        if self.scanner.is_code() {
+            self.inner.push(' ');
+        }
+    }
 }
 
-impl  Encoder for SqlEncoder  {
+impl Encoder for SqlEncoder<'_> {
     // Change this to `!` once stabilized.
     type Error = SimpleError;
 
@@ -327,8 +544,9 @@ impl  Encoder for SqlEncoder  {
 
     #[inline]
     fn write_escaped(&mut self, part: &str) -> Result<(), Self::Error> {
+        self.shape_fail();
         EscapingStringEncoder(&mut self.inner).write_escaped(part);
-        self.inner.push_str(" ");
+        self.write_separator();
         // println!("escaped | bf:{}/ af:{}/", part, self.inner);
 
         Ok(())
@@ -337,6 +555,7 @@ impl  Encoder for SqlEncoder  {
     #[cfg(feature = "pulldown-cmark")]
     #[inline]
     fn write_html<'b, I: Iterator<Item = Event<'b>>>(&mut self, iter: I) -> Result<(), Self::Error> {
+        self.shape_fail();
         html::push_html(&mut self.inner, iter);
         // println!("aaaaaaaaa");
 
@@ -347,8 +566,10 @@ impl  Encoder for SqlEncoder  {
     fn format_unescaped<D: fmt::Display>(&mut self, display: D) -> Result<(), Self::Error> {
         use std::fmt::Write;
 
+        self.shape_fail();
         // Never fails for a string
-        let _ = write!(&mut self.inner, "{} ", display);
+        let _ = write!(&mut self.inner, "{}", display);
+        self.write_separator();
         // println!("bbbbbbb");
 
         Ok(())
@@ -358,85 +579,68 @@ impl  Encoder for SqlEncoder  {
     fn format_escaped<D: fmt::Display>(&mut self, display: D) -> Result<(), Self::Error> {
         use std::fmt::Write;
 
+        self.shape_fail();
         // Never fails for a string
-        let _ = write!(EscapingStringEncoder(&mut self.inner), "{} ", display);
+        let _ = write!(EscapingStringEncoder(&mut self.inner), "{}", display);
+        self.write_separator();
         // println!("cccccc");
 
         Ok(())
     }
-}
-
 
+    #[inline]
+    fn begin_section(&mut self) {
+        if let Some(shape) = self.shape.as_deref_mut() {
+            shape.begin_section();
+        }
+    }
 
-/// 跳过空白字符,
-/// 遇到非空白字符时返回 Some(跳过的字符数),
-/// 遇到结尾时返回 None
-fn skip_blank(s: &str) -> Option<usize> {
-    let mut current_idx = 0;
-    let slen = s.len();
-
-    while current_idx < slen {
-        let c = char_at(s, current_idx);
-        let is_not_blank = BLANKET_CHARS.iter().all(|b| *b != c);
-        if is_not_blank { break }
+    #[inline]
+    fn enter_section(&mut self) {
+        if let Some(shape) = self.shape.as_deref_mut() {
+            shape.enter_section();
+        }
+    }
 
-        current_idx += 1;
+    #[inline]
+    fn end_section(&mut self) {
+        if let Some(shape) = self.shape.as_deref_mut() {
+            shape.end_section();
+        }
     }
 
-    if current_idx < slen {
-        Some(current_idx)
-    } else {
-        None
+    #[inline]
+    fn write_value(&mut self) {
+        if let Some(shape) = self.shape.as_deref_mut() {
+            shape.write_value();
+        }
     }
 }
 
-#[inline]
-fn char_at(s: &str, idx: usize) -> u8 {
-    *&s[idx..idx + 1].as_bytes()[0]
-}
+/// 解析 `![DEL(xxx)]` 控制符，返回 (含控制符的 len, 需要 DEL 的 token)
+fn get_del_token(s: &str) -> Result<(usize, &str), SimpleError> {
+    let bytes = s.as_bytes();
+    let slen = bytes.len();
+    let mut current_idx = 6;
+    let mut has_end = false;
+    while current_idx < slen {
+        let c = bytes[current_idx];
+        let is_blank = BLANKET_CHARS.contains(&c);
+        current_idx += 1;
 
-/// stop at blank or end
-fn get_token(s: &str) -> Result<Token, SimpleError> {
-    let mut current_idx = 0;
-    let slen = s.len();
-
-    // ![DEL(xxx)] 处理
-    if s.len() >= 6 && "![DEL(" == &s[0..6] {
-        current_idx += 6;
-        let mut has_end = false;
-        while current_idx < slen {
-            let c = char_at(s, current_idx);
-            let is_blank = BLANKET_CHARS.iter().any(|&b| b == c);
+        if is_blank { 
+            break 
+        } else if c == b')' && bytes.get(current_idx) == Some(&b']') {
+            has_end = true;
             current_idx += 1;
-
-            if is_blank { 
-                break 
-            } else if c == b')' {
-                let c = char_at(&s[current_idx..], 0);
-                if c == b']' {
-                    has_end = true;
-                    current_idx += 1;
-                    break
-                }
-            }
+            break
         }
+    }
 
-        if has_end {
-            let token = &s[6..current_idx - 2];
-            return Ok(Token::DEL(current_idx, token))
-        } else {
-            Err(SimpleInnerError(" '![DEL(..)' syntax error".to_owned()))?
-        }
+    if has_end {
+        let token = &s[6..current_idx - 2];
+        Ok((current_idx, token))
     } else {
-        while current_idx < slen {
-            let c = char_at(s, current_idx);
-            let is_blank = BLANKET_CHARS.iter().any(|&b| b == c);
-            if is_blank { 
-                break 
-            }
-            current_idx += 1;
-        }
-        let token = &s[0..current_idx];
-        return Ok(Token::Normal(current_idx, token))
+        Err(SimpleInnerError(" '![DEL(..)' syntax error".to_owned()))?
     }
-}
\ No newline at end of file
+}
@@ -37,6 +37,12 @@ pub enum TemplateError {
 
     /// The template file with the given name was not found
     NotFound(String),
+
+    /// Unknown attribute in `{{#where}}`, `{{#set}}` or `{{#trim ...}}` element
+    IllegalTrimAttribute(String),
+
+    /// Syntax error in the condition of `{{#if expr}}` section
+    IllegalExpression(String),
 }
 
 impl error::Error for TemplateError {}
@@ -77,6 +83,12 @@ impl fmt::Display for TemplateError {
                 name
             ),
             TemplateError::NotFound(name) => write!(f, "Template file {} not found", name),
+            TemplateError::IllegalTrimAttribute(attr) => write!(
+                f,
+                "Illegal attribute `{}` of trim element, expecting prefix=.., suffix=.., prefix_overrides=.. or suffix_overrides=..",
+                attr
+            ),
+            TemplateError::IllegalExpression(expr) => write!(f, "Illegal expression `{}` in {{{{#if ..}}}} section", expr),
         }
     }
 }
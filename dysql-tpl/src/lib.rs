@@ -77,11 +77,14 @@ mod simple;
 pub mod traits;
 
 pub mod encoding;
+pub mod sql_syntax;
 
-pub use content::Content;
+pub use content::{Content, ContentField, ContentFields};
+pub use encoding::{SqlParams, SqlShape};
+pub use sql_syntax::{SqlScanner, SqlSegment, SqlSegmentKind, SqlSegments, SqlState, SqlSyntax};
 pub use error::TemplateError;
 use fnv::FnvHasher;
-pub use template::{Section, Template};
+pub use template::{FieldPath, Operand, Section, Template};
 pub use simple::{SimpleTemplate, SimpleSection, SimpleValue, SimpleError, SimpleInnerError};
 
 #[cfg(feature = "export_derive")]
@@ -53,6 +53,8 @@ macro_rules! impl_simple_value_varaint {
                 t_DateTime_Local(DateTime<Local>),
                 t_DateTime_FixedOffset(DateTime<FixedOffset>),
                 None(Option<i32>),
+                /// Vec 等序列类型的元素值
+                t_list(Vec<SimpleValue>),
             }
         }
     }
@@ -1,7 +1,7 @@
 use bytes::BytesMut;
 use postgres_types::{ToSql, to_sql_checked};
 
-use super::{RawStr, RawString};
+use super::{RawStr, RawString, SimpleValue};
 
 impl ToSql for RawStr {
     fn to_sql(&self, ty: &postgres_types::Type, w: &mut BytesMut) 
@@ -43,4 +43,44 @@ impl ToSql for RawString {
     }
 
     to_sql_checked!();
-}
\ No newline at end of file
+}
+
+macro_rules! impl_simple_value_to_sql {
+    (
+        $($vtype: ident),*
+    ) => {
+        paste::paste! {
+            /// 列表参数绑定为 postgres 数组，元素按数据库推断出的数组元素类型编码
+            impl ToSql for SimpleValue {
+                fn to_sql(&self, ty: &postgres_types::Type, w: &mut BytesMut) 
+                    -> Result<postgres_types::IsNull, Box<dyn std::error::Error + Sync + Send>>
+                where
+                    Self: Sized 
+                {
+                    match self {
+                        $(
+                            SimpleValue::[<t_ $vtype>](val) => val.to_sql_checked(ty, w),
+                        )*
+                        SimpleValue::t_str(val) => val.to_sql_checked(ty, w),
+                        SimpleValue::t_String(val) => val.to_sql_checked(ty, w),
+                        SimpleValue::None(_) => Ok(postgres_types::IsNull::Yes),
+                        SimpleValue::t_list(val) => val.to_sql_checked(ty, w),
+                        _ => Err(format!("the type of {:?} is not support", self).into()),
+                    }
+                }
+
+                fn accepts(_ty: &postgres_types::Type) -> bool
+                where
+                    Self: Sized 
+                {
+                    // 具体类型在 to_sql 中由各变体校验
+                    true
+                }
+
+                to_sql_checked!();
+            }
+        }
+    };
+}
+
+impl_simple_value_to_sql!(i64, i32, i16, i8, u32, f32, f64, bool, Uuid, NaiveDateTime, Utc, DateTime_Local, DateTime_FixedOffset);
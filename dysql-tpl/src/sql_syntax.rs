@@ -0,0 +1,328 @@
+//! Lexical rules of sql shared by the template renderer and the sql parsers in dysql-core.
+
+/// 与数据库方言相关的 sql 词法规则
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SqlSyntax {
+    /// 未指定数据库方言时使用，字符串中的 `\` 为转义符
+    #[default]
+    Standard,
+    /// 字符串中的 `\` 为转义符，`#` 开始单行注释
+    Mysql,
+    /// 支持 `$tag$ .. $tag$` 字符串及嵌套的多行注释，只有 `E'..'` 中的 `\` 为转义符
+    Postgres,
+    /// 字符串中的 `\` 不是转义符
+    Sqlite,
+}
+
+/// sql 中某个位置所处的词法环境
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlState {
+    Code,
+    /// `'..'` 字符串，`".."` 及 `` `..` `` 引号中的标识符，`backslash` 表示 `\` 是否为转义符
+    Quoted { quote: u8, backslash: bool },
+    /// `-- ..` 单行注释 (mysql 中还有 `# ..`)
+    LineComment,
+    /// `/* .. */` 多行注释，参数为嵌套的层数
+    BlockComment(u32),
+    /// postgres 的 `$tag$ .. $tag$` 字符串
+    DollarQuoted,
+}
+
+/// 按 sql 的词法规则跟踪字符串、引号中的标识符及注释的开始和结束，
+/// 状态在多次调用间保留，sql 可以分段传入 (如模板渲染时逐段写入)
+#[derive(Debug, Clone)]
+pub struct SqlScanner {
+    syntax: SqlSyntax,
+    state: SqlState,
+    dollar_tag: String,
+}
+
+impl SqlScanner {
+    pub fn new(syntax: SqlSyntax) -> Self {
+        Self {
+            syntax,
+            state: SqlState::Code,
+            dollar_tag: String::new(),
+        }
+    }
+
+    pub fn syntax(&self) -> SqlSyntax {
+        self.syntax
+    }
+
+    pub fn state(&self) -> SqlState {
+        self.state
+    }
+
+    #[inline]
+    pub fn is_code(&self) -> bool {
+        self.state == SqlState::Code
+    }
+
+    /// 判断 `pos` 处是否为字符串、标识符或注释的开始，是的话进入对应的状态，返回开始标记的长度，
+    /// 只在 `Code` 状态下调用
+    pub fn open_literal(&mut self, bytes: &[u8], pos: usize) -> usize {
+        let prev = pos.checked_sub(1).map(|i| bytes[i]);
+        let (state, len) = match (bytes[pos], bytes.get(pos + 1)) {
+            (b'\'', _) => {
+                let is_escape_str = matches!(prev, Some(b'E' | b'e'))
+                    && !pos.checked_sub(2).is_some_and(|i| is_ident_char(bytes[i]));
+                let backslash = match self.syntax {
+                    SqlSyntax::Standard | SqlSyntax::Mysql => true,
+                    SqlSyntax::Postgres => is_escape_str,
+                    SqlSyntax::Sqlite => false,
+                };
+                (SqlState::Quoted { quote: b'\'', backslash }, 1)
+            }
+            (quote @ (b'"' | b'`'), _) => (SqlState::Quoted { quote, backslash: false }, 1),
+            (b'-', Some(b'-')) => (SqlState::LineComment, 2),
+            (b'#', _) if self.syntax == SqlSyntax::Mysql => (SqlState::LineComment, 1),
+            (b'/', Some(b'*')) => (SqlState::BlockComment(1), 2),
+            (b'$', _) if self.syntax == SqlSyntax::Postgres && !prev.is_some_and(is_ident_char) => {
+                match dollar_tag(bytes, pos) {
+                    Some(tag) => {
+                        self.dollar_tag.clear();
+                        self.dollar_tag.push_str(tag);
+                        (SqlState::DollarQuoted, tag.len())
+                    }
+                    None => (SqlState::Code, 1),
+                }
+            }
+            _ => (SqlState::Code, 1),
+        };
+        self.state = state;
+
+        len
+    }
+
+    /// 从 `pos` 开始查找字符串、标识符或注释的结束位置，找到时切换为 `Code` 状态，
+    /// 返回结束标记之后的位置，到达结尾仍未结束时返回 `bytes.len()`
+    pub fn close_literal(&mut self, bytes: &[u8], pos: usize) -> usize {
+        let len = bytes.len();
+        let mut pos = pos;
+        while pos < len {
+            let next = bytes.get(pos + 1).copied();
+            match (self.state, bytes[pos]) {
+                // 字符串中 `\` 之后的字符及两个连续的引号都不会结束字符串
+                (SqlState::Quoted { backslash: true, .. }, b'\\') => pos += 2,
+                (SqlState::Quoted { quote, .. }, c) if c == quote => {
+                    if next == Some(quote) {
+                        pos += 2;
+                    } else {
+                        self.state = SqlState::Code;
+                        return pos + 1;
+                    }
+                }
+                (SqlState::LineComment, b'\n') => {
+                    self.state = SqlState::Code;
+                    return pos + 1;
+                }
+                (SqlState::BlockComment(depth), b'*') if next == Some(b'/') => {
+                    pos += 2;
+                    if depth == 1 {
+                        self.state = SqlState::Code;
+                        return pos;
+                    }
+                    self.state = SqlState::BlockComment(depth - 1);
+                }
+                (SqlState::BlockComment(depth), b'/') if next == Some(b'*') && self.syntax == SqlSyntax::Postgres => {
+                    self.state = SqlState::BlockComment(depth + 1);
+                    pos += 2;
+                }
+                (SqlState::DollarQuoted, b'$') if bytes[pos..].starts_with(self.dollar_tag.as_bytes()) => {
+                    self.state = SqlState::Code;
+                    return pos + self.dollar_tag.len();
+                }
+                _ => pos += 1,
+            }
+        }
+
+        len
+    }
+}
+
+/// `SqlSegments` 切分出的一段 sql 的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqlSegmentKind {
+    /// 字符串、引号中的标识符及注释之外的 sql
+    Code,
+    /// `'..'` 字符串及 postgres 的 `$tag$ .. $tag$` 字符串
+    Str,
+    /// `".."` 及 `` `..` `` 引号中的标识符
+    QuotedIdent,
+    /// 单行注释，不含结尾的换行
+    LineComment,
+    BlockComment,
+}
+
+/// 切分出的一段 sql，`offset` 为其在 sql 中的字节偏移量
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SqlSegment<'a> {
+    pub kind: SqlSegmentKind,
+    pub offset: usize,
+    pub text: &'a str,
+}
+
+/// 按词法规则将 sql 切分为代码、字符串、引号中的标识符及注释，
+/// 未结束的字符串、标识符或注释一直延续到 sql 的结尾
+pub struct SqlSegments<'a> {
+    sql: &'a str,
+    scanner: SqlScanner,
+    pos: usize,
+    /// 已进入字符串或注释时，开始标记的长度
+    open_len: usize,
+}
+
+impl<'a> SqlSegments<'a> {
+    pub fn new(sql: &'a str, syntax: SqlSyntax) -> Self {
+        Self { sql, scanner: SqlScanner::new(syntax), pos: 0, open_len: 0 }
+    }
+}
+
+impl<'a> Iterator for SqlSegments<'a> {
+    type Item = SqlSegment<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bytes = self.sql.as_bytes();
+        let start = self.pos;
+        if start >= bytes.len() {
+            return None;
+        }
+
+        if self.scanner.is_code() {
+            let mut end = start;
+            while end < bytes.len() {
+                let len = self.scanner.open_literal(bytes, end);
+                if !self.scanner.is_code() {
+                    self.open_len = len;
+                    break;
+                }
+                end += len;
+            }
+            let end = end.min(bytes.len());
+            if end > start {
+                self.pos = end;
+                return Some(SqlSegment { kind: SqlSegmentKind::Code, offset: start, text: &self.sql[start..end] });
+            }
+        }
+
+        let kind = match self.scanner.state() {
+            SqlState::Quoted { quote: b'\'', .. } | SqlState::DollarQuoted => SqlSegmentKind::Str,
+            SqlState::Quoted { .. } => SqlSegmentKind::QuotedIdent,
+            SqlState::LineComment => SqlSegmentKind::LineComment,
+            SqlState::BlockComment(_) | SqlState::Code => SqlSegmentKind::BlockComment,
+        };
+        let mut end = self.scanner.close_literal(bytes, start + self.open_len).min(bytes.len());
+        if kind == SqlSegmentKind::LineComment && bytes[end - 1] == b'\n' {
+            end -= 1;
+        }
+        if !self.scanner.is_code() {
+            // 到达结尾仍未结束，不再继续切分
+            self.scanner = SqlScanner::new(self.scanner.syntax);
+        }
+        self.pos = end;
+
+        Some(SqlSegment { kind, offset: start, text: &self.sql[start..end] })
+    }
+}
+
+#[inline]
+pub fn is_ident_char(c: u8) -> bool {
+    c.is_ascii_alphanumeric() || c == b'_' || c >= 0x80
+}
+
+/// 获取从 `start` 开始的参数名的结束位置，参数名由字母、数字、`_` 及 `.` 分隔的字段路径组成，如 `data.name`、`ids.0`
+pub fn param_end(bytes: &[u8], start: usize) -> usize {
+    let mut end = start;
+    while end < bytes.len() {
+        let is_path_sep = bytes[end] == b'.' && end > start && bytes.get(end + 1).is_some_and(|c| is_ident_char(*c));
+        if is_ident_char(bytes[end]) || is_path_sep {
+            end += 1;
+        } else {
+            break;
+        }
+    }
+
+    end
+}
+
+/// 获取 `$tag$` 形式的开始标记，tag 可以为空，`$1` 这样的占位符返回 None
+fn dollar_tag(bytes: &[u8], start: usize) -> Option<&str> {
+    let mut end = start + 1;
+    while end < bytes.len() && bytes[end] != b'$' {
+        let c = bytes[end];
+        if !is_ident_char(c) || (end == start + 1 && c.is_ascii_digit()) {
+            return None;
+        }
+        end += 1;
+    }
+
+    if end < bytes.len() {
+        std::str::from_utf8(&bytes[start..=end]).ok()
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 返回 `pos` 处开始的字符串或注释的结束位置及之后的状态
+    fn skip(sql: &str, pos: usize, syntax: SqlSyntax) -> (usize, SqlState) {
+        let mut scanner = SqlScanner::new(syntax);
+        let len = scanner.open_literal(sql.as_bytes(), pos);
+        if scanner.is_code() {
+            return (pos + len, SqlState::Code);
+        }
+        let end = scanner.close_literal(sql.as_bytes(), pos + len);
+        (end, scanner.state())
+    }
+
+    #[test]
+    fn test_sql_scanner() {
+        let sql = r"'C:\' -- x";
+        assert_eq!((5, SqlState::Code), skip(sql, 0, SqlSyntax::Sqlite));
+        assert_eq!((5, SqlState::Code), skip(sql, 0, SqlSyntax::Postgres));
+        assert_eq!(sql.len(), skip(sql, 0, SqlSyntax::Mysql).0);
+
+        let sql = r"E'it\'s' x";
+        assert_eq!((8, SqlState::Code), skip(sql, 1, SqlSyntax::Postgres));
+
+        let sql = "$tag$ a $$ b $tag$ x";
+        assert_eq!((18, SqlState::Code), skip(sql, 0, SqlSyntax::Postgres));
+        assert_eq!(1, skip("$1 x", 0, SqlSyntax::Postgres).0);
+
+        let sql = "/* a /* b */ c */ x";
+        assert_eq!((17, SqlState::Code), skip(sql, 0, SqlSyntax::Postgres));
+        assert_eq!((12, SqlState::Code), skip(sql, 0, SqlSyntax::Mysql));
+
+        assert_eq!((5, SqlState::LineComment), skip("-- ab", 0, SqlSyntax::Sqlite));
+        assert_eq!(vec!["data.name", "ids.0"], ["data.name.", "ids.0)"].iter().map(|p| &p[..param_end(p.as_bytes(), 0)]).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_sql_segments() {
+        let segments = |sql, syntax| SqlSegments::new(sql, syntax).map(|s| (s.kind, s.text)).collect::<Vec<_>>();
+
+        let sql = "select 'a\n--b', \"c\" -- d\nfrom /* e */ t";
+        assert_eq!(
+            vec![
+                (SqlSegmentKind::Code, "select "),
+                (SqlSegmentKind::Str, "'a\n--b'"),
+                (SqlSegmentKind::Code, ", "),
+                (SqlSegmentKind::QuotedIdent, "\"c\""),
+                (SqlSegmentKind::Code, " "),
+                (SqlSegmentKind::LineComment, "-- d"),
+                (SqlSegmentKind::Code, "\nfrom "),
+                (SqlSegmentKind::BlockComment, "/* e */"),
+                (SqlSegmentKind::Code, " t"),
+            ],
+            segments(sql, SqlSyntax::Postgres)
+        );
+
+        let sql = r"'C:\' -- x";
+        assert_eq!(3, segments(sql, SqlSyntax::Sqlite).len());
+        assert_eq!(vec![(SqlSegmentKind::Str, sql)], segments(sql, SqlSyntax::Mysql));
+    }
+}
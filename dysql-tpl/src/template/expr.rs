@@ -0,0 +1,375 @@
+use std::cmp::Ordering;
+
+use serde::{Serialize, Deserialize};
+
+use crate::traits::ContentSequence;
+use crate::{hash_name, TemplateError};
+
+/// 条件表达式中字段路径的一段，如 `data.age` 中的 `data` 和 `age`
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+pub struct FieldPath {
+    pub hash: u64,
+    pub name: String,
+}
+
+impl FieldPath {
+    fn new(name: &str) -> Self {
+        FieldPath { hash: hash_name(name), name: name.to_owned() }
+    }
+}
+
+/// 条件表达式中的操作数，借用 `Content` 中的值，不做内存分配
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operand<'a> {
+    /// Option::None 或者不存在的字段
+    Null,
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    Str(&'a str),
+    /// 列表，只记录元素个数
+    List(usize),
+    /// 不能比较的值，只能判断真假
+    Other(bool),
+}
+
+impl Operand<'_> {
+    /// None、false、0、空字符串及空列表为假
+    pub fn is_truthy(&self) -> bool {
+        match *self {
+            Operand::Null => false,
+            Operand::Bool(b) => b,
+            Operand::Int(n) => n != 0,
+            Operand::Float(f) => f != 0.0,
+            Operand::Str(s) => !s.is_empty(),
+            Operand::List(len) => len > 0,
+            Operand::Other(b) => b,
+        }
+    }
+
+    fn compare(&self, other: &Operand<'_>) -> Option<Ordering> {
+        match (*self, *other) {
+            (Operand::Null, Operand::Null) => Some(Ordering::Equal),
+            (Operand::Bool(a), Operand::Bool(b)) => Some(a.cmp(&b)),
+            (Operand::Int(a), Operand::Int(b)) => Some(a.cmp(&b)),
+            (Operand::Int(a), Operand::Float(b)) => (a as f64).partial_cmp(&b),
+            (Operand::Float(a), Operand::Int(b)) => a.partial_cmp(&(b as f64)),
+            (Operand::Float(a), Operand::Float(b)) => a.partial_cmp(&b),
+            (Operand::Str(a), Operand::Str(b)) => Some(a.cmp(b)),
+            (Operand::List(a), Operand::List(b)) => Some(a.cmp(&b)),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Serialize, Deserialize)]
+enum CmpOp {
+    Eq,
+    Ne,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl CmpOp {
+    fn test(self, ord: Option<Ordering>) -> bool {
+        match self {
+            CmpOp::Eq => ord == Some(Ordering::Equal),
+            // 类型不同的值不相等
+            CmpOp::Ne => ord != Some(Ordering::Equal),
+            CmpOp::Gt => ord == Some(Ordering::Greater),
+            CmpOp::Ge => matches!(ord, Some(Ordering::Greater | Ordering::Equal)),
+            CmpOp::Lt => ord == Some(Ordering::Less),
+            CmpOp::Le => matches!(ord, Some(Ordering::Less | Ordering::Equal)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize)]
+enum Term {
+    Null,
+    Bool(bool),
+    Int(i128),
+    Float(f64),
+    Str(String),
+    Field(Vec<FieldPath>),
+    /// `len(path)`，列表的元素个数或字符串的字符数
+    Len(Vec<FieldPath>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize)]
+enum Node {
+    Or(Box<Node>, Box<Node>),
+    And(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Cmp(CmpOp, Term, Term),
+    Term(Term),
+}
+
+/// `{{#if expr}}` 中的条件表达式，支持 `==`、`!=`、`>`、`>=`、`<`、`<=`、`&&`、`||`、`!`、括号及 `len(..)`
+#[derive(Debug, Clone, PartialEq)]
+#[derive(Serialize, Deserialize)]
+pub(crate) struct Expr {
+    source: String,
+    node: Node,
+}
+
+impl Expr {
+    pub(crate) fn parse(source: &str) -> Result<Self, TemplateError> {
+        let source = source.trim();
+        let illegal = || TemplateError::IllegalExpression(source.to_owned());
+
+        let mut parser = ExprParser { tokens: tokenize(source).ok_or_else(illegal)?, pos: 0 };
+        let node = parser.parse_or().ok_or_else(illegal)?;
+        if parser.pos != parser.tokens.len() {
+            return Err(illegal());
+        }
+
+        Ok(Expr { source: source.to_owned(), node })
+    }
+
+    /// 表达式的源码，编译期校验时作为 section 开关的名称
+    pub(crate) fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// 在当前 section 的 `Content` 栈上求值
+    pub(crate) fn eval<C: ContentSequence>(&self, contents: &C) -> bool {
+        eval_node(&self.node, contents)
+    }
+}
+
+fn eval_node<C: ContentSequence>(node: &Node, contents: &C) -> bool {
+    match node {
+        Node::Or(l, r) => eval_node(l, contents) || eval_node(r, contents),
+        Node::And(l, r) => eval_node(l, contents) && eval_node(r, contents),
+        Node::Not(n) => !eval_node(n, contents),
+        Node::Cmp(op, l, r) => op.test(eval_term(l, contents).compare(&eval_term(r, contents))),
+        Node::Term(t) => eval_term(t, contents).is_truthy(),
+    }
+}
+
+fn eval_term<'a, C: ContentSequence>(term: &'a Term, contents: &'a C) -> Operand<'a> {
+    match term {
+        Term::Null => Operand::Null,
+        Term::Bool(b) => Operand::Bool(*b),
+        Term::Int(n) => Operand::Int(*n),
+        Term::Float(f) => Operand::Float(*f),
+        Term::Str(s) => Operand::Str(s),
+        Term::Field(path) => lookup(path, contents),
+        Term::Len(path) => match lookup(path, contents) {
+            Operand::List(len) => Operand::Int(len as i128),
+            Operand::Str(s) => Operand::Int(s.chars().count() as i128),
+            Operand::Null => Operand::Int(0),
+            _ => Operand::Null,
+        },
+    }
+}
+
+fn lookup<'a, C: ContentSequence>(path: &[FieldPath], contents: &'a C) -> Operand<'a> {
+    let (field, rest) = path.split_first().expect("Unexpected error");
+    contents.field_operand(field.hash, &field.name, rest).unwrap_or(Operand::Null)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token<'a> {
+    Ident(&'a str),
+    Int(i128),
+    Float(f64),
+    Str(String),
+    Op(&'static str),
+}
+
+const OPERATORS: [&str; 12] = ["==", "!=", ">=", "<=", "&&", "||", ">", "<", "!", "(", ")", "."];
+
+/// 切分表达式，字符串用单引号或双引号括起来，`\` 转义引号
+fn tokenize(source: &str) -> Option<Vec<Token<'_>>> {
+    let bytes = source.as_bytes();
+    let mut tokens = Vec::new();
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        let c = bytes[idx];
+        if c.is_ascii_whitespace() {
+            idx += 1;
+        } else if c == b'\'' || c == b'"' {
+            let mut s = String::new();
+            let mut chars = source[idx + 1..].char_indices();
+            loop {
+                match chars.next()? {
+                    (_, '\\') => s.push(chars.next()?.1),
+                    (end, ch) if ch as u32 == c as u32 => {
+                        idx += end + 2;
+                        break;
+                    }
+                    (_, ch) => s.push(ch),
+                }
+            }
+            tokens.push(Token::Str(s));
+        } else if c.is_ascii_digit() || (c == b'-' && bytes.get(idx + 1).is_some_and(u8::is_ascii_digit)) {
+            let start = idx;
+            idx += 1;
+            // 小数点后不是数字时为路径分隔符，如 `ids.0.name`
+            while idx < bytes.len()
+                && (bytes[idx].is_ascii_digit() || (bytes[idx] == b'.' && bytes.get(idx + 1).is_some_and(u8::is_ascii_digit)))
+            {
+                idx += 1;
+            }
+            let num = &source[start..idx];
+            if num.contains('.') {
+                tokens.push(Token::Float(num.parse().ok()?));
+            } else {
+                tokens.push(Token::Int(num.parse().ok()?));
+            }
+        } else if c.is_ascii_alphabetic() || c == b'_' || c == b'$' {
+            let start = idx;
+            while idx < bytes.len() && (bytes[idx].is_ascii_alphanumeric() || bytes[idx] == b'_' || bytes[idx] == b'$') {
+                idx += 1;
+            }
+            tokens.push(Token::Ident(&source[start..idx]));
+        } else {
+            let op = OPERATORS.iter().find(|op| source[idx..].starts_with(*op))?;
+            tokens.push(Token::Op(op));
+            idx += op.len();
+        }
+    }
+
+    Some(tokens)
+}
+
+struct ExprParser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+}
+
+impl<'a> ExprParser<'a> {
+    fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos)
+    }
+
+    fn eat(&mut self, op: &str) -> bool {
+        let is_op = matches!(self.peek(), Some(Token::Op(o)) if *o == op);
+        if is_op {
+            self.pos += 1;
+        }
+        is_op
+    }
+
+    fn parse_or(&mut self) -> Option<Node> {
+        let mut node = self.parse_and()?;
+        while self.eat("||") {
+            node = Node::Or(Box::new(node), Box::new(self.parse_and()?));
+        }
+        Some(node)
+    }
+
+    fn parse_and(&mut self) -> Option<Node> {
+        let mut node = self.parse_unary()?;
+        while self.eat("&&") {
+            node = Node::And(Box::new(node), Box::new(self.parse_unary()?));
+        }
+        Some(node)
+    }
+
+    fn parse_unary(&mut self) -> Option<Node> {
+        if self.eat("!") {
+            return Some(Node::Not(Box::new(self.parse_unary()?)));
+        }
+        if self.eat("(") {
+            let node = self.parse_or()?;
+            return self.eat(")").then_some(node);
+        }
+
+        let left = self.parse_term()?;
+        let op = match self.peek() {
+            Some(Token::Op("==")) => CmpOp::Eq,
+            Some(Token::Op("!=")) => CmpOp::Ne,
+            Some(Token::Op(">")) => CmpOp::Gt,
+            Some(Token::Op(">=")) => CmpOp::Ge,
+            Some(Token::Op("<")) => CmpOp::Lt,
+            Some(Token::Op("<=")) => CmpOp::Le,
+            _ => return Some(Node::Term(left)),
+        };
+        self.pos += 1;
+
+        Some(Node::Cmp(op, left, self.parse_term()?))
+    }
+
+    fn parse_term(&mut self) -> Option<Term> {
+        let token = self.tokens.get(self.pos)?.clone();
+        self.pos += 1;
+        let term = match token {
+            Token::Int(n) => Term::Int(n),
+            Token::Float(f) => Term::Float(f),
+            Token::Str(s) => Term::Str(s),
+            Token::Ident("null") => Term::Null,
+            Token::Ident("true") => Term::Bool(true),
+            Token::Ident("false") => Term::Bool(false),
+            Token::Ident("len") if self.eat("(") => {
+                let path = self.parse_path()?;
+                if !self.eat(")") {
+                    return None;
+                }
+                Term::Len(path)
+            }
+            Token::Ident(_) => {
+                self.pos -= 1;
+                Term::Field(self.parse_path()?)
+            }
+            Token::Op(_) => return None,
+        };
+
+        Some(term)
+    }
+
+    fn parse_path(&mut self) -> Option<Vec<FieldPath>> {
+        let mut path = Vec::new();
+        loop {
+            match self.tokens.get(self.pos)? {
+                Token::Ident(name) => path.push(FieldPath::new(name)),
+                // `ids.0` 这样的下标
+                Token::Int(idx) if !path.is_empty() && *idx >= 0 => path.push(FieldPath::new(&idx.to_string())),
+                _ => return None,
+            }
+            self.pos += 1;
+            if !self.eat(".") {
+                return Some(path);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_expr() {
+        let expr = Expr::parse(" age > 0 && (status == 'active' || status == \"pending\") ").unwrap();
+        assert_eq!("age > 0 && (status == 'active' || status == \"pending\")", expr.source());
+
+        assert!(Expr::parse("!deleted && len(ids) > 0 && data.score >= -1.5").is_ok());
+        assert!(Expr::parse("name != null").is_ok());
+        assert!(Expr::parse("").is_err());
+        assert!(Expr::parse("age >").is_err());
+        assert!(Expr::parse("(age > 0").is_err());
+        assert!(Expr::parse("status == 'active").is_err());
+        assert!(Expr::parse("age = 1").is_err());
+    }
+
+    #[test]
+    fn test_operand_compare() {
+        assert!(CmpOp::Gt.test(Operand::Int(3).compare(&Operand::Float(2.5))));
+        assert!(CmpOp::Eq.test(Operand::Str("a").compare(&Operand::Str("a"))));
+        assert!(CmpOp::Ne.test(Operand::Str("1").compare(&Operand::Int(1))));
+        assert!(!CmpOp::Lt.test(Operand::Null.compare(&Operand::Int(1))));
+        assert!(!Operand::List(0).is_truthy());
+        assert!(Operand::Float(1e-20).is_truthy());
+        assert!(!Operand::Float(-0.0).is_truthy());
+    }
+}
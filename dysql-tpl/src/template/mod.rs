@@ -13,15 +13,21 @@ use std::path::Path;
 
 use serde::{Serialize, Deserialize};
 
-use crate::encoding::{EscapingIOEncoder, SqlEncoder};
+use crate::encoding::{EscapingIOEncoder, SqlEncoder, SqlParams, SqlShape};
+use crate::sql_syntax::param_end;
 use crate::{Partials, hash_name};
-use crate::{Content, TemplateError};
+use crate::{Content, TemplateError, SimpleTemplate, SimpleError};
 
+mod expr;
 mod parse;
 mod section;
+mod trim;
 
 pub use section::Section;
 pub use parse::Tag;
+pub use expr::{FieldPath, Operand};
+use expr::Expr;
+use trim::TrimSpec;
 
 #[derive(Debug)]
 #[derive(Serialize, Deserialize)]
@@ -36,6 +42,9 @@ pub struct Template {
 
     /// Source from which this template was parsed.
     source: String,
+
+    /// 模板中可能出现的 `:name` 命名参数及用于获取参数值的简化模版
+    params: Vec<(String, SimpleTemplate)>,
 }
 
 impl Template {
@@ -60,11 +69,13 @@ impl Template {
             blocks: Vec::with_capacity(16),
             capacity_hint: 0,
             source,
+            params: Vec::new(),
         };
         let last = tpl.parse(unsafe_source, partials)?;
         let tail = &unsafe_source[last..].trim_end();
         tpl.blocks.push(Block::nameless(tail, Tag::Tail));
         tpl.capacity_hint += tail.len();
+        tpl.params = Self::scan_params(&tpl.blocks);
 
         Ok(tpl)
     }
@@ -117,6 +128,150 @@ impl Template {
         buf.trim()
     }
 
+    /// 渲染 sql 的同时处理其中的 `:name` 命名参数，由 `params` 写入占位符并收集参数值，
+    /// 渲染结果写入 `buf`，`buf` 原有的内容会被清空，可以在多次渲染间复用
+    pub fn render_sql_params<C>(&self, content: &C, params: &mut dyn SqlParams, buf: &mut String) -> Result<(), SimpleError>
+    where
+        C: Content
+    {
+        let mut encoder = SqlEncoder::with_params(std::mem::take(buf), params);
+        let rst = Section::new(&self.blocks)
+            .with(content)
+            .render(&mut encoder, Option::<&()>::None);
+        *buf = encoder.trim();
+
+        rst
+    }
+
+    /// 同 `render_sql_params`，渲染的同时在 `shape` 中记录所走的分支 (结果与 `sql_shape` 相同)，
+    /// 渲染结果未被缓存时不必为了获取分支再遍历一次 `content`
+    pub fn render_sql_params_shape<C>(&self, content: &C, params: &mut dyn SqlParams, buf: &mut String, shape: &mut SqlShape) -> Result<(), SimpleError>
+    where
+        C: Content
+    {
+        let mut encoder = SqlEncoder::with_params(std::mem::take(buf), params).with_shape(shape);
+        let rst = Section::new(&self.blocks)
+            .with(content)
+            .render(&mut encoder, Option::<&()>::None);
+        *buf = encoder.trim();
+
+        rst
+    }
+
+    /// 不输出 sql，只记录用 `content` 渲染时所走的分支，用于缓存渲染出的 sql
+    pub fn sql_shape<C>(&self, content: &C, shape: &mut SqlShape)
+    where
+        C: Content
+    {
+        shape.clear();
+        let _ = Section::new(&self.blocks)
+            .with(content)
+            .render(shape, Option::<&()>::None);
+    }
+
+    /// 获取命名参数 `name` 对应的简化模版，模板中没有该参数时返回 None
+    pub fn param_template(&self, name: &str) -> Option<&SimpleTemplate> {
+        self.params.iter().find(|(param, _)| param == name).map(|(_, tpl)| tpl)
+    }
+
+    /// 找出各个 block 中所有可能的 `:name` 命名参数，不区分是否在字符串或注释中
+    fn scan_params(blocks: &[Block]) -> Vec<(String, SimpleTemplate)> {
+        let mut params: Vec<(String, SimpleTemplate)> = Vec::new();
+        for block in blocks {
+            let bytes = block.html.as_bytes();
+            for (i, _) in block.html.match_indices(':') {
+                if i > 0 && bytes[i - 1] == b':' {
+                    continue;
+                }
+                let name = &block.html[i + 1..param_end(bytes, i + 1)];
+                if !name.is_empty() && params.iter().all(|(param, _)| param != name) {
+                    params.push((name.to_owned(), SimpleTemplate::new(name)));
+                }
+            }
+        }
+
+        params
+    }
+
+    /// 模板中不包含任何标签，渲染结果与 `Content` 无关
+    pub fn is_static(&self) -> bool {
+        self.blocks.len() == 1
+    }
+
+    /// 获取模板中所有 section (包括 `{{^..}}` 和 `{{?..}}`) 的名称，去重后按出现顺序返回，
+    /// `{{#if expr}}` 以表达式的源码作为名称
+    pub fn section_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for block in &self.blocks {
+            let name = match block.tag {
+                Tag::Section | Tag::Inverse | Tag::NotNone => block.name.as_str(),
+                Tag::If => block.expr.as_ref().expect("Unexpected error").source(),
+                _ => continue,
+            };
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+
+        names
+    }
+
+    /// 不依赖 `Content`，按 `enabled` 指定的 section 开关渲染出一种 sql 组合，
+    /// 所有变量都输出为 `placeholder`，用于在编译期对 sql 的各种组合进行校验
+    pub fn render_sql_variant<F>(&self, enabled: F, placeholder: &str) -> String
+    where
+        F: Fn(&str) -> bool,
+    {
+        let mut buf = SqlEncoder::with_capacity(self.capacity_hint);
+        Self::render_variant_blocks(&self.blocks, &enabled, placeholder, &mut buf);
+
+        buf.trim()
+    }
+
+    fn render_variant_blocks<F>(blocks: &[Block], enabled: &F, placeholder: &str, encoder: &mut SqlEncoder<'_>)
+    where
+        F: Fn(&str) -> bool,
+    {
+        use crate::encoding::Encoder;
+
+        let mut index = 0;
+        while let Some(block) = blocks.get(index) {
+            index += 1;
+            let _ = encoder.write_unescaped(&block.html);
+
+            match block.tag {
+                Tag::Escaped | Tag::Unescaped => {
+                    let _ = encoder.format_unescaped(placeholder);
+                }
+                Tag::Section | Tag::Inverse | Tag::NotNone => {
+                    let children = &blocks[index..index + block.children as usize];
+                    let is_on = enabled(&block.name);
+                    if (block.tag == Tag::Inverse) != is_on {
+                        Self::render_variant_blocks(children, enabled, placeholder, encoder);
+                    }
+                    index += block.children as usize;
+                }
+                Tag::If => {
+                    let children = &blocks[index..index + block.children as usize];
+                    if enabled(block.expr.as_ref().expect("Unexpected error").source()) {
+                        Self::render_variant_blocks(children, enabled, placeholder, encoder);
+                    }
+                    index += block.children as usize;
+                }
+                Tag::Trim => {
+                    let children = &blocks[index..index + block.children as usize];
+                    let mut body = SqlEncoder::with_capacity(block.html.len());
+                    Self::render_variant_blocks(children, enabled, placeholder, &mut body);
+                    if let Some(trimmed) = block.trim.as_ref().and_then(|spec| spec.apply(&body.inner)) {
+                        let _ = encoder.write_unescaped(&trimmed);
+                    }
+                    index += block.children as usize;
+                }
+                _ => (),
+            }
+        }
+    }
+
     /// Render this `Template` with a given `Content` to a writer.
     pub fn render_to_writer<W, C>(&self, writer: &mut W, content: &C) -> io::Result<()>
     where
@@ -161,7 +316,7 @@ impl Template {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq)]
 #[derive(Serialize, Deserialize)]
 pub(crate) struct Block {
     html: String,
@@ -169,6 +324,8 @@ pub(crate) struct Block {
     hash: u64,
     tag: Tag,
     children: u32,
+    trim: Option<TrimSpec>,
+    expr: Option<Expr>,
 }
 
 impl Block {
@@ -180,6 +337,26 @@ impl Block {
             hash: hash_name(name),
             tag,
             children: 0,
+            trim: None,
+            expr: None,
+        }
+    }
+
+    /// `{{#where}}`，`{{#set}}`，`{{#trim ...}}` 裁剪元素
+    #[inline]
+    fn trim(html: &str, name: &str, spec: TrimSpec) -> Self {
+        Block {
+            trim: Some(spec),
+            ..Block::new(html, name, Tag::Trim)
+        }
+    }
+
+    /// `{{#if expr}}` 条件表达式 section
+    #[inline]
+    fn expr(html: &str, name: &str, expr: Expr) -> Self {
+        Block {
+            expr: Some(expr),
+            ..Block::new(html, name, Tag::If)
         }
     }
 
@@ -192,6 +369,8 @@ impl Block {
             hash: 0,
             tag,
             children: 0,
+            trim: None,
+            expr: None,
         }
     }
 }
@@ -232,6 +411,8 @@ mod test {
                 hash: 2271575940368597870,
                 tag: Tag::Escaped,
                 children: 0,
+                trim: None,
+                expr: None,
             }
         );
     }
@@ -271,6 +452,22 @@ mod test {
         );
     }
 
+    #[test]
+    fn renders_sql_variant_correctly() {
+        let source = "select * from t where ![DEL(and)] {{#name}}and name = :name{{/name}} {{^age}}and age is null{{/age}} order by {{field}}";
+        let tpl = Template::new(source).unwrap();
+
+        assert_eq!(tpl.section_names(), vec!["name", "age"]);
+        assert_eq!(
+            tpl.render_sql_variant(|_| true, "1"),
+            "select * from t where name = :name order by 1"
+        );
+        assert_eq!(
+            tpl.render_sql_variant(|_| false, "1"),
+            "select * from t where age is null order by 1"
+        );
+    }
+
     #[test]
     fn constructs_nested_sections_with_dot_correctly() {
         let source = "<body><h1>{{site title}}</h1>{{^archive posts}}<article>{{name}}</article>{{/archive posts}}</body>";
@@ -12,6 +12,8 @@ use logos::Logos;
 use serde::{Serialize, Deserialize};
 
 use super::{hash_name, Block, TemplateError, Template};
+use super::expr::Expr;
+use super::trim::TrimSpec;
 use crate::Partials;
 
 #[derive(Debug, PartialEq, Clone, Copy, Default)]
@@ -63,6 +65,12 @@ pub enum Tag {
 
     /// Tailing html
     Tail,
+
+    /// `{{#where}}`，`{{#set}}`，`{{#trim ...}}` 裁剪元素，由 `{{#` 开始的标签转换而来
+    Trim,
+
+    /// `{{#if expr}}` 条件表达式 section，由 `{{#` 开始的标签转换而来
+    If,
 }
 
 impl From<ParseError> for TemplateError {
@@ -165,6 +173,31 @@ impl Template {
                         self.blocks[tail_idx + i].children = (d - i) as u32; //children: 2 1 0 递减
                     }
                 }
+                // 条件表达式：`if` 之后直到 `}}` 的内容都是表达式，不按空格和 `.` 切分
+                Tag::Section if name == "if" => {
+                    let remainder = closing.remainder();
+                    let len = expr_len(remainder).ok_or(TemplateError::UnclosedTag)?;
+                    let expr = Expr::parse(&remainder[..len])?;
+                    closing.bump(len);
+                    if !matches!(closing.next(), Some(Ok(Closing::Match))) {
+                        return Err(TemplateError::UnclosedTag);
+                    }
+                    stack.try_push(self.blocks.len())?;
+                    self.blocks.push(Block::expr(html, name, expr));
+                }
+                // 裁剪元素：标识符后空格分隔的是元素的属性，而不是嵌套的 section
+                Tag::Section if TrimSpec::for_element(name).is_some() => {
+                    let mut spec = TrimSpec::for_element(name).expect("Unexpected error");
+                    loop {
+                        match closing.next() {
+                            Some(Ok(Closing::Ident)) => spec.set_attr(closing.slice())?,
+                            Some(Ok(Closing::Match)) => break,
+                            _ => return Err(TemplateError::UnclosedTag),
+                        }
+                    }
+                    stack.try_push(self.blocks.len())?;
+                    self.blocks.push(Block::trim(html, name, spec));
+                }
                 Tag::Section | Tag::Inverse => loop {
                     // 如果起始为 {{# ，在标识符后又找到空格分隔的下一个标识符
                     match closing.next() {
@@ -278,10 +311,34 @@ impl Template {
             lex.extras = Braces::Two;
         }
 
+        // 模板结束时还有未关闭的 section
+        if let Some(&head_idx) = stack.last() {
+            return Err(TemplateError::UnclosedSection(self.blocks[head_idx].name.clone()));
+        }
+
         Ok(last)
     }
 }
 
+/// 表达式的长度，即到 `}}` 为止的字节数，引号中的 `}}` 不算结束
+fn expr_len(source: &str) -> Option<usize> {
+    let bytes = source.as_bytes();
+    let mut quote = None;
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match (quote, bytes[idx]) {
+            (Some(_), b'\\') => idx += 1,
+            (Some(q), c) if c == q => quote = None,
+            (None, c @ (b'\'' | b'"')) => quote = Some(c),
+            (None, b'}') if bytes.get(idx + 1) == Some(&b'}') => return Some(idx),
+            _ => (),
+        }
+        idx += 1;
+    }
+
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use crate::Template;
@@ -297,4 +354,13 @@ mod tests {
         ";
         let _tpl = Template::new(s).unwrap();
     }
+
+    #[test]
+    fn test_unclosed_section() {
+        let s = "select * from t where 1 = 1 {{#age}}and age > :age";
+        match Template::new(s) {
+            Err(crate::TemplateError::UnclosedSection(name)) => assert_eq!("age", name),
+            _ => panic!("unclosed section should fail"),
+        }
+    }
 }
\ No newline at end of file
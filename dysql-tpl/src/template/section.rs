@@ -8,11 +8,15 @@
 // along with Ramhorns.  If not, see <http://www.gnu.org/licenses/>
 
 use super::{Block, Tag};
-use crate::encoding::Encoder;
+use crate::encoding::{Encoder, NeverError};
 use crate::{Content, Next};
 use crate::traits::ContentSequence;
+use std::fmt;
 use std::ops::Range;
 
+#[cfg(feature = "pulldown-cmark")]
+use pulldown_cmark::Event;
+
 /// A section of a `Template` that can be rendered individually, usually delimited by
 /// `{{#section}} ... {{/section}}` tags.
 #[derive(Clone, Copy)]
@@ -80,6 +84,7 @@ where
         E: Encoder,
     {
         let mut index = 0;
+        encoder.enter_section();
 
         while let Some(block) = self.blocks.get(index) { // 消耗本次 render 所需的一层 block
             index += 1;
@@ -87,6 +92,7 @@ where
 
             match block.tag {
                 Tag::Escaped => {
+                    encoder.write_value();
                     if block.name == "$value" {
                         if let Some(content) = content {
                             content.render_escaped(encoder)?; 
@@ -96,6 +102,7 @@ where
                     }
                 }
                 Tag::Unescaped => {
+                    encoder.write_value();
                     if block.name == "$value" {
                         if let Some(content) = content {
                             content.render_unescaped(encoder)?; 
@@ -106,36 +113,69 @@ where
                     
                 }
                 Tag::Section => {
+                    encoder.begin_section();
                     self.contents.render_field_section(
                         block.hash, // block0.hash，block0.child = 2
                         &block.name,  // block0.name
                         self.slice(index..index + block.children as usize), // 消去本次 render_field_section 后剩下的 子blocks[1，2]， block3 不是 block0 的子 block
                         encoder,
                     )?;
+                    encoder.end_section();
                     index += block.children as usize;
                 }
                 Tag::Inverse => {
+                    encoder.begin_section();
                     self.contents.render_field_inverse(
                         block.hash,
                         &block.name,
                         self.slice(index..index + block.children as usize),
                         encoder,
                     )?;
+                    encoder.end_section();
                     index += block.children as usize;
                 }
                 Tag::NotNone => {
+                    encoder.begin_section();
                     let rst = self.contents.render_field_notnone_section(
                         block.hash,
                         &block.name,
                         self.slice(index..index + block.children as usize),
                         encoder,
                     )?;
+                    encoder.end_section();
+
+                    // 内容是否接着原样输出也记录为一个分支
+                    encoder.begin_section();
+                    if rst {
+                        encoder.enter_section();
+                    }
+                    encoder.end_section();
 
                     if !rst {
                         index += block.children as usize;
                     }
                     // index += block.children as usize;
                 }
+                Tag::If => {
+                    encoder.begin_section();
+                    if block.expr.as_ref().is_some_and(|expr| expr.eval(&self.contents)) {
+                        self.slice(index..index + block.children as usize).render(encoder, content)?;
+                    }
+                    encoder.end_section();
+                    index += block.children as usize;
+                }
+                Tag::Trim => {
+                    // 先渲染元素的内容，裁剪后再写入 encoder
+                    encoder.begin_section();
+                    let mut body = TrimBody { body: String::new(), outer: encoder };
+                    let _ = self.slice(index..index + block.children as usize).render(&mut body, content);
+                    let body = body.body;
+                    encoder.end_section();
+                    if let Some(trimmed) = block.trim.as_ref().and_then(|spec| spec.apply(&body)) {
+                        encoder.write_unescaped(&trimmed)?;
+                    }
+                    index += block.children as usize;
+                }
                 _ => {}
             }
         }
@@ -143,3 +183,89 @@ where
         Ok(())
     }
 }
+
+/// `Encoder` 中记录分支的部分，`TrimBody` 通过它转发给外层的 encoder，避免嵌套的元素生成无限的泛型实例
+trait SectionHooks {
+    fn begin_section(&mut self);
+    fn enter_section(&mut self);
+    fn end_section(&mut self);
+    fn write_value(&mut self);
+}
+
+impl<E: Encoder> SectionHooks for E {
+    #[inline]
+    fn begin_section(&mut self) {
+        Encoder::begin_section(self)
+    }
+
+    #[inline]
+    fn enter_section(&mut self) {
+        Encoder::enter_section(self)
+    }
+
+    #[inline]
+    fn end_section(&mut self) {
+        Encoder::end_section(self)
+    }
+
+    #[inline]
+    fn write_value(&mut self) {
+        Encoder::write_value(self)
+    }
+}
+
+/// `{{#where}}` 等元素的内容先渲染到 `body` 中，分支及变量的记录仍交给外层的 encoder
+struct TrimBody<'e> {
+    body: String,
+    outer: &'e mut dyn SectionHooks,
+}
+
+impl Encoder for TrimBody<'_> {
+    type Error = NeverError;
+
+    #[inline]
+    fn write_unescaped(&mut self, part: &str) -> Result<(), Self::Error> {
+        self.body.write_unescaped(part)
+    }
+
+    #[inline]
+    fn write_escaped(&mut self, part: &str) -> Result<(), Self::Error> {
+        self.body.write_escaped(part)
+    }
+
+    #[cfg(feature = "pulldown-cmark")]
+    #[inline]
+    fn write_html<'b, I: Iterator<Item = Event<'b>>>(&mut self, iter: I) -> Result<(), Self::Error> {
+        self.body.write_html(iter)
+    }
+
+    #[inline]
+    fn format_unescaped<D: fmt::Display>(&mut self, display: D) -> Result<(), Self::Error> {
+        self.body.format_unescaped(display)
+    }
+
+    #[inline]
+    fn format_escaped<D: fmt::Display>(&mut self, display: D) -> Result<(), Self::Error> {
+        self.body.format_escaped(display)
+    }
+
+    #[inline]
+    fn begin_section(&mut self) {
+        self.outer.begin_section()
+    }
+
+    #[inline]
+    fn enter_section(&mut self) {
+        self.outer.enter_section()
+    }
+
+    #[inline]
+    fn end_section(&mut self) {
+        self.outer.end_section()
+    }
+
+    #[inline]
+    fn write_value(&mut self) {
+        self.outer.write_value()
+    }
+}
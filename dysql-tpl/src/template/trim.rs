@@ -0,0 +1,129 @@
+use serde::{Serialize, Deserialize};
+
+use crate::TemplateError;
+
+/// `{{#where}}`，`{{#set}}`，`{{#trim ...}}` 元素的裁剪规则：
+/// 渲染后的内容不为空时，先去掉开头的 prefix_overrides 和结尾的 suffix_overrides，再加上 prefix 和 suffix
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+#[derive(Serialize, Deserialize)]
+pub(crate) struct TrimSpec {
+    prefix: String,
+    suffix: String,
+    prefix_overrides: Vec<String>,
+    suffix_overrides: Vec<String>,
+}
+
+impl TrimSpec {
+    /// 根据元素名获取默认的裁剪规则，不是裁剪元素时返回 None
+    pub(crate) fn for_element(name: &str) -> Option<Self> {
+        let spec = match name {
+            "where" => TrimSpec {
+                prefix: "WHERE".to_owned(),
+                prefix_overrides: vec!["AND".to_owned(), "OR".to_owned()],
+                ..Default::default()
+            },
+            "set" => TrimSpec {
+                prefix: "SET".to_owned(),
+                prefix_overrides: vec![",".to_owned()],
+                suffix_overrides: vec![",".to_owned()],
+                ..Default::default()
+            },
+            "trim" => TrimSpec::default(),
+            _ => return None,
+        };
+
+        Some(spec)
+    }
+
+    /// 设置 `key=value` 形式的属性，`*_overrides` 的多个值用 `|` 分隔
+    pub(crate) fn set_attr(&mut self, attr: &str) -> Result<(), TemplateError> {
+        let (key, value) = attr
+            .split_once('=')
+            .ok_or_else(|| TemplateError::IllegalTrimAttribute(attr.to_owned()))?;
+        let overrides = || value.split('|').filter(|v| !v.is_empty()).map(|v| v.to_owned()).collect();
+
+        match key {
+            "prefix" => self.prefix = value.to_owned(),
+            "suffix" => self.suffix = value.to_owned(),
+            "prefix_overrides" => self.prefix_overrides = overrides(),
+            "suffix_overrides" => self.suffix_overrides = overrides(),
+            _ => return Err(TemplateError::IllegalTrimAttribute(attr.to_owned())),
+        }
+
+        Ok(())
+    }
+
+    /// 裁剪渲染后的内容，内容为空时返回 None
+    pub(crate) fn apply(&self, body: &str) -> Option<String> {
+        let mut body = body.trim();
+        if let Some(rest) = self.prefix_overrides.iter().find_map(|ov| strip_prefix_word(body, ov)) {
+            body = rest.trim_start();
+        }
+        if let Some(rest) = self.suffix_overrides.iter().find_map(|ov| strip_suffix_word(body, ov)) {
+            body = rest.trim_end();
+        }
+        if body.is_empty() {
+            return None;
+        }
+
+        let mut rst = String::with_capacity(self.prefix.len() + body.len() + self.suffix.len() + 2);
+        if !self.prefix.is_empty() {
+            rst.push_str(&self.prefix);
+            rst.push(' ');
+        }
+        rst.push_str(body);
+        if !self.suffix.is_empty() {
+            rst.push(' ');
+            rst.push_str(&self.suffix);
+        }
+
+        Some(rst)
+    }
+}
+
+#[inline]
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// 忽略大小写去掉开头的 token，token 以单词结尾时要求其后不是单词字符 (避免 `order` 被当作 `or`)
+fn strip_prefix_word<'a>(s: &'a str, token: &str) -> Option<&'a str> {
+    let head = s.get(..token.len())?;
+    let rest = &s[token.len()..];
+    let boundary = !token.ends_with(is_word_char) || !rest.starts_with(is_word_char);
+
+    (head.eq_ignore_ascii_case(token) && boundary).then_some(rest)
+}
+
+/// 忽略大小写去掉结尾的 token，token 以单词开头时要求其前不是单词字符
+fn strip_suffix_word<'a>(s: &'a str, token: &str) -> Option<&'a str> {
+    let split = s.len().checked_sub(token.len())?;
+    let tail = s.get(split..)?;
+    let rest = &s[..split];
+    let boundary = !token.starts_with(is_word_char) || !rest.ends_with(is_word_char);
+
+    (tail.eq_ignore_ascii_case(token) && boundary).then_some(rest)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trim_spec() {
+        let spec = TrimSpec::for_element("where").unwrap();
+        assert_eq!(Some("WHERE name = :name".to_owned()), spec.apply("  and name = :name "));
+        assert_eq!(Some("WHERE ordered = 1".to_owned()), spec.apply("ordered = 1"));
+        assert_eq!(None, spec.apply("  \n "));
+
+        let spec = TrimSpec::for_element("set").unwrap();
+        assert_eq!(Some("SET name = :name, age = :age".to_owned()), spec.apply("name = :name, age = :age, "));
+
+        let mut spec = TrimSpec::for_element("trim").unwrap();
+        spec.set_attr("prefix=(").unwrap();
+        spec.set_attr("suffix=)").unwrap();
+        spec.set_attr("prefix_overrides=AND|OR").unwrap();
+        assert_eq!(Some("( a = 1 or b = 2 )".to_owned()), spec.apply("OR a = 1 or b = 2"));
+        assert!(spec.set_attr("prefixes=(").is_err());
+    }
+}
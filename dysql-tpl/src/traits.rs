@@ -6,7 +6,7 @@
 use crate::encoding::Encoder;
 use crate::simple::simple_section::SimpleSection;
 use crate::simple::{SimpleValue, SimpleError, SimpleInnerError};
-use crate::template::Section;
+use crate::template::{FieldPath, Operand, Section};
 use crate::Content;
 
 /// Helper trait used to rotate a queue of parent `Content`s. Think of this as of a
@@ -139,6 +139,11 @@ pub trait ContentSequence: Combine + Sized + Copy {
     {
         Ok(false)
     }
+    /// Get a field by the hash **or** string of its name as an operand of `{{#if expr}}`.
+    #[inline]
+    fn field_operand(&self, _hash: u64, _name: &str, _path: &[FieldPath]) -> Option<Operand<'_>> {
+        None
+    }
 }
 
 impl Combine for () {
@@ -327,4 +332,12 @@ where
 
         Ok(rst)
     }
+
+    #[inline]
+    fn field_operand(&self, hash: u64, name: &str, path: &[FieldPath]) -> Option<Operand<'_>> {
+        self.3.field_operand(hash, name, path)
+            .or_else(|| self.2.field_operand(hash, name, path))
+            .or_else(|| self.1.field_operand(hash, name, path))
+            .or_else(|| self.0.field_operand(hash, name, path))
+    }
 }
@@ -0,0 +1,49 @@
+//! 从数据库抓取表结构快照，供编译期 sql 校验使用
+//!
+//! cargo run --example dysql_prepare -- [database_url] [output]
+//!
+//! database_url 未指定时读取 DATABASE_URL，output 未指定时读取 DYSQL_SCHEMA_FILE，默认为 .dysql/schema.json
+
+use dysql::{DySqlResult, SchemaSnapshot};
+
+const DEFAULT_SCHEMA_FILE: &str = ".dysql/schema.json";
+
+#[tokio::main]
+async fn main() {
+    dotenv::dotenv().ok();
+
+    let mut args = std::env::args().skip(1);
+    let url = args.next()
+        .or_else(|| std::env::var("DATABASE_URL").ok())
+        .expect("usage: dysql_prepare [database_url] [output], or set DATABASE_URL");
+    let output = args.next()
+        .or_else(|| std::env::var("DYSQL_SCHEMA_FILE").ok())
+        .unwrap_or_else(|| DEFAULT_SCHEMA_FILE.to_owned());
+
+    let schema = capture(&url).await.unwrap();
+    schema.save(&output).unwrap();
+
+    println!("saved {} tables to {}", schema.tables.len(), output);
+}
+
+/// 按 url 的 scheme 选择数据库
+async fn capture(url: &str) -> DySqlResult<SchemaSnapshot> {
+    use sqlx::Connection;
+
+    let scheme = url.split(':').next().unwrap_or_default();
+    match scheme {
+        "sqlite" => {
+            let mut conn = sqlx::SqliteConnection::connect(url).await.unwrap();
+            SchemaSnapshot::capture_sqlx_sqlite(&mut conn).await
+        }
+        "postgres" | "postgresql" => {
+            let mut conn = sqlx::PgConnection::connect(url).await.unwrap();
+            SchemaSnapshot::capture_sqlx_postgres(&mut conn).await
+        }
+        "mysql" | "mariadb" => {
+            let mut conn = sqlx::MySqlConnection::connect(url).await.unwrap();
+            SchemaSnapshot::capture_sqlx_mysql(&mut conn).await
+        }
+        _ => panic!("unsupported database url: {}", url),
+    }
+}
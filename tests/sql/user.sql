@@ -0,0 +1,9 @@
+-- name: find_by_age
+-- 查询年龄大于 :age 的用户
+SELECT * FROM test_user
+WHERE 1 = 1
+  {{#age}}AND age > :age{{/age}}
+ORDER BY id
+
+-- name: count_all
+SELECT count(*) FROM test_user
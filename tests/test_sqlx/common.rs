@@ -20,7 +20,7 @@ impl UserDto {
 
 #[allow(dead_code)]
 #[derive(Debug, PartialEq)]
-#[derive(FromRow)]
+#[derive(FromRow, Content)]
 pub struct User {
     pub id: i64,
     pub name: Option<String>,
@@ -69,6 +69,13 @@ async fn test_fetch_scalar() -> dysql::DySqlResult<()>{
     })?;
     assert_eq!(9, rst);
 
+    // mysql 的 `\'` 转义及 `#` 注释在编译期同样能够通过校验
+    let value = Value::new(1);
+    let rst = fetch_scalar!(|&conn, value| -> i64 {
+        r#"select count (*) from test_user where id = :value and name <> 'it\'s' # :note"#
+    })?;
+    assert_eq!(1, rst);
+
     Ok(())
 }
 
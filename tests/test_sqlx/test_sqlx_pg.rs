@@ -34,6 +34,29 @@ async fn test_fetch_all() {
     assert_eq!(9, rst.len());
 }
 
+#[tokio::test]
+async fn test_any_array() {
+    let conn = connect_postgres_db().await;
+
+    let dto = UserDto::new(None, None, None, Some(vec![2, 3, 5]));
+    let rst = fetch_all!(|&conn, &dto| -> User {
+        "select * from test_user where id = any(:id_rng) order by id"
+    }).unwrap();
+    assert_eq!(vec![2, 3, 5], rst.iter().map(|u| u.id).collect::<Vec<_>>());
+
+    let dto = Value::new(vec!["a4".to_owned(), "a5".to_owned()]);
+    let rst = fetch_all!(|&conn, &dto| -> User {
+        "select * from test_user where name = any(:value) order by id"
+    }).unwrap();
+    assert_eq!(vec![4, 5], rst.iter().map(|u| u.id).collect::<Vec<_>>());
+
+    let dto = UserDto::new(None, None, None, Some(vec![]));
+    let rst = fetch_all!(|&conn, &dto| -> User {
+        "select * from test_user where id = any(:id_rng)"
+    });
+    assert!(rst.is_err());
+}
+
 sql!("select_sql","select * from test_user ");
 #[tokio::test]
 async fn test_fetch_one() {
@@ -68,6 +91,12 @@ async fn test_fetch_scalar() -> dysql::DySqlResult<()>{
     })?;
     assert_eq!(9, rst);
 
+    let value = Value::new(1 as i64);
+    let rst = fetch_scalar!(|&conn, value| -> i64 {
+        r#"select count (*) from test_user where id::text = :value::text and name <> 'a:b' -- :name"#
+    })?;
+    assert_eq!(1, rst);
+
     Ok(())
 }
 
@@ -2,7 +2,7 @@ mod common;
 
 use std::error::Error;
 
-use dysql::{PageDto, SortModel, sql, fetch_one, insert, fetch_scalar, execute, page, fetch_all, Value};
+use dysql::{PageDto, CursorDto, SortModel, sql, fetch_one, fetch_optional, insert, fetch_scalar, execute, execute_batch, upsert, page, cursor_page, fetch_all, fetch_stream, Value, DySqlResult, Pagination};
 
 use sqlx::Acquire;
 
@@ -61,6 +61,47 @@ async fn test_fetch_all() {
     assert_eq!(9, rst.len());
 }
 
+#[tokio::test]
+async fn test_fetch_stream() {
+    use futures::TryStreamExt;
+
+    let mut conn = connect_db().await;
+
+    let dto = UserDto{ id: None, name: None, age: Some(13) , id_rng: None };
+    let mut stream = fetch_stream!(|&mut conn, &dto| -> User {
+        r#"SELECT * FROM test_user
+        WHERE 1 = 1
+          {{#name}}AND name = :name{{/name}}
+          {{#age}}AND age > :age{{/age}}
+        ORDER BY id"#
+    });
+
+    let first = stream.try_next().await.unwrap();
+    assert_eq!(Some(User { id: 2, name: Some("zhanglan".to_owned()), age: Some(21) }), first);
+
+    let mut count = 1;
+    while let Some(_) = stream.try_next().await.unwrap() {
+        count += 1;
+    }
+    assert_eq!(7, count);
+}
+
+#[tokio::test]
+async fn test_sql_file() {
+    let mut conn = connect_db().await;
+
+    let dto = UserDto{ id: None, name: None, age: Some(13) , id_rng: None };
+    let rst = fetch_all!(|&mut conn, &dto| -> User {
+        file("tests/sql/user.sql", "find_by_age")
+    }).unwrap();
+    assert_eq!(7, rst.len());
+
+    let rst = fetch_scalar!(|&mut conn| -> i64 {
+        file("tests/sql/user.sql", "count_all") + " where id > 5"
+    }).unwrap();
+    assert_eq!(4, rst);
+}
+
 sql!("select_sql","select * from test_user ");
 #[tokio::test]
 async fn test_fetch_one() {
@@ -79,6 +120,141 @@ async fn test_fetch_one() {
     assert_eq!(User { id: 2, name: Some("zhanglan".to_owned()), age: Some(21) }, rst);
 }
 
+#[tokio::test]
+async fn test_fetch_optional() {
+    let mut conn = connect_db().await;
+
+    let dto = UserDto{ id: Some(2), name: None, age: None, id_rng: None };
+    let rst = fetch_optional!(|&mut conn, &dto| -> User {
+        select_sql + "where id = :id"
+    }).unwrap();
+    assert_eq!(Some(User { id: 2, name: Some("zhanglan".to_owned()), age: Some(21) }), rst);
+
+    let dto = UserDto{ id: Some(100), name: None, age: None, id_rng: None };
+    let rst = fetch_optional!(|&mut conn, &dto| -> User {
+        select_sql + "where id = :id"
+    }).unwrap();
+    assert_eq!(None, rst);
+}
+
+struct AppState {
+    conn: sqlx::SqliteConnection,
+}
+
+struct UserQuery {
+    filter: UserDto,
+}
+
+impl UserQuery {
+    fn filter(&self) -> &UserDto {
+        &self.filter
+    }
+}
+
+#[tokio::test]
+async fn test_expr_executor_and_dto() {
+    let mut state = AppState { conn: connect_db().await };
+    let req = UserQuery { filter: UserDto{ id: None, name: None, age: Some(13), id_rng: None } };
+
+    let rst = fetch_all!(|&mut state.conn, &req.filter| -> User {
+        select_sql + "where age > :age order by id"
+    }).unwrap();
+    assert_eq!(7, rst.len());
+
+    let rst = fetch_all!(|&mut state.conn, req.filter()| -> User {
+        select_sql + "where age > :age order by id"
+    }).unwrap();
+    assert_eq!(7, rst.len());
+
+    let rst = fetch_one!(|&mut state.conn, Value::new(2_i64)| -> User {
+        select_sql + "where id = :value"
+    }).unwrap();
+    assert_eq!(User { id: 2, name: Some("zhanglan".to_owned()), age: Some(21) }, rst);
+
+    // executor 和 dto 表达式只求值一次
+    let calls = std::cell::Cell::new(0);
+    let make_dto = || {
+        calls.set(calls.get() + 1);
+        UserDto{ id: None, name: None, age: Some(13), id_rng: None }
+    };
+    let rst = fetch_all!(|&mut state.conn, make_dto()| -> User {
+        select_sql + "where age > :age order by id"
+    }).unwrap();
+    assert_eq!(7, rst.len());
+    assert_eq!(1, calls.get());
+
+    let dto = make_dto();
+    let make_pg_dto = || {
+        calls.set(calls.get() + 1);
+        PageDto::new(3, 10, Some(&dto))
+    };
+    let rst = page!(|&mut state.conn, make_pg_dto()| -> User {
+        "select * from test_user where {{#data}}age > :data.age{{/data}}"
+    }).unwrap();
+    assert_eq!(7, rst.total);
+    assert_eq!(3, calls.get());
+
+    let mut tran = state.conn.begin().await.unwrap();
+    let rst = fetch_scalar!(|&mut *tran, _| -> i64 {
+        r#"select count(*) from test_user"#
+    }).unwrap();
+    assert_eq!(9, rst);
+    tran.rollback().await.unwrap();
+
+    // 变量形式的 executor 直接使用，不会被 move
+    let db = std::cell::RefCell::new(state.conn);
+    let mut conn = db.borrow_mut();
+    let rst = fetch_scalar!(|conn| -> i64 {
+        r#"select count(*) from test_user"#
+    }).unwrap();
+    assert_eq!(9, rst);
+    let rst = fetch_scalar!(|conn| -> i64 {
+        r#"select count(*) from test_user where age > 13"#
+    }).unwrap();
+    assert_eq!(7, rst);
+}
+
+#[tokio::test]
+async fn test_tuple_ret_type() {
+    use futures::TryStreamExt;
+
+    let mut conn = connect_db().await;
+
+    let dto = UserDto{ id: None, name: None, age: Some(30), id_rng: None };
+    let rst = fetch_all!(|&mut conn, &dto| -> (i64, Option<String>) {
+        r#"select id, name from test_user where age > :age order by id"#
+    }).unwrap();
+    assert_eq!(vec![(3, Some("zhangsan".to_owned())), (8, Some("a8".to_owned())), (9, Some("a9".to_owned()))], rst);
+
+    let rst = fetch_one!(|&mut conn| -> (i64, i32) {
+        r#"select id, age from test_user where id = 2"#
+    }).unwrap();
+    assert_eq!((2, 21), rst);
+
+    let rst = fetch_one!(|&mut conn| -> Option<User> {
+        r#"select * from test_user where id = 100"#
+    }).unwrap();
+    assert_eq!(None, rst);
+
+    let rst = fetch_optional!(|&mut conn| -> (String,) {
+        r#"select name from test_user where id = 1"#
+    }).unwrap();
+    assert_eq!(Some(("huanglan".to_owned(),)), rst);
+
+    let mut stream = fetch_stream!(|&mut conn, &dto| -> (i64, Option<i32>) {
+        r#"select id, age from test_user where age > :age order by id"#
+    });
+    assert_eq!(Some((3, Some(35))), stream.try_next().await.unwrap());
+    drop(stream);
+
+    let mut pg_dto = PageDto::new(2, 0, Some(&dto));
+    let rst = page!(|&mut conn, pg_dto| -> (i64, Option<String>) {
+        r#"select id, name from test_user where age > :data.age order by id"#
+    }).unwrap();
+    assert_eq!(3, rst.total);
+    assert_eq!(vec![(3, Some("zhangsan".to_owned())), (8, Some("a8".to_owned()))], rst.data);
+}
+
 #[tokio::test]
 async fn test_fetch_scalar() -> dysql::DySqlResult<()>{
     let mut conn = connect_db().await;
@@ -114,6 +290,30 @@ async fn test_execute() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_execute_batch() -> Result<(), Box<dyn Error>> {
+    let mut conn = connect_db().await;
+    let mut tran = conn.begin().await?;
+
+    let dtos = vec![
+        UserDto{ id: None, name: Some("a".to_owned()), age: Some(1), id_rng: None },
+        UserDto{ id: None, name: Some("b".to_owned()), age: None, id_rng: None },
+        UserDto{ id: None, name: Some("c".to_owned()), age: Some(3), id_rng: None },
+    ];
+    let affected_rows_nums = execute_batch!(|&mut *tran, dtos| {
+        r#"insert into test_user (name {{#age}}, age{{/age}}) values (:name {{#age}}, :age{{/age}})"#
+    })?;
+    assert_eq!(vec![1, 1, 1], affected_rows_nums);
+
+    let count = fetch_scalar!(|&mut *tran| -> i64 {
+        r#"select count(*) from test_user where name in ('a', 'b', 'c')"#
+    })?;
+    assert_eq!(3, count);
+    tran.rollback().await?;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_insert() -> Result<(), Box<dyn Error>> {
     let mut conn = connect_db().await;
@@ -129,6 +329,74 @@ async fn test_insert() -> Result<(), Box<dyn Error>> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_scoped_fragment() {
+    let mut conn = connect_db().await;
+
+    // 片段在之后的模块中定义，通过路径引用，并在编译 sql 模板前替换片段参数
+    let dto = UserDto{ id: None, name: None, age: Some(30), id_rng: None };
+    let rst = fetch_all!(|&mut conn, &dto| -> User {
+        fragments::select_user("u") + "where " + fragments::age_filter("u") + " order by u.id"
+    }).unwrap();
+    assert_eq!(
+        vec![
+            User { id: 3, name: Some("zhangsan".to_owned()), age: Some(35) },
+            User { id: 8, name: Some("a8".to_owned()), age: Some(31) },
+            User { id: 9, name: Some("a9".to_owned()), age: Some(33) },
+        ],
+        rst
+    );
+
+    // pub 的片段同样通过模块路径引用，不同模块中的同名片段互不影响
+    let rst = fetch_all!(|&mut conn, &dto| -> User {
+        fragments::select_pub + "where " + fragments::nested::select_pub + " order by id"
+    }).unwrap();
+    assert_eq!(vec![3, 8, 9], rst.iter().map(|u| u.id).collect::<Vec<_>>());
+}
+
+mod fragments {
+    use dysql::sql;
+
+    sql!(pub(crate) select_user(alias), "select ${alias}.* from test_user ${alias} ");
+    sql!(pub(crate) age_filter(alias), "${alias}.age > :age");
+    sql!(pub select_pub, "select * from test_user ");
+
+    pub mod nested {
+        dysql::sql!(pub select_pub, "age > :age");
+    }
+}
+
+#[tokio::test]
+async fn test_upsert() -> Result<(), Box<dyn Error>> {
+    let mut conn = connect_db().await;
+    let mut tran = conn.begin().await?;
+
+    let dto = UserDto{ id: Some(3), name: Some("lisi".to_owned()), age: Some(50), id_rng: None };
+    let affected_rows_num = upsert!(|&mut *tran, &dto| {
+        r#"insert into test_user (id, name, age) values (:id, :name, :age)"#
+    }, conflict(id), update(name))?;
+    assert_eq!(1, affected_rows_num);
+
+    let user = fetch_one!(|&mut *tran, dto| -> User {
+        r#"select * from test_user where id = :id"#
+    })?;
+    assert_eq!(User { id: 3, name: Some("lisi".to_owned()), age: Some(35) }, user);
+
+    let dto = UserDto{ id: Some(20), name: Some("wangwu".to_owned()), age: Some(18), id_rng: None };
+    let insert_id = upsert!(|&mut *tran, &dto| -> i64 {
+        r#"insert into test_user (id, name, age) values (:id, :name, :age)"#
+    }, conflict(id), update(name, age))?;
+    assert_eq!(20, insert_id);
+
+    let affected_rows_num = upsert!(|&mut *tran, dto| {
+        r#"insert into test_user (id, name, age) values (:id, :name, :age)"#
+    }, conflict(id))?;
+    assert_eq!(0, affected_rows_num);
+    tran.rollback().await?;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_page() {
     let mut conn = connect_db().await;
@@ -155,6 +423,123 @@ async fn test_page() {
     assert_eq!(9, rst.total);
 }
 
+#[tokio::test]
+async fn test_cursor_page() {
+    let mut conn = connect_db().await;
+
+    let dto = UserDto{ id: None, name: None, age: Some(13), id_rng: None };
+    let sort_model = vec![
+        SortModel {field: "age".to_owned(), sort: "asc".to_owned()},
+        SortModel {field: "id".to_owned(), sort: "asc".to_owned()},
+    ];
+    let mut cursor_dto = CursorDto::new(3, Some(&dto), sort_model, None);
+    let mut ids = vec![];
+    loop {
+        let rst = cursor_page!(|&mut conn, cursor_dto| -> User {
+            "select * from test_user where 1 = 1 {{#data}}{{#age}}and age > :data.age{{/age}}{{/data}}"
+        }).unwrap();
+        ids.extend(rst.data.iter().map(|u| u.id));
+        if !rst.has_next {
+            assert!(rst.next_cursor.is_none());
+            break;
+        }
+        cursor_dto.cursor = rst.next_cursor;
+    }
+    assert_eq!(vec![2, 5, 6, 7, 8, 9, 3], ids);
+
+    // 排序方向不一致
+    let sort_model = vec![
+        SortModel {field: "age".to_owned(), sort: "desc".to_owned()},
+        SortModel {field: "id".to_owned(), sort: "asc".to_owned()},
+    ];
+    let mut cursor_dto = CursorDto::new(3, Option::<()>::None, sort_model, None);
+    let rst = cursor_page!(|&mut conn, cursor_dto| -> User { "select * from test_user" }).unwrap();
+    assert_eq!(vec![3, 9, 8], rst.data.iter().map(|u| u.id).collect::<Vec<_>>());
+    cursor_dto.cursor = rst.next_cursor;
+    let rst = cursor_page!(|&mut conn, cursor_dto| -> User { "select * from test_user" }).unwrap();
+    assert_eq!(vec![7, 6, 2], rst.data.iter().map(|u| u.id).collect::<Vec<_>>());
+    assert!(rst.has_next);
+
+    // IN 列表展开后游标的参数继续绑定
+    let dto = UserDto{ id: None, name: None, age: None, id_rng: Some(vec![1, 2, 3, 4]) };
+    let sort_model = vec![SortModel {field: "id".to_owned(), sort: "asc".to_owned()}];
+    let mut cursor_dto = CursorDto::new(3, Some(&dto), sort_model, None);
+    let rst = cursor_page!(|&mut conn, cursor_dto| -> User {
+        "select * from test_user where id in (:data.id_rng)"
+    }).unwrap();
+    assert_eq!(vec![1, 2, 3], rst.data.iter().map(|u| u.id).collect::<Vec<_>>());
+    cursor_dto.cursor = rst.next_cursor;
+    let rst = cursor_page!(|&mut conn, cursor_dto| -> User {
+        "select * from test_user where id in (:data.id_rng)"
+    }).unwrap();
+    assert_eq!(vec![4], rst.data.iter().map(|u| u.id).collect::<Vec<_>>());
+    assert!(!rst.has_next);
+}
+
+#[tokio::test]
+async fn test_where_set_element() {
+    let mut conn = connect_db().await;
+
+    let dto = UserDto::new(Some(4), Some("a4_new".to_owned()), None, None);
+    let rst = execute!(|&mut conn, &dto| {
+        "update test_user {{#set}} {{#name}}name = :name,{{/name}} {{#age}}age = :age,{{/age}} {{/set}} where id = :id"
+    }).unwrap();
+    assert_eq!(1, rst);
+
+    let rst = fetch_all!(|&mut conn, &dto| -> User {
+        "select * from test_user {{#where}} {{#name}}and name = :name{{/name}} {{#age}}and age > :age{{/age}} {{/where}}"
+    }).unwrap();
+    assert_eq!(vec![User { id: 4, name: Some("a4_new".to_owned()), age: Some(12) }], rst);
+
+    let dto = UserDto::new(None, None, None, None);
+    let rst = fetch_all!(|&mut conn, &dto| -> User {
+        "select * from test_user {{#where}} {{#name}}and name = :name{{/name}} {{#age}}and age > :age{{/age}} {{/where}}"
+    }).unwrap();
+    assert_eq!(9, rst.len());
+}
+
+#[tokio::test]
+async fn test_if_expression() {
+    let mut conn = connect_db().await;
+
+    let dto = UserDto::new(None, Some("a5".to_owned()), Some(20), Some(vec![]));
+    let rst = fetch_all!(|&mut conn, &dto| -> User {
+        "select * from test_user {{#where}}
+            {{#if age > 30}}and age > :age{{/if}}
+            {{#if name == 'a5' || name == 'a6'}}and name = :name{{/if}}
+            {{#if len(id_rng) > 0}}and id in (:id_rng){{/if}}
+        {{/where}} order by id"
+    }).unwrap();
+    assert_eq!(vec![User { id: 5, name: Some("a5".to_owned()), age: Some(21) }], rst);
+
+    let dto = UserDto::new(None, None, Some(30), Some(vec![3, 8, 9]));
+    let rst = fetch_all!(|&mut conn, &dto| -> User {
+        "select * from test_user {{#where}}
+            {{#if age > 30}}and age > :age{{/if}}
+            {{#if name == 'a5' || name == 'a6'}}and name = :name{{/if}}
+            {{#if len(id_rng) > 0}}and id in (:id_rng){{/if}}
+        {{/where}} order by id"
+    }).unwrap();
+    assert_eq!(3, rst.len());
+}
+
+#[tokio::test]
+async fn test_sql_comments() {
+    let mut conn = connect_db().await;
+
+    let dto = UserDto::new(None, Some("a5".to_owned()), Some(13), None);
+    let rst = fetch_all!(|&mut conn, &dto| -> User {
+        "select * from test_user -- 按 name 和 age 过滤
+        {{#where}}
+            -- name 为 None 时不过滤 :name
+            {{#name}}and name = :name{{/name}} -- 精确匹配
+            {{#age}}and age > :age{{/age}}
+        {{/where}}
+        order by id -- 按 id 排序"
+    }).unwrap();
+    assert_eq!(vec![User { id: 5, name: Some("a5".to_owned()), age: Some(21) }], rst);
+}
+
 #[tokio::test]
 async fn test_trim_sql() {
     let mut conn = connect_db().await;
@@ -181,4 +566,99 @@ async fn test_trim_sql() {
     // println!("{:?}", rst);
 
     assert_eq!(2, rst.total);
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_in_list() {
+    let mut conn = connect_db().await;
+
+    let dto = UserDto::new(None, None, None, Some(vec![2, 3, 5]));
+    let rst = fetch_all!(|&mut conn, &dto| -> User {
+        "select * from test_user where id in (:id_rng) order by id"
+    }).unwrap();
+    assert_eq!(vec![2, 3, 5], rst.iter().map(|u| u.id).collect::<Vec<_>>());
+
+    let dto = UserDto::new(None, None, Some(13), Some(vec![1, 2, 3, 4]));
+    let mut pg_dto = PageDto::new(2, 1, Some(&dto));
+    let rst = page!(|&mut conn, pg_dto| -> User {
+        "select * from test_user where age > :data.age and id in ( :data.id_rng ) order by id"
+    }).unwrap();
+    assert_eq!(2, rst.total);
+    assert_eq!(vec![2, 3], rst.data.iter().map(|u| u.id).collect::<Vec<_>>());
+
+    let dto = UserDto::new(None, None, None, Some(vec![]));
+    let rst = fetch_all!(|&mut conn, &dto| -> User {
+        "select * from test_user where id in (:id_rng)"
+    });
+    assert!(rst.is_err());
+
+    // 同一处调用先绑定 None 再绑定列表，不能使用绑定 None 时渲染出的 sql
+    let mut rsts = vec![];
+    for id_rng in [None, Some(vec![2, 3])] {
+        let dto = UserDto::new(None, None, None, id_rng);
+        let rst = fetch_all!(|&mut conn, &dto| -> User {
+            "select * from test_user where id in (:id_rng) order by id"
+        }).unwrap();
+        rsts.push(rst.iter().map(|u| u.id).collect::<Vec<_>>());
+    }
+    assert_eq!(vec![vec![], vec![2, 3]], rsts);
+}
+
+struct UserRepo {
+    conn: sqlx::SqliteConnection,
+}
+
+#[dysql::mapper(UserRepo, conn)]
+trait UserMapper {
+    #[sql(r#"SELECT * FROM test_user
+        WHERE 1 = 1
+          {{#name}}AND name = :name{{/name}}
+          {{#age}}AND age > :age{{/age}}
+        ORDER BY id"#)]
+    async fn find_users(&mut self, dto: &UserDto) -> DySqlResult<Vec<User>>;
+
+    #[sql("select * from test_user where id = :value")]
+    async fn find_by_id(&mut self, dto: Value<i64>) -> DySqlResult<User>;
+
+    #[sql("select * from test_user where id = :value")]
+    async fn find_optional_by_id(&mut self, dto: Value<i64>) -> DySqlResult<Option<User>>;
+
+    #[sql("select count(*) from test_user")]
+    async fn count(&mut self) -> DySqlResult<i64>;
+
+    #[sql("insert into test_user (name, age) values (:name, :age)")]
+    async fn add(&mut self, dto: &UserDto) -> DySqlResult<i64>;
+
+    #[execute("delete from test_user where id = :id")]
+    async fn remove(&mut self, dto: &UserDto) -> DySqlResult<u64>;
+
+    #[sql("select * from test_user where 1 = 1 {{#data}}{{#age}}and age > :data.age{{/age}}{{/data}}")]
+    async fn page_users(&mut self, pg_dto: PageDto<UserDto>) -> DySqlResult<Pagination<User>>;
+}
+
+#[tokio::test]
+async fn test_mapper() {
+    let mut repo = UserRepo { conn: connect_db().await };
+
+    let dto = UserDto{ id: None, name: None, age: Some(13) , id_rng: None };
+    let rst = repo.find_users(&dto).await.unwrap();
+    assert_eq!(7, rst.len());
+
+    let rst = repo.find_by_id(Value::new(2)).await.unwrap();
+    assert_eq!(User { id: 2, name: Some("zhanglan".to_owned()), age: Some(21) }, rst);
+    assert_eq!(None, repo.find_optional_by_id(Value::new(100)).await.unwrap());
+
+    let dto = UserDto{ id: None, name: Some("lisi".to_owned()), age: Some(50), id_rng: None };
+    let insert_id = repo.add(&dto).await.unwrap();
+    assert_eq!(10, insert_id);
+    assert_eq!(10, repo.count().await.unwrap());
+
+    let dto = UserDto{ id: Some(insert_id), name: None, age: None, id_rng: None };
+    assert_eq!(1, repo.remove(&dto).await.unwrap());
+    assert_eq!(9, repo.count().await.unwrap());
+
+    let dto = UserDto{ id: None, name: None, age: Some(13), id_rng: None };
+    let pg_dto = PageDto::new(3, 0, Some(dto));
+    let rst = repo.page_users(pg_dto).await.unwrap();
+    assert_eq!(7, rst.total);
+}
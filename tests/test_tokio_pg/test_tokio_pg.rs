@@ -2,7 +2,7 @@ mod common;
 
 use std::error::Error;
 
-use dysql::{PageDto, SortModel, sql, fetch_one, insert, fetch_scalar, execute, page, fetch_all, Value};
+use dysql::{PageDto, SortModel, sql, fetch_one, fetch_optional, insert, fetch_scalar, execute, page, fetch_all, Value};
 
 use crate::common::{UserDto, User};
 
@@ -38,6 +38,30 @@ async fn test_fetch_all() {
     assert_eq!(9, rst.len());
 }
 
+#[tokio::test]
+async fn test_any_array() {
+    let conn = connect_postgres_db().await;
+
+    let dto = Value::new(vec![2_i64, 3, 5]);
+    let rst = fetch_all!(|&conn, &dto| -> User {
+        "select * from test_user where id = any(:value) order by id"
+    }).unwrap();
+    assert_eq!(vec![2, 3, 5], rst.iter().map(|u| u.id).collect::<Vec<_>>());
+
+    let dto = Value::new(vec!["a4".to_owned(), "a5".to_owned()]);
+    let rst = fetch_all!(|&conn, &dto| -> User {
+        "select * from test_user where name = any(:value) order by id"
+    }).unwrap();
+    assert_eq!(vec![4, 5], rst.iter().map(|u| u.id).collect::<Vec<_>>());
+
+    // 空数组的元素类型由数据库推断
+    let dto = Value::new(Vec::<i64>::new());
+    let rst = fetch_all!(|&conn, &dto| -> User {
+        "select * from test_user where id = any(:value)"
+    }).unwrap();
+    assert!(rst.is_empty());
+}
+
 sql!("select_sql","select * from test_user ");
 #[tokio::test]
 async fn test_fetch_one() {
@@ -56,6 +80,38 @@ async fn test_fetch_one() {
     assert_eq!(User { id: 2, name: Some("zhanglan".to_owned()), age: Some(21) }, rst);
 }
 
+#[tokio::test]
+async fn test_fetch_optional() {
+    let conn = connect_postgres_db().await;
+
+    // 多于一行时取第一行
+    let rst = fetch_optional!(|&conn| -> User {
+        select_sql + "order by id"
+    }).unwrap();
+    assert_eq!(Some(User { id: 1, name: Some("huanglan".to_owned()), age: Some(10) }), rst);
+
+    let dto = dysql::Value::new(-1_i64);
+    let rst = fetch_optional!(|&conn, dto| -> User {
+        select_sql + "where id = :value"
+    }).unwrap();
+    assert_eq!(None, rst);
+}
+
+#[tokio::test]
+async fn test_tuple_ret_type() {
+    let conn = connect_postgres_db().await;
+
+    let rst = fetch_one!(|&conn| -> (i64, Option<String>) {
+        r#"select id, name from test_user where id = 2"#
+    }).unwrap();
+    assert_eq!((2, Some("zhanglan".to_owned())), rst);
+
+    let rst = fetch_all!(|&conn| -> (i64,) {
+        r#"select id from test_user where id < 3 order by id"#
+    }).unwrap();
+    assert_eq!(vec![(1,), (2,)], rst);
+}
+
 #[tokio::test]
 async fn test_fetch_scalar() -> dysql::DySqlResult<()>{
     let conn = connect_postgres_db().await;
@@ -577,6 +577,36 @@ fn can_render_self_referencing_structures() {
     assert_eq!(rendered, "Hello: FooBar");
 }
 
+#[test]
+fn can_render_self_referencing_structures_through_pointers() {
+    #[derive(Content)]
+    struct Tree {
+        name: String,
+        child: Option<Box<Self>>,
+    }
+
+    #[derive(Content)]
+    struct Node<'a> {
+        name: &'a str,
+        parent: Option<&'a Node<'a>>,
+    }
+
+    let tpl = Template::new("{{name}}{{#child}}/{{name}}{{#child}}/{{name}}{{/child}}{{/child}}").unwrap();
+    let tree = Tree {
+        name: "a".to_owned(),
+        child: Some(Box::new(Tree { name: "b".to_owned(), child: Some(Box::new(Tree { name: "c".to_owned(), child: None })) })),
+    };
+    assert_eq!(tpl.render(&tree), "a/b/c");
+    assert!(<Tree as Content>::FIELDS.contains_path("child"));
+    assert!(!<Tree as Content>::FIELDS.contains_path("chlid"));
+
+    let root = Node { name: "root", parent: None };
+    let leaf = Node { name: "leaf", parent: Some(&root) };
+    let tpl = Template::new("{{name}}{{#parent}}<{{name}}{{/parent}}").unwrap();
+    assert_eq!(tpl.render(&leaf), "leaf<root");
+    assert!(<Node as Content>::FIELDS.contains_path("parent.name"));
+}
+
 #[test]
 fn can_render_fields_from_parents() {
     #[derive(Content)]
@@ -715,6 +745,218 @@ fn derive_flatten() {
     );
 }
 
+#[test]
+fn can_render_trim_elements() {
+    #[derive(Content)]
+    struct UserDto {
+        id: Option<i64>,
+        name: Option<String>,
+        age: Option<i32>,
+    }
+
+    let tpl = Template::new(
+        "select * from test_user {{#where}} {{#name}}and name = :name{{/name}} {{#age}}or age > :age{{/age}} {{/where}} order by id"
+    ).unwrap();
+    let sql = tpl.render_sql(&UserDto { id: None, name: None, age: Some(13) });
+    assert_eq!(sql, "select * from test_user WHERE age > :age order by id");
+    let sql = tpl.render_sql(&UserDto { id: None, name: None, age: None });
+    assert_eq!(sql, "select * from test_user order by id");
+
+    let tpl = Template::new(
+        "update test_user {{#set}} {{#name}}name = :name,{{/name}} {{#age}}age = :age,{{/age}} {{/set}} where id = :id"
+    ).unwrap();
+    let sql = tpl.render_sql(&UserDto { id: Some(1), name: Some("a".to_owned()), age: Some(13) });
+    assert_eq!(sql, "update test_user SET name = :name, age = :age where id = :id");
+
+    let tpl = Template::new(
+        "select * from test_user where id > 0 {{#trim prefix=and(( suffix=)) prefix_overrides=AND|OR}} {{#name}}or name = :name{{/name}} {{/trim}}"
+    ).unwrap();
+    let sql = tpl.render_sql(&UserDto { id: None, name: Some("a".to_owned()), age: None });
+    assert_eq!(sql, "select * from test_user where id > 0 and(( name = :name ))");
+}
+
+#[test]
+fn can_render_if_expressions() {
+    #[derive(Content)]
+    struct Filter {
+        age: Option<i32>,
+        score: f64,
+    }
+
+    #[derive(Content)]
+    struct UserDto {
+        status: Option<String>,
+        ids: Vec<i64>,
+        filter: Filter,
+    }
+
+    let tpl = Template::new(
+        "select * from test_user {{#where}} \
+        {{#if filter.age > 0 && filter.age <= 60}}and age = :filter.age{{/if}} \
+        {{#if status == 'active' || status == \"pending\"}}and status = :status{{/if}} \
+        {{#if !(len(ids) > 0)}}and 1 = 0{{/if}} \
+        {{#if filter.score >= 1.5 && status != null}}and score >= :filter.score{{/if}} \
+        {{/where}}"
+    ).unwrap();
+
+    let mut dto = UserDto {
+        status: Some("pending".to_owned()),
+        ids: vec![1],
+        filter: Filter { age: Some(13), score: 2.0 },
+    };
+    let sql = tpl.render_sql(&dto);
+    assert_eq!(sql, "select * from test_user WHERE age = :filter.age and status = :status and score >= :filter.score");
+
+    dto.status = None;
+    dto.ids.clear();
+    dto.filter.age = Some(0);
+    let sql = tpl.render_sql(&dto);
+    assert_eq!(sql, "select * from test_user WHERE 1 = 0");
+
+    // 嵌套在 section 中时先在 section 的 Content 中查找字段
+    let tpl = Template::new("{{#filter}}{{#if age == 13 && len(ids) == 0}}age = :age{{/if}}{{/filter}}").unwrap();
+    dto.filter.age = Some(13);
+    assert_eq!(tpl.render_sql(&dto), "age = :age");
+
+    match Template::new("select * from test_user {{#if age = 1}}and age = 1{{/if}}") {
+        Err(dysql::TemplateError::IllegalExpression(expr)) => assert_eq!(expr, "age = 1"),
+        _ => panic!("illegal expression should fail"),
+    }
+}
+
+#[test]
+fn can_render_sql_keeping_literals() {
+    #[derive(Content)]
+    struct UserDto {
+        name: Option<String>,
+        ids: Vec<i64>,
+    }
+
+    let tpl = Template::new(
+        "select  \"user  name\"  from test_user -- 注释  :name\n  where name =  'a  b'\n   and remark = 'it''s  \n  ok' \
+        {{#name}} and   name like '%{{name}}  %' /* a  b */{{/name}}  and id in (  ![DEL(,)] {{#ids}} , {{$value}} {{/ids}} )"
+    ).unwrap();
+    let dto = UserDto { name: Some("x".to_owned()), ids: vec![1, 2] };
+    assert_eq!(
+        tpl.render_sql(&dto),
+        "select \"user  name\" from test_user -- 注释  :name\nwhere name = 'a  b' and remark = 'it''s  \n  ok' \
+        and name like '%x  %' /* a  b */ and id in ( 1 , 2 )"
+    );
+
+    let tpl = Template::new("select * from test_user -- {{#name}}:name{{/name}}").unwrap();
+    assert_eq!(tpl.render_sql(&dto), "select * from test_user -- :name\n");
+}
+
+#[test]
+fn can_render_sql_params() {
+    use dysql::{SqlParams, SqlSyntax, SimpleError};
+
+    #[derive(Content)]
+    struct UserDto {
+        name: Option<String>,
+        age: i32,
+    }
+
+    struct Names(Vec<String>);
+
+    impl SqlParams for Names {
+        fn syntax(&self) -> SqlSyntax {
+            SqlSyntax::Postgres
+        }
+
+        fn write_param(&mut self, sql: &mut String, name: &str) -> Result<(), SimpleError> {
+            self.0.push(name.to_owned());
+            sql.push_str(&format!("${}", self.0.len()));
+            Ok(())
+        }
+    }
+
+    let tpl = Template::new(
+        "select created_at::date, $tag$ :a $tag$, E'\\' :b', arr[1\\:2] from test_user \
+        where {{#name}}name = :name and{{/name}} age > :age /* :c /* :d */ :e */"
+    ).unwrap();
+    let mut names = Names(Vec::new());
+    let mut buf = String::new();
+    tpl.render_sql_params(&UserDto { name: Some("x".to_owned()), age: 1 }, &mut names, &mut buf).unwrap();
+    assert_eq!(
+        buf,
+        "select created_at::date, $tag$ :a $tag$, E'\\' :b', arr[1:2] from test_user where name = $1 and age > $2 /* :c /* :d */ :e */"
+    );
+    assert_eq!(names.0, vec!["name", "age"]);
+
+    // 复用 buf，渲染前会清空原有的内容
+    let mut names = Names(Vec::new());
+    tpl.render_sql_params(&UserDto { name: None, age: 1 }, &mut names, &mut buf).unwrap();
+    assert!(buf.ends_with("from test_user where age > $1 /* :c /* :d */ :e */"));
+    assert_eq!(names.0, vec!["age"]);
+
+    let tpl = Template::new("select * from test_user where age > : age").unwrap();
+    assert!(tpl.render_sql_params(&UserDto { name: None, age: 1 }, &mut names, &mut buf).is_err());
+}
+
+#[test]
+fn can_compute_sql_shape() {
+    use dysql::{SqlShape, SqlParams, SqlSyntax, SimpleError};
+
+    #[derive(Content)]
+    struct UserDto {
+        name: Option<String>,
+        age: Option<i32>,
+        ids: Vec<i32>,
+    }
+
+    let dto = |name: Option<&str>, age: Option<i32>, ids: Vec<i32>| UserDto { name: name.map(str::to_owned), age, ids };
+    let shape = |tpl: &Template, dto: &UserDto| {
+        let mut shape = SqlShape::default();
+        tpl.sql_shape(dto, &mut shape);
+        shape
+    };
+
+    let tpl = Template::new(
+        "select * from test_user {{#where}} {{#name}}and name = :name{{/name}} {{#if age > 10}}and age > :age{{/if}} {{/where}}"
+    ).unwrap();
+    let a = shape(&tpl, &dto(Some("a"), Some(20), vec![]));
+    assert!(a.is_cacheable());
+    assert_eq!(a, shape(&tpl, &dto(Some("b"), Some(30), vec![1])));
+    assert_ne!(a, shape(&tpl, &dto(None, Some(30), vec![])));
+    assert_ne!(a, shape(&tpl, &dto(Some("a"), Some(5), vec![])));
+    assert_ne!(shape(&tpl, &dto(None, Some(20), vec![])), shape(&tpl, &dto(Some("a"), None, vec![])));
+
+    // 输出了变量或遍历列表时渲染结果不只由分支决定
+    let tpl = Template::new("select * from test_user order by {{name}}").unwrap();
+    assert!(!shape(&tpl, &dto(Some("id"), None, vec![])).is_cacheable());
+
+    let tpl = Template::new("select * from test_user where 1 = 1 {{#ids}} and id <> :ids{{/ids}}").unwrap();
+    assert!(shape(&tpl, &dto(None, None, vec![1])).is_cacheable());
+    assert!(!shape(&tpl, &dto(None, None, vec![1, 2])).is_cacheable());
+
+    // 渲染的同时记录的分支与单独计算的相同
+    struct Names(Vec<String>);
+
+    impl SqlParams for Names {
+        fn syntax(&self) -> SqlSyntax {
+            SqlSyntax::Standard
+        }
+
+        fn write_param(&mut self, sql: &mut String, name: &str) -> Result<(), SimpleError> {
+            sql.push('?');
+            self.0.push(name.to_owned());
+            Ok(())
+        }
+    }
+
+    let tpl = Template::new(
+        "select * from test_user {{#where}} {{#name}}and name = :name{{/name}} {{#ids}}or id = :ids{{/ids}} {{/where}} order by {{age}}"
+    ).unwrap();
+    let mut buf = String::new();
+    let mut rendered_shape = SqlShape::default();
+    for dto in [dto(Some("a"), Some(1), vec![]), dto(None, None, vec![1]), dto(None, Some(1), vec![1, 2])] {
+        let mut names = Names(Vec::new());
+        tpl.render_sql_params_shape(&dto, &mut names, &mut buf, &mut rendered_shape).unwrap();
+        assert_eq!(shape(&tpl, &dto), rendered_shape);
+    }
+}
+
 // #[test]
 // fn simple_partials() {
 //     let mut tpls: Ramhorns = Ramhorns::lazy("./tests/templates").unwrap();